@@ -0,0 +1,86 @@
+//! All static checks of a program together.
+//!
+//! The [interpreter](crate::interpreter) and the [vm](crate::vm) detect errors while a program runs,
+//! but the native backends translate a program ahead of time and need every check to pass first.
+
+use crate::ast::Program;
+use crate::flow::{check_returns, FlowError};
+use crate::init::{check_initialization, InitError};
+use crate::resolve::{resolve, ResolveError, Resolution};
+use crate::symbols::{check_functions, SymbolError};
+use crate::typeck::{check_types, TypeError, TypeErrorKind, TypeTable};
+use std::error::Error;
+use std::fmt;
+
+/// What the checks found out about a correct program
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    pub resolution: Resolution,
+    pub types: TypeTable,
+}
+
+/// An error of one of the checks
+#[derive(Debug, Clone, PartialEq)]
+pub enum Diagnostic {
+    Resolve(ResolveError),
+    Symbol(SymbolError),
+    Type(TypeError),
+    Flow(FlowError),
+    Init(InitError),
+}
+
+impl Diagnostic {
+    fn is_unknown_type(&self) -> bool {
+        matches!(self, Diagnostic::Type(TypeError { kind: TypeErrorKind::UnknownType, .. }))
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::Resolve(error) => error.fmt(f),
+            Diagnostic::Symbol(error) => error.fmt(f),
+            Diagnostic::Type(error) => error.fmt(f),
+            Diagnostic::Flow(error) => error.fmt(f),
+            Diagnostic::Init(error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for Diagnostic {}
+
+/// Run every check on the program.
+///
+/// The errors are grouped by check: names, functions, types, return paths and definite
+/// assignment. Warnings are not reported.
+/// ```
+/// use cb_3::C1Parser;
+/// use cb_3::analysis::analyze;
+///
+/// let program = C1Parser::parse("int main() { printf(x); }").unwrap();
+/// let errors = analyze(&program).unwrap_err();
+/// assert_eq!(errors[0].to_string(), "line 1, column 21: variable 'x' is not defined");
+/// assert_eq!(errors[1].to_string(), "line 1, column 5: function 'main' has to return a value of type 'int' on every path");
+/// ```
+pub fn analyze(program: &Program) -> Result<Analysis, Vec<Diagnostic>> {
+    let (resolution, errors) = resolve(program);
+    let mut diagnostics: Vec<Diagnostic> = errors.into_iter().map(Diagnostic::Resolve).collect();
+    diagnostics.extend(check_functions(program).into_iter().map(Diagnostic::Symbol));
+    let types = match check_types(program) {
+        Ok(types) => Some(types),
+        Err(errors) => {
+            diagnostics.extend(errors.into_iter().map(Diagnostic::Type));
+            None
+        }
+    };
+    diagnostics.extend(check_returns(program).into_iter().filter(|error| !error.is_warning()).map(Diagnostic::Flow));
+    diagnostics.extend(check_initialization(program).into_iter().map(Diagnostic::Init));
+    // an expression of unknown type is caused by an error of the other checks, which describes it
+    if diagnostics.iter().any(|diagnostic| !diagnostic.is_unknown_type()) {
+        diagnostics.retain(|diagnostic| !diagnostic.is_unknown_type());
+    }
+    match types {
+        Some(types) if diagnostics.is_empty() => Ok(Analysis { resolution, types }),
+        _ => Err(diagnostics),
+    }
+}
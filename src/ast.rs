@@ -0,0 +1,232 @@
+//! Abstract syntax tree produced by the [C1Parser](crate::C1Parser).
+//!
+//! The node types follow the grammar in `c-1-syntax.ebnf` closely. Every node that can be the
+//! subject of a diagnostic remembers its [Span], which covers all of its tokens and starts at the
+//! line and column of the first one.
+
+use crate::Span;
+use std::fmt;
+
+/// program ::= ( declassignment ";" | functiondefinition )* <EOF>
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    /// Global variables in the order of their declassignments
+    pub globals: Vec<Declaration>,
+    pub functions: Vec<FunctionDefinition>,
+}
+
+/// functiondefinition ::= type <ID> "(" ( parameterlist )? ")" "{" statementlist "}"
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionDefinition {
+    pub return_type: Type,
+    pub name: Identifier,
+    pub parameters: Vec<Parameter>,
+    pub body: Block,
+    pub span: Span,
+}
+
+/// type <ID>, one entry of a parameterlist
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub ty: Type,
+    pub name: Identifier,
+    pub span: Span,
+}
+
+/// type ::= <KW_BOOLEAN> | <KW_FLOAT> | <KW_INT> | <KW_VOID>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Type {
+    Boolean,
+    Float,
+    Int,
+    Void,
+}
+
+/// An occurrence of an <ID> token
+#[derive(Debug, Clone, PartialEq)]
+pub struct Identifier {
+    pub name: String,
+    pub span: Span,
+}
+
+/// declassignment ::= type <ID> ( "=" assignment )?
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration {
+    pub ty: Type,
+    pub name: Identifier,
+    pub value: Option<Expr>,
+    pub span: Span,
+}
+
+/// "{" statementlist "}"
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub statements: Vec<Statement>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatementKind {
+    /// "{" statementlist "}" in statement position
+    Block(Block),
+    /// declassignment
+    Declaration(Declaration),
+    /// <KW_IF> "(" assignment ")" block ( <KW_ELSE> block )?
+    If {
+        condition: Expr,
+        then_branch: Box<Statement>,
+        else_branch: Option<Box<Statement>>,
+    },
+    /// <KW_FOR> "(" statassignment ";" assignment ";" statassignment ")" block
+    For {
+        init: Box<Assignment>,
+        condition: Expr,
+        step: Box<Assignment>,
+        body: Box<Statement>,
+    },
+    /// <KW_WHILE> "(" assignment ")" block
+    While {
+        condition: Expr,
+        body: Box<Statement>,
+    },
+    /// <KW_DO> block <KW_WHILE> "(" assignment ")"
+    DoWhile {
+        body: Box<Statement>,
+        condition: Expr,
+    },
+    /// <KW_RETURN> ( assignment )?
+    Return(Option<Expr>),
+    /// <KW_PRINTF> "(" assignment ")"
+    Printf(Expr),
+    /// statassignment
+    Assignment(Assignment),
+    /// functioncall
+    Call(FunctionCall),
+}
+
+/// <ID> "=" assignment
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assignment {
+    pub target: Identifier,
+    pub value: Expr,
+}
+
+/// functioncall ::= <ID> "(" ( assignment ( "," assignment )* )? ")"
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionCall {
+    pub name: Identifier,
+    pub arguments: Vec<Expr>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind {
+    /// <CONST_INT>
+    Int(i32),
+    /// <CONST_FLOAT>
+    Float(f64),
+    /// <CONST_BOOLEAN>
+    Bool(bool),
+    /// <ID>
+    Variable(Identifier),
+    /// functioncall
+    Call(FunctionCall),
+    /// <ID> "=" assignment, evaluates to the assigned value
+    Assign(Box<Assignment>),
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expr>,
+    },
+    Binary {
+        op: BinaryOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnaryOp {
+    /// "-"
+    Neg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryOp {
+    /// "+"
+    Add,
+    /// "-"
+    Sub,
+    /// "||"
+    Or,
+    /// "*"
+    Mul,
+    /// "/"
+    Div,
+    /// "&&"
+    And,
+    /// "=="
+    Equal,
+    /// "!="
+    NotEqual,
+    /// "<"
+    Less,
+    /// "<="
+    LessEqual,
+    /// ">"
+    Greater,
+    /// ">="
+    GreaterEqual,
+}
+
+/// Types are shown as their keyword
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Type::Boolean => "bool",
+            Type::Float => "float",
+            Type::Int => "int",
+            Type::Void => "void",
+        })
+    }
+}
+
+/// Operators are shown as they appear in the source text
+impl fmt::Display for UnaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnaryOp::Neg => f.write_str("-"),
+        }
+    }
+}
+
+/// Operators are shown as they appear in the source text
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Or => "||",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::And => "&&",
+            BinaryOp::Equal => "==",
+            BinaryOp::NotEqual => "!=",
+            BinaryOp::Less => "<",
+            BinaryOp::LessEqual => "<=",
+            BinaryOp::Greater => ">",
+            BinaryOp::GreaterEqual => ">=",
+        })
+    }
+}
@@ -0,0 +1,507 @@
+//! Compiler from parsed C(-1) programs to bytecode for the [vm](crate::vm).
+//!
+//! The bytecode is a stack machine code. Every function has its own list of instructions, jump
+//! targets are indices into that list. Variables are stored in the slots assigned by
+//! [resolve](crate::resolve::resolve): local variables and parameters in the slots of the current
+//! call, global variables in the global slots of the module.
+//!
+//! Every instruction remembers the location of the source code it was compiled from, so runtime
+//! errors are reported at the same location as by the [interpreter](crate::interpreter). Names that
+//! cannot be resolved are compiled to instructions that fail, like the interpreter fails when it
+//! reaches them.
+
+use crate::ast::{Assignment, BinaryOp, Block, Expr, ExprKind, FunctionCall, FunctionDefinition, Identifier, Program, Statement, StatementKind, Type, UnaryOp};
+use crate::interpreter::Value;
+use crate::resolve::{resolve, BindingKind, Resolution};
+use crate::Span;
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instruction {
+    /// Push the constant with the given index
+    Const(u32),
+    /// Push the result of a void function
+    Void,
+    /// Push the value of a local variable, which has to be assigned
+    Load(u32),
+    /// Pop a value and store it in a local variable
+    Store(u32),
+    /// Make a local variable unassigned
+    Clear(u32),
+    /// Push the value of a global variable, which has to be assigned
+    LoadGlobal(u32),
+    /// Pop a value and store it in a global variable
+    StoreGlobal(u32),
+    /// Convert the value on top of the stack to the given type
+    Convert(Type),
+    /// Push a copy of the value on top of the stack
+    Dup,
+    /// Remove the value on top of the stack
+    Pop,
+    /// Negate the number on top of the stack
+    Neg,
+    /// Pop the right and the left operand and push the result of the operator. For `&&` and `||`
+    /// both operands have to be `bool` and the result is the right one, see [ShortCircuit]
+    ///
+    /// [ShortCircuit]: Instruction::ShortCircuit
+    Binary(BinaryOp),
+    /// Continue at the given instruction
+    Jump(u32),
+    /// Pop a `bool` and continue at the given instruction if it is `false`
+    JumpIfFalse(u32),
+    /// Continue at the given instruction if the left operand of `&&` or `||` on top of the stack
+    /// decides the result, which stays on the stack. Otherwise the right operand is evaluated next
+    ShortCircuit(BinaryOp, u32),
+    /// Fail if the function with the given index cannot be called with the given number of
+    /// arguments, or if the maximum call depth is reached. The arguments stay on the stack
+    CheckCall { function: u32, arguments: u32 },
+    /// Convert the argument with the given number of arguments above it on the stack to the given
+    /// type
+    ConvertArgument(u32, Type),
+    /// Pop the arguments and call the function with the given index. The checks of [CheckCall]
+    /// are repeated, so a call is safe without them
+    ///
+    /// [CheckCall]: Instruction::CheckCall
+    Call { function: u32, arguments: u32 },
+    /// Pop the return value and return from the current function
+    Ret,
+    /// The end of a function. Returns from a void function, every other function has to return
+    /// before
+    End,
+    /// Pop a value and print it
+    Print,
+    /// Fail because the variable with the name at the given index of the module's names is not
+    /// defined
+    UndefinedVariable(u32),
+    /// Fail because the function with the name at the given index of the module's names is not
+    /// defined
+    UndefinedFunction(u32),
+}
+
+/// Instructions are shown as their mnemonic followed by their operands
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Const(index) => write!(f, "const {}", index),
+            Instruction::Void => f.write_str("void"),
+            Instruction::Load(slot) => write!(f, "load {}", slot),
+            Instruction::Store(slot) => write!(f, "store {}", slot),
+            Instruction::Clear(slot) => write!(f, "clear {}", slot),
+            Instruction::LoadGlobal(slot) => write!(f, "load_global {}", slot),
+            Instruction::StoreGlobal(slot) => write!(f, "store_global {}", slot),
+            Instruction::Convert(ty) => write!(f, "convert {}", ty),
+            Instruction::Dup => f.write_str("dup"),
+            Instruction::Pop => f.write_str("pop"),
+            Instruction::Neg => f.write_str("neg"),
+            Instruction::Binary(op) => write!(f, "binary {}", op),
+            Instruction::Jump(target) => write!(f, "jump {}", target),
+            Instruction::JumpIfFalse(target) => write!(f, "jump_if_false {}", target),
+            Instruction::ShortCircuit(op, target) => write!(f, "short_circuit {} {}", op, target),
+            Instruction::CheckCall { function, arguments } => write!(f, "check_call {} {}", function, arguments),
+            Instruction::ConvertArgument(above, ty) => write!(f, "convert_argument {} {}", above, ty),
+            Instruction::Call { function, arguments } => write!(f, "call {} {}", function, arguments),
+            Instruction::Ret => f.write_str("ret"),
+            Instruction::End => f.write_str("end"),
+            Instruction::Print => f.write_str("print"),
+            Instruction::UndefinedVariable(index) => write!(f, "undefined_variable {}", index),
+            Instruction::UndefinedFunction(index) => write!(f, "undefined_function {}", index),
+        }
+    }
+}
+
+/// The location of the instructions from `offset` up to the offset of the next entry
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineEntry {
+    pub offset: u32,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    /// Location of the name in the definition, where an invalid call of `main` is reported
+    pub span: Span,
+    pub parameters: Vec<Type>,
+    pub return_type: Type,
+    /// Names of the local variables, by their slot
+    pub locals: Vec<String>,
+    pub code: Vec<Instruction>,
+    /// Location of the instructions, ordered by offset
+    pub lines: Vec<LineEntry>,
+}
+
+impl Function {
+    /// Return the location of the source code the instruction at the offset was compiled from
+    pub fn span_at(&self, offset: usize) -> Span {
+        let index = self.lines.partition_point(|entry| entry.offset as usize <= offset);
+        self.lines[index.saturating_sub(1)].span
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Global {
+    pub name: String,
+    pub ty: Type,
+}
+
+/// A compiled program
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module {
+    pub constants: Vec<Value>,
+    /// Names of the undefined variables and functions the code refers to
+    pub names: Vec<String>,
+    /// Global variables, by their slot
+    pub globals: Vec<Global>,
+    /// Initializes the global variables in the order of their declassignments
+    pub init: Function,
+    /// Functions in the order of their definitions
+    pub functions: Vec<Function>,
+}
+
+impl Module {
+    /// Return the index of the `main` function
+    pub fn main(&self) -> Option<usize> {
+        self.functions.iter().position(|function| function.name == "main")
+    }
+}
+
+/// Compile a parsed program.
+///
+/// Every program can be compiled. Type errors and names that cannot be resolved are detected when
+/// the bytecode is executed.
+/// ```
+/// use cb_3::C1Parser;
+/// use cb_3::bytecode::{compile, Instruction};
+///
+/// let program = C1Parser::parse("void main() { printf(1); }").unwrap();
+/// let module = compile(&program);
+/// assert_eq!(module.functions[0].code, [Instruction::Const(0), Instruction::Print, Instruction::End]);
+/// ```
+pub fn compile(program: &Program) -> Module {
+    let mut functions = HashMap::new();
+    for (index, function) in program.functions.iter().enumerate() {
+        functions.entry(function.name.name.as_str()).or_insert(index as u32);
+    }
+    let mut compiler = Compiler {
+        program,
+        functions,
+        resolution: resolve(program).0,
+        constants: Vec::new(),
+        names: Vec::new(),
+        code: Vec::new(),
+        lines: Vec::new(),
+        locals: Vec::new(),
+    };
+    let mut globals = Vec::new();
+    for declaration in &program.globals {
+        match &declaration.value {
+            Some(value) => {
+                compiler.expr(value);
+                compiler.emit(Instruction::Convert(declaration.ty), value.span);
+            }
+            None => {
+                let zero = compiler.constant(Value::zero(declaration.ty));
+                compiler.emit(Instruction::Const(zero), declaration.span);
+            }
+        }
+        compiler.store(&declaration.name, declaration.span);
+        globals.push(Global { name: declaration.name.name.clone(), ty: declaration.ty });
+    }
+    let end = program.globals.last().map(|declaration| declaration.span).unwrap_or_default();
+    compiler.emit(Instruction::End, end);
+    let init = compiler.finish(String::new(), Span::default(), Vec::new(), Type::Void);
+    let functions = program.functions.iter().map(|function| compiler.function(function)).collect();
+    Module { constants: compiler.constants, names: compiler.names, globals, init, functions }
+}
+
+struct Compiler<'p> {
+    program: &'p Program,
+    /// Index of the first function with each name
+    functions: HashMap<&'p str, u32>,
+    resolution: Resolution,
+    constants: Vec<Value>,
+    names: Vec<String>,
+    /// Code, line table and local variable names of the function that is compiled
+    code: Vec<Instruction>,
+    lines: Vec<LineEntry>,
+    locals: Vec<String>,
+}
+
+impl Compiler<'_> {
+    /// Return the index of the constant, which is added to the constant pool if necessary
+    fn constant(&mut self, value: Value) -> u32 {
+        let same = |constant: &Value| match (constant, value) {
+            (Value::Float(constant), Value::Float(value)) => constant.to_bits() == value.to_bits(),
+            (constant, value) => *constant == value,
+        };
+        match self.constants.iter().position(same) {
+            Some(index) => index as u32,
+            None => {
+                self.constants.push(value);
+                self.constants.len() as u32 - 1
+            }
+        }
+    }
+
+    /// Return the index of the name of an undefined variable or function, which is added to the
+    /// names if necessary
+    fn name(&mut self, name: &str) -> u32 {
+        match self.names.iter().position(|other| other == name) {
+            Some(index) => index as u32,
+            None => {
+                self.names.push(name.to_string());
+                self.names.len() as u32 - 1
+            }
+        }
+    }
+
+    /// Append an instruction and return its offset
+    fn emit(&mut self, instruction: Instruction, span: Span) -> u32 {
+        let offset = self.code.len() as u32;
+        if self.lines.last().map(|entry| entry.span) != Some(span) {
+            self.lines.push(LineEntry { offset, span });
+        }
+        self.code.push(instruction);
+        offset
+    }
+
+    /// Return the offset of the next instruction
+    fn here(&self) -> u32 {
+        self.code.len() as u32
+    }
+
+    /// Set the target of the jump at the offset
+    fn patch(&mut self, offset: u32, target: u32) {
+        match &mut self.code[offset as usize] {
+            Instruction::Jump(old) | Instruction::JumpIfFalse(old) | Instruction::ShortCircuit(_, old) => *old = target,
+            _ => unreachable!("only jumps are patched"),
+        }
+    }
+
+    fn finish(&mut self, name: String, span: Span, parameters: Vec<Type>, return_type: Type) -> Function {
+        Function {
+            name,
+            span,
+            parameters,
+            return_type,
+            locals: std::mem::take(&mut self.locals),
+            code: std::mem::take(&mut self.code),
+            lines: std::mem::take(&mut self.lines),
+        }
+    }
+
+    fn function(&mut self, function: &FunctionDefinition) -> Function {
+        self.locals = vec![String::new(); self.resolution.frame_size(function)];
+        for (slot, parameter) in function.parameters.iter().enumerate() {
+            self.locals[slot] = parameter.name.name.clone();
+        }
+        self.block(&function.body);
+        self.emit(Instruction::End, function.span);
+        let parameters = function.parameters.iter().map(|parameter| parameter.ty).collect();
+        self.finish(function.name.name.clone(), function.name.span, parameters, function.return_type)
+    }
+
+    /// Return the instruction that loads or stores the variable the identifier refers to, and the
+    /// declared type of the variable. If it cannot be resolved, an instruction that fails is emitted
+    /// instead.
+    fn variable(&mut self, identifier: &Identifier, store: bool) -> Option<(Instruction, Option<Type>)> {
+        let id = match self.resolution.lookup(identifier) {
+            Some(id) => id,
+            None => {
+                let name = self.name(&identifier.name);
+                self.emit(Instruction::UndefinedVariable(name), identifier.span);
+                return None;
+            }
+        };
+        let binding = self.resolution.binding(id);
+        let slot = binding.slot as u32;
+        let instruction = match (binding.kind, store) {
+            (BindingKind::Global, false) => Instruction::LoadGlobal(slot),
+            (BindingKind::Global, true) => Instruction::StoreGlobal(slot),
+            (_, false) => Instruction::Load(slot),
+            (_, true) => Instruction::Store(slot),
+        };
+        let ty = binding.ty;
+        if binding.kind != BindingKind::Global {
+            self.locals[binding.slot] = identifier.name.clone();
+        }
+        Some((instruction, ty))
+    }
+
+    /// Store the value on top of the stack in the variable
+    fn store(&mut self, identifier: &Identifier, span: Span) {
+        if let Some((instruction, _)) = self.variable(identifier, true) {
+            self.emit(instruction, span);
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        for statement in &block.statements {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        let span = statement.span;
+        match &statement.kind {
+            StatementKind::Block(block) => self.block(block),
+            StatementKind::Declaration(declaration) => match &declaration.value {
+                Some(value) => {
+                    self.expr(value);
+                    self.emit(Instruction::Convert(declaration.ty), value.span);
+                    self.store(&declaration.name, span);
+                }
+                None => {
+                    if let Some((Instruction::Store(slot), _)) = self.variable(&declaration.name, true) {
+                        self.emit(Instruction::Clear(slot), span);
+                    }
+                }
+            },
+            StatementKind::If { condition, then_branch, else_branch } => {
+                self.expr(condition);
+                let to_else = self.emit(Instruction::JumpIfFalse(0), condition.span);
+                self.statement(then_branch);
+                match else_branch {
+                    Some(else_branch) => {
+                        let to_end = self.emit(Instruction::Jump(0), span);
+                        let else_start = self.here();
+                        self.patch(to_else, else_start);
+                        self.statement(else_branch);
+                        let end = self.here();
+                        self.patch(to_end, end);
+                    }
+                    None => {
+                        let end = self.here();
+                        self.patch(to_else, end);
+                    }
+                }
+            }
+            StatementKind::For { init, condition, step, body } => {
+                self.assignment(init, false);
+                let start = self.here();
+                self.expr(condition);
+                let to_end = self.emit(Instruction::JumpIfFalse(0), condition.span);
+                self.statement(body);
+                self.assignment(step, false);
+                self.emit(Instruction::Jump(start), span);
+                let end = self.here();
+                self.patch(to_end, end);
+            }
+            StatementKind::While { condition, body } => {
+                let start = self.here();
+                self.expr(condition);
+                let to_end = self.emit(Instruction::JumpIfFalse(0), condition.span);
+                self.statement(body);
+                self.emit(Instruction::Jump(start), span);
+                let end = self.here();
+                self.patch(to_end, end);
+            }
+            StatementKind::DoWhile { body, condition } => {
+                let start = self.here();
+                self.statement(body);
+                self.expr(condition);
+                let to_end = self.emit(Instruction::JumpIfFalse(0), condition.span);
+                self.emit(Instruction::Jump(start), span);
+                let end = self.here();
+                self.patch(to_end, end);
+            }
+            StatementKind::Return(Some(value)) => {
+                self.expr(value);
+                self.emit(Instruction::Ret, value.span);
+            }
+            StatementKind::Return(None) => {
+                self.emit(Instruction::Void, span);
+                self.emit(Instruction::Ret, span);
+            }
+            StatementKind::Printf(value) => {
+                self.expr(value);
+                self.emit(Instruction::Print, value.span);
+            }
+            StatementKind::Assignment(assignment) => self.assignment(assignment, false),
+            StatementKind::Call(call) => {
+                self.call(call);
+                self.emit(Instruction::Pop, span);
+            }
+        }
+    }
+
+    /// Compile an assignment, which leaves its value on the stack if it is part of an expression
+    fn assignment(&mut self, assignment: &Assignment, value_needed: bool) {
+        self.expr(&assignment.value);
+        let target = &assignment.target;
+        if let Some((instruction, ty)) = self.variable(target, true) {
+            if let Some(ty) = ty {
+                self.emit(Instruction::Convert(ty), assignment.value.span);
+            }
+            if value_needed {
+                self.emit(Instruction::Dup, target.span);
+            }
+            self.emit(instruction, target.span);
+        }
+    }
+
+    fn call(&mut self, call: &FunctionCall) {
+        let index = match self.functions.get(call.name.name.as_str()) {
+            Some(index) => *index,
+            None => {
+                // the interpreter fails before it evaluates the arguments
+                let name = self.name(&call.name.name);
+                self.emit(Instruction::UndefinedFunction(name), call.span);
+                return;
+            }
+        };
+        for argument in &call.arguments {
+            self.expr(argument);
+        }
+        // like the interpreter, the arguments are converted after the call is checked
+        let arguments = call.arguments.len() as u32;
+        self.emit(Instruction::CheckCall { function: index, arguments }, call.span);
+        let program = self.program;
+        let parameters = &program.functions[index as usize].parameters;
+        if parameters.len() == call.arguments.len() {
+            for (position, (parameter, argument)) in parameters.iter().zip(&call.arguments).enumerate() {
+                self.emit(Instruction::ConvertArgument(arguments - 1 - position as u32, parameter.ty), argument.span);
+            }
+        }
+        self.emit(Instruction::Call { function: index, arguments }, call.span);
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        let span = expr.span;
+        match &expr.kind {
+            ExprKind::Int(value) => self.push(Value::Int(*value), span),
+            ExprKind::Float(value) => self.push(Value::Float(*value), span),
+            ExprKind::Bool(value) => self.push(Value::Bool(*value), span),
+            ExprKind::Variable(identifier) => {
+                if let Some((instruction, _)) = self.variable(identifier, false) {
+                    self.emit(instruction, span);
+                }
+            }
+            ExprKind::Call(call) => self.call(call),
+            ExprKind::Assign(assignment) => self.assignment(assignment, true),
+            ExprKind::Unary { op: UnaryOp::Neg, operand } => {
+                self.expr(operand);
+                self.emit(Instruction::Neg, span);
+            }
+            ExprKind::Binary { op: op @ (BinaryOp::And | BinaryOp::Or), lhs, rhs } => {
+                self.expr(lhs);
+                let to_end = self.emit(Instruction::ShortCircuit(*op, 0), span);
+                self.expr(rhs);
+                self.emit(Instruction::Binary(*op), span);
+                let end = self.here();
+                self.patch(to_end, end);
+            }
+            ExprKind::Binary { op, lhs, rhs } => {
+                self.expr(lhs);
+                self.expr(rhs);
+                self.emit(Instruction::Binary(*op), span);
+            }
+        }
+    }
+
+    fn push(&mut self, value: Value, span: Span) {
+        let index = self.constant(value);
+        self.emit(Instruction::Const(index), span);
+    }
+}
@@ -0,0 +1,718 @@
+//! The `.c1b` file format for compiled programs.
+//!
+//! A `.c1b` file stores a bytecode [Module], so a program can be executed without its source text.
+//! All integers are little-endian, the line table keeps the full locations so runtime errors of a
+//! loaded program are reported like those of the compiled one:
+//! ```text
+//! file        ::= "C1B\0" version:u16 constants names globals function:initialization functions
+//! constants   ::= count:u32 constant*
+//! constant    ::= 0 value:i32 | 1 value:f64 | 2 value:u8 | 3
+//! names       ::= count:u32 name:string*
+//! globals     ::= count:u32 ( name:string type )*
+//! functions   ::= count:u32 function*
+//! function    ::= name:string span return:type count:u32 type* count:u32 local:string* code lines
+//! code        ::= count:u32 ( opcode:u8 operand* )*
+//! lines       ::= count:u32 ( offset:u32 span )*
+//! span        ::= start:u32 end:u32 line:u32 column:u32
+//! string      ::= length:u32 utf8:u8*
+//! type        ::= 0 (bool) | 1 (float) | 2 (int) | 3 (void)
+//! ```
+//!
+//! The loader rejects files that are truncated or malformed, and code that could leave the
+//! instructions of its function, refers to constants, names, variables or functions that do not exist, or
+//! does not keep the stack balanced. Programs that are only wrong when they are executed, like
+//! calls with the wrong number of arguments, are loaded.
+
+use crate::ast::{BinaryOp, Type};
+use crate::bytecode::{Function, Global, Instruction, LineEntry, Module};
+use crate::interpreter::Value;
+use crate::Span;
+use std::error::Error;
+use std::fmt;
+use std::fmt::Write as _;
+
+pub const MAGIC: &[u8; 4] = b"C1B\0";
+
+/// The version of the format that is written, and the only one that is loaded
+pub const VERSION: u16 = 1;
+
+const TYPES: [Type; 4] = [Type::Boolean, Type::Float, Type::Int, Type::Void];
+
+const OPERATORS: [BinaryOp; 12] = [
+    BinaryOp::Add,
+    BinaryOp::Sub,
+    BinaryOp::Or,
+    BinaryOp::Mul,
+    BinaryOp::Div,
+    BinaryOp::And,
+    BinaryOp::Equal,
+    BinaryOp::NotEqual,
+    BinaryOp::Less,
+    BinaryOp::LessEqual,
+    BinaryOp::Greater,
+    BinaryOp::GreaterEqual,
+];
+
+/// A file that cannot be loaded
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadError {
+    pub kind: LoadErrorKind,
+    /// Byte offset of the data that could not be read, or of the start of the invalid function
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadErrorKind {
+    /// The file ends in the middle of the data
+    Truncated,
+    /// The file does not start with [MAGIC]
+    BadMagic,
+    UnsupportedVersion(u16),
+    /// A tag byte that is not defined for the kind of data, like "opcode" or "type"
+    InvalidTag {
+        what: &'static str,
+        tag: u8,
+    },
+    InvalidString,
+    /// There is more data after the last function
+    TrailingBytes,
+    /// The global initialization has parameters, local variables or a return type
+    InvalidInitialization,
+    /// A function has more parameters than local variables
+    MissingParameterSlots(String),
+    /// The line table of a function does not start at the first instruction, is not ordered or
+    /// refers to instructions that do not exist
+    InvalidLineTable(String),
+    /// The instruction with the given offset of a function is invalid
+    InvalidCode {
+        function: String,
+        instruction: usize,
+        problem: CodeProblem,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CodeProblem {
+    ConstantOutOfRange(u32),
+    NameOutOfRange(u32),
+    LocalOutOfRange(u32),
+    GlobalOutOfRange(u32),
+    FunctionOutOfRange(u32),
+    JumpOutOfRange(u32),
+    /// `short_circuit` with an operator other than `&&` and `||`
+    NotShortCircuiting(BinaryOp),
+    /// Execution can continue behind the last instruction
+    MissingEnd,
+    /// The instruction needs more values than the stack holds
+    StackUnderflow,
+    /// The instruction is reached with different numbers of values on the stack
+    StackMismatch,
+    /// `ret` without exactly the return value on the stack, or `end` with values on the stack
+    UnbalancedReturn,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "byte {}: ", self.offset)?;
+        match &self.kind {
+            LoadErrorKind::Truncated => f.write_str("unexpected end of file"),
+            LoadErrorKind::BadMagic => f.write_str("not a .c1b file"),
+            LoadErrorKind::UnsupportedVersion(version) => write!(f, "unsupported version {}, expected {}", version, VERSION),
+            LoadErrorKind::InvalidTag { what, tag } => write!(f, "invalid {} {}", what, tag),
+            LoadErrorKind::InvalidString => f.write_str("string is not valid UTF-8"),
+            LoadErrorKind::TrailingBytes => f.write_str("unexpected data after the last function"),
+            LoadErrorKind::InvalidInitialization => f.write_str("the global initialization cannot have parameters, local variables or a return type"),
+            LoadErrorKind::MissingParameterSlots(function) => write!(f, "function '{}' has more parameters than local variables", function),
+            LoadErrorKind::InvalidLineTable(function) => write!(f, "invalid line table in function '{}'", function),
+            LoadErrorKind::InvalidCode { function, instruction, problem } => {
+                write!(f, "instruction {} of function '{}': ", instruction, function)?;
+                match problem {
+                    CodeProblem::ConstantOutOfRange(index) => write!(f, "constant {} does not exist", index),
+                    CodeProblem::NameOutOfRange(index) => write!(f, "name {} does not exist", index),
+                    CodeProblem::LocalOutOfRange(slot) => write!(f, "local variable {} does not exist", slot),
+                    CodeProblem::GlobalOutOfRange(slot) => write!(f, "global variable {} does not exist", slot),
+                    CodeProblem::FunctionOutOfRange(index) => write!(f, "function {} does not exist", index),
+                    CodeProblem::JumpOutOfRange(target) => write!(f, "jump target {} does not exist", target),
+                    CodeProblem::NotShortCircuiting(op) => write!(f, "operator '{}' does not short-circuit", op),
+                    CodeProblem::MissingEnd => f.write_str("execution continues behind the last instruction"),
+                    CodeProblem::StackUnderflow => f.write_str("not enough values on the stack"),
+                    CodeProblem::StackMismatch => f.write_str("reached with different stack heights"),
+                    CodeProblem::UnbalancedReturn => f.write_str("values are left on the stack"),
+                }
+            }
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+/// Serialize a compiled program.
+/// ```
+/// use cb_3::C1Parser;
+/// use cb_3::bytecode::compile;
+/// use cb_3::c1b::{load, write};
+///
+/// let module = compile(&C1Parser::parse("void main() { printf(1); }").unwrap());
+/// let bytes = write(&module);
+/// assert!(bytes.starts_with(b"C1B\0"));
+/// assert_eq!(load(&bytes), Ok(module));
+/// ```
+pub fn write(module: &Module) -> Vec<u8> {
+    let mut writer = Writer { bytes: Vec::new() };
+    writer.bytes.extend_from_slice(MAGIC);
+    writer.bytes.extend_from_slice(&VERSION.to_le_bytes());
+    writer.u32(module.constants.len());
+    for constant in &module.constants {
+        match *constant {
+            Value::Int(value) => {
+                writer.u8(0);
+                writer.bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            Value::Float(value) => {
+                writer.u8(1);
+                writer.bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            Value::Bool(value) => {
+                writer.u8(2);
+                writer.u8(value as u8);
+            }
+            Value::Void => writer.u8(3),
+        }
+    }
+    writer.u32(module.names.len());
+    for name in &module.names {
+        writer.string(name);
+    }
+    writer.u32(module.globals.len());
+    for global in &module.globals {
+        writer.string(&global.name);
+        writer.ty(global.ty);
+    }
+    writer.function(&module.init);
+    writer.u32(module.functions.len());
+    for function in &module.functions {
+        writer.function(function);
+    }
+    writer.bytes
+}
+
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn u32(&mut self, value: impl TryInto<u32>) {
+        let value: u32 = value.try_into().unwrap_or_else(|_| panic!("value does not fit into the format"));
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn string(&mut self, value: &str) {
+        self.u32(value.len());
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    fn span(&mut self, span: Span) {
+        self.u32(span.start);
+        self.u32(span.end);
+        self.u32(span.line);
+        self.u32(span.column);
+    }
+
+    fn ty(&mut self, ty: Type) {
+        self.u8(TYPES.iter().position(|other| *other == ty).unwrap() as u8);
+    }
+
+    fn op(&mut self, op: BinaryOp) {
+        self.u8(OPERATORS.iter().position(|other| *other == op).unwrap() as u8);
+    }
+
+    fn function(&mut self, function: &Function) {
+        self.string(&function.name);
+        self.span(function.span);
+        self.ty(function.return_type);
+        self.u32(function.parameters.len());
+        for parameter in &function.parameters {
+            self.ty(*parameter);
+        }
+        self.u32(function.locals.len());
+        for local in &function.locals {
+            self.string(local);
+        }
+        self.u32(function.code.len());
+        for instruction in &function.code {
+            self.instruction(*instruction);
+        }
+        self.u32(function.lines.len());
+        for entry in &function.lines {
+            self.u32(entry.offset);
+            self.span(entry.span);
+        }
+    }
+
+    fn instruction(&mut self, instruction: Instruction) {
+        match instruction {
+            Instruction::Const(index) => {
+                self.u8(0);
+                self.u32(index);
+            }
+            Instruction::Void => self.u8(1),
+            Instruction::Load(slot) => {
+                self.u8(2);
+                self.u32(slot);
+            }
+            Instruction::Store(slot) => {
+                self.u8(3);
+                self.u32(slot);
+            }
+            Instruction::Clear(slot) => {
+                self.u8(4);
+                self.u32(slot);
+            }
+            Instruction::LoadGlobal(slot) => {
+                self.u8(5);
+                self.u32(slot);
+            }
+            Instruction::StoreGlobal(slot) => {
+                self.u8(6);
+                self.u32(slot);
+            }
+            Instruction::Convert(ty) => {
+                self.u8(7);
+                self.ty(ty);
+            }
+            Instruction::Dup => self.u8(8),
+            Instruction::Pop => self.u8(9),
+            Instruction::Neg => self.u8(10),
+            Instruction::Binary(op) => {
+                self.u8(11);
+                self.op(op);
+            }
+            Instruction::Jump(target) => {
+                self.u8(12);
+                self.u32(target);
+            }
+            Instruction::JumpIfFalse(target) => {
+                self.u8(13);
+                self.u32(target);
+            }
+            Instruction::ShortCircuit(op, target) => {
+                self.u8(14);
+                self.op(op);
+                self.u32(target);
+            }
+            Instruction::CheckCall { function, arguments } => {
+                self.u8(15);
+                self.u32(function);
+                self.u32(arguments);
+            }
+            Instruction::ConvertArgument(above, ty) => {
+                self.u8(16);
+                self.u32(above);
+                self.ty(ty);
+            }
+            Instruction::Call { function, arguments } => {
+                self.u8(17);
+                self.u32(function);
+                self.u32(arguments);
+            }
+            Instruction::Ret => self.u8(18),
+            Instruction::End => self.u8(19),
+            Instruction::Print => self.u8(20),
+            Instruction::UndefinedVariable(index) => {
+                self.u8(21);
+                self.u32(index);
+            }
+            Instruction::UndefinedFunction(index) => {
+                self.u8(22);
+                self.u32(index);
+            }
+        }
+    }
+}
+
+/// Load and validate a compiled program.
+///
+/// Returns the first problem of the file.
+/// ```
+/// use cb_3::c1b::{load, LoadErrorKind};
+///
+/// let error = load(b"C1B\0\x01").unwrap_err();
+/// assert_eq!(error.kind, LoadErrorKind::Truncated);
+/// assert_eq!(error.to_string(), "byte 4: unexpected end of file");
+/// ```
+pub fn load(bytes: &[u8]) -> Result<Module, LoadError> {
+    let mut reader = Reader { bytes, position: 0 };
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(LoadError { kind: LoadErrorKind::BadMagic, offset: 0 });
+    }
+    let version = u16::from_le_bytes(reader.array()?);
+    if version != VERSION {
+        return Err(LoadError { kind: LoadErrorKind::UnsupportedVersion(version), offset: MAGIC.len() });
+    }
+    let mut constants = Vec::new();
+    for _ in 0..reader.u32()? {
+        let start = reader.position;
+        let constant = match reader.u8()? {
+            0 => Value::Int(i32::from_le_bytes(reader.array()?)),
+            1 => Value::Float(f64::from_le_bytes(reader.array()?)),
+            2 => match reader.u8()? {
+                0 => Value::Bool(false),
+                1 => Value::Bool(true),
+                tag => return Err(LoadError { kind: LoadErrorKind::InvalidTag { what: "bool", tag }, offset: start + 1 }),
+            },
+            3 => Value::Void,
+            tag => return Err(LoadError { kind: LoadErrorKind::InvalidTag { what: "constant", tag }, offset: start }),
+        };
+        constants.push(constant);
+    }
+    let mut names = Vec::new();
+    for _ in 0..reader.u32()? {
+        names.push(reader.string()?);
+    }
+    let mut globals = Vec::new();
+    for _ in 0..reader.u32()? {
+        let name = reader.string()?;
+        let ty = reader.ty()?;
+        globals.push(Global { name, ty });
+    }
+    let init_start = reader.position;
+    let init = reader.function()?;
+    if !init.parameters.is_empty() || !init.locals.is_empty() || init.return_type != Type::Void {
+        return Err(LoadError { kind: LoadErrorKind::InvalidInitialization, offset: init_start });
+    }
+    let count = reader.u32()?;
+    let mut functions = Vec::new();
+    let mut starts = vec![init_start];
+    for _ in 0..count {
+        starts.push(reader.position);
+        functions.push(reader.function()?);
+    }
+    if reader.position != bytes.len() {
+        return Err(LoadError { kind: LoadErrorKind::TrailingBytes, offset: reader.position });
+    }
+    let module = Module { constants, names, globals, init, functions };
+    for (function, start) in std::iter::once(&module.init).chain(&module.functions).zip(starts) {
+        validate(&module, function).map_err(|kind| LoadError { kind, offset: start })?;
+    }
+    Ok(module)
+}
+
+struct Reader<'b> {
+    bytes: &'b [u8],
+    position: usize,
+}
+
+impl<'b> Reader<'b> {
+    fn take(&mut self, length: usize) -> Result<&'b [u8], LoadError> {
+        match self.bytes.get(self.position..).and_then(|rest| rest.get(..length)) {
+            Some(bytes) => {
+                self.position += length;
+                Ok(bytes)
+            }
+            None => Err(LoadError { kind: LoadErrorKind::Truncated, offset: self.position }),
+        }
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], LoadError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, LoadError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, LoadError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn usize(&mut self) -> Result<usize, LoadError> {
+        Ok(self.u32()? as usize)
+    }
+
+    fn string(&mut self) -> Result<String, LoadError> {
+        let length = self.usize()?;
+        let start = self.position;
+        let bytes = self.take(length)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| LoadError { kind: LoadErrorKind::InvalidString, offset: start })
+    }
+
+    /// Read a tag byte and look it up in the table of the values it stands for
+    fn tag<T: Copy>(&mut self, what: &'static str, values: &[T]) -> Result<T, LoadError> {
+        let offset = self.position;
+        let tag = self.u8()?;
+        values.get(tag as usize).copied().ok_or(LoadError { kind: LoadErrorKind::InvalidTag { what, tag }, offset })
+    }
+
+    fn span(&mut self) -> Result<Span, LoadError> {
+        Ok(Span { start: self.usize()?, end: self.usize()?, line: self.usize()?, column: self.usize()? })
+    }
+
+    fn ty(&mut self) -> Result<Type, LoadError> {
+        self.tag("type", &TYPES)
+    }
+
+    fn op(&mut self) -> Result<BinaryOp, LoadError> {
+        self.tag("operator", &OPERATORS)
+    }
+
+    fn function(&mut self) -> Result<Function, LoadError> {
+        let name = self.string()?;
+        let span = self.span()?;
+        let return_type = self.ty()?;
+        let mut parameters = Vec::new();
+        for _ in 0..self.u32()? {
+            parameters.push(self.ty()?);
+        }
+        let mut locals = Vec::new();
+        for _ in 0..self.u32()? {
+            locals.push(self.string()?);
+        }
+        let mut code = Vec::new();
+        for _ in 0..self.u32()? {
+            code.push(self.instruction()?);
+        }
+        let mut lines = Vec::new();
+        for _ in 0..self.u32()? {
+            let offset = self.u32()?;
+            let span = self.span()?;
+            lines.push(LineEntry { offset, span });
+        }
+        Ok(Function { name, span, parameters, return_type, locals, code, lines })
+    }
+
+    fn instruction(&mut self) -> Result<Instruction, LoadError> {
+        let offset = self.position;
+        let instruction = match self.u8()? {
+            0 => Instruction::Const(self.u32()?),
+            1 => Instruction::Void,
+            2 => Instruction::Load(self.u32()?),
+            3 => Instruction::Store(self.u32()?),
+            4 => Instruction::Clear(self.u32()?),
+            5 => Instruction::LoadGlobal(self.u32()?),
+            6 => Instruction::StoreGlobal(self.u32()?),
+            7 => Instruction::Convert(self.ty()?),
+            8 => Instruction::Dup,
+            9 => Instruction::Pop,
+            10 => Instruction::Neg,
+            11 => Instruction::Binary(self.op()?),
+            12 => Instruction::Jump(self.u32()?),
+            13 => Instruction::JumpIfFalse(self.u32()?),
+            14 => Instruction::ShortCircuit(self.op()?, self.u32()?),
+            15 => Instruction::CheckCall { function: self.u32()?, arguments: self.u32()? },
+            16 => Instruction::ConvertArgument(self.u32()?, self.ty()?),
+            17 => Instruction::Call { function: self.u32()?, arguments: self.u32()? },
+            18 => Instruction::Ret,
+            19 => Instruction::End,
+            20 => Instruction::Print,
+            21 => Instruction::UndefinedVariable(self.u32()?),
+            22 => Instruction::UndefinedFunction(self.u32()?),
+            tag => return Err(LoadError { kind: LoadErrorKind::InvalidTag { what: "opcode", tag }, offset }),
+        };
+        Ok(instruction)
+    }
+}
+
+/// Check that the function only refers to what exists and keeps the stack balanced, so the
+/// [vm](crate::vm) can execute it
+fn validate(module: &Module, function: &Function) -> Result<(), LoadErrorKind> {
+    let name = || function.name.clone();
+    if function.parameters.len() > function.locals.len() {
+        return Err(LoadErrorKind::MissingParameterSlots(name()));
+    }
+    let length = function.code.len();
+    let ordered = function.lines.windows(2).all(|pair| pair[0].offset < pair[1].offset);
+    let first = function.lines.first().map(|entry| entry.offset);
+    let last = function.lines.last().map(|entry| entry.offset as usize);
+    if !ordered || (length > 0 && first != Some(0)) || last.is_some_and(|last| last >= length) {
+        return Err(LoadErrorKind::InvalidLineTable(name()));
+    }
+    let invalid = |instruction, problem| LoadErrorKind::InvalidCode { function: name(), instruction, problem };
+    if length == 0 {
+        return Err(invalid(0, CodeProblem::MissingEnd));
+    }
+
+    // number of values on the stack before each instruction, found by following every path
+    let mut heights: Vec<Option<usize>> = vec![None; length];
+    heights[0] = Some(0);
+    let mut pending = vec![0];
+    while let Some(offset) = pending.pop() {
+        let height = heights[offset].unwrap();
+        let instruction = function.code[offset];
+        let check = |index: u32, count: usize, problem: fn(u32) -> CodeProblem| {
+            if index as usize >= count {
+                Err(invalid(offset, problem(index)))
+            } else {
+                Ok(())
+            }
+        };
+        let (pops, pushes) = match instruction {
+            Instruction::Const(index) => {
+                check(index, module.constants.len(), CodeProblem::ConstantOutOfRange)?;
+                (0, 1)
+            }
+            Instruction::Void => (0, 1),
+            Instruction::Load(slot) => {
+                check(slot, function.locals.len(), CodeProblem::LocalOutOfRange)?;
+                (0, 1)
+            }
+            Instruction::Store(slot) => {
+                check(slot, function.locals.len(), CodeProblem::LocalOutOfRange)?;
+                (1, 0)
+            }
+            Instruction::Clear(slot) => {
+                check(slot, function.locals.len(), CodeProblem::LocalOutOfRange)?;
+                (0, 0)
+            }
+            Instruction::LoadGlobal(slot) => {
+                check(slot, module.globals.len(), CodeProblem::GlobalOutOfRange)?;
+                (0, 1)
+            }
+            Instruction::StoreGlobal(slot) => {
+                check(slot, module.globals.len(), CodeProblem::GlobalOutOfRange)?;
+                (1, 0)
+            }
+            Instruction::Convert(_) | Instruction::Neg => (1, 1),
+            Instruction::Dup => (1, 2),
+            Instruction::Pop | Instruction::Print | Instruction::JumpIfFalse(_) => (1, 0),
+            Instruction::Binary(_) => (2, 1),
+            Instruction::Jump(_) | Instruction::End => (0, 0),
+            Instruction::ShortCircuit(op, _) => {
+                if !matches!(op, BinaryOp::And | BinaryOp::Or) {
+                    return Err(invalid(offset, CodeProblem::NotShortCircuiting(op)));
+                }
+                (1, 1)
+            }
+            Instruction::CheckCall { function, arguments } => {
+                check(function, module.functions.len(), CodeProblem::FunctionOutOfRange)?;
+                (arguments as usize, arguments as usize)
+            }
+            Instruction::ConvertArgument(above, _) => (above as usize + 1, above as usize + 1),
+            Instruction::Call { function, arguments } => {
+                check(function, module.functions.len(), CodeProblem::FunctionOutOfRange)?;
+                (arguments as usize, 1)
+            }
+            Instruction::Ret => (1, 0),
+            Instruction::UndefinedVariable(index) | Instruction::UndefinedFunction(index) => {
+                check(index, module.names.len(), CodeProblem::NameOutOfRange)?;
+                (0, 0)
+            }
+        };
+        if height < pops {
+            return Err(invalid(offset, CodeProblem::StackUnderflow));
+        }
+        let height = height - pops + pushes;
+        let mut successors = Vec::new();
+        match instruction {
+            Instruction::Ret | Instruction::End => {
+                if height != 0 {
+                    return Err(invalid(offset, CodeProblem::UnbalancedReturn));
+                }
+            }
+            // execution fails, the stack does not matter any more
+            Instruction::UndefinedVariable(_) | Instruction::UndefinedFunction(_) => {}
+            Instruction::Jump(target) => successors.push(target),
+            Instruction::JumpIfFalse(target) | Instruction::ShortCircuit(_, target) => {
+                successors.push(offset as u32 + 1);
+                successors.push(target);
+            }
+            _ => successors.push(offset as u32 + 1),
+        }
+        for successor in successors {
+            let slot = match heights.get_mut(successor as usize) {
+                Some(slot) => slot,
+                None if successor as usize == length && successor as usize == offset + 1 => {
+                    return Err(invalid(offset, CodeProblem::MissingEnd));
+                }
+                None => return Err(invalid(offset, CodeProblem::JumpOutOfRange(successor))),
+            };
+            match *slot {
+                None => {
+                    *slot = Some(height);
+                    pending.push(successor as usize);
+                }
+                Some(other) if other != height => return Err(invalid(successor as usize, CodeProblem::StackMismatch)),
+                Some(_) => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Return a textual listing of a compiled program.
+///
+/// Every instruction is listed with its offset, the line it was compiled from if that differs from
+/// the line of the previous instruction, and the constant, variable or function it refers to.
+/// ```
+/// use cb_3::C1Parser;
+/// use cb_3::bytecode::compile;
+/// use cb_3::c1b::disassemble;
+///
+/// let module = compile(&C1Parser::parse("void main() {\n    printf(1);\n}").unwrap());
+/// let listing = disassemble(&module);
+/// assert!(listing.ends_with("function void main()\n     0     2  const 0          ; int 1\n     1        print\n     2     1  end\n"));
+/// ```
+pub fn disassemble(module: &Module) -> String {
+    let mut listing = String::new();
+    writeln!(listing, "c1b version {}", VERSION).unwrap();
+    writeln!(listing, "constants").unwrap();
+    for (index, constant) in module.constants.iter().enumerate() {
+        writeln!(listing, "{:>6}  {}", index, constant_text(constant)).unwrap();
+    }
+    writeln!(listing, "names").unwrap();
+    for (index, name) in module.names.iter().enumerate() {
+        writeln!(listing, "{:>6}  {}", index, name).unwrap();
+    }
+    writeln!(listing, "globals").unwrap();
+    for (slot, global) in module.globals.iter().enumerate() {
+        writeln!(listing, "{:>6}  {} {}", slot, global.ty, global.name).unwrap();
+    }
+    writeln!(listing, "initialization").unwrap();
+    disassemble_code(module, &module.init, &mut listing);
+    for function in &module.functions {
+        let parameters: Vec<String> = function.parameters.iter().map(Type::to_string).collect();
+        writeln!(listing, "function {} {}({})", function.return_type, function.name, parameters.join(", ")).unwrap();
+        if !function.locals.is_empty() {
+            writeln!(listing, "  locals {}", function.locals.join(", ")).unwrap();
+        }
+        disassemble_code(module, function, &mut listing);
+    }
+    listing
+}
+
+fn constant_text(constant: &Value) -> String {
+    match constant {
+        Value::Int(value) => format!("int {}", value),
+        Value::Float(value) => format!("float {:?}", value),
+        Value::Bool(value) => format!("bool {}", value),
+        Value::Void => "void".to_string(),
+    }
+}
+
+fn disassemble_code(module: &Module, function: &Function, listing: &mut String) {
+    let mut previous_line = None;
+    for (offset, instruction) in function.code.iter().enumerate() {
+        let line = function.lines.first().map(|_| function.span_at(offset).line);
+        let line_text = match line {
+            Some(line) if line != 0 && Some(line) != previous_line => line.to_string(),
+            _ => String::new(),
+        };
+        previous_line = line;
+        let comment = match *instruction {
+            Instruction::Const(index) => module.constants.get(index as usize).map(constant_text),
+            Instruction::Load(slot) | Instruction::Store(slot) | Instruction::Clear(slot) => function.locals.get(slot as usize).cloned(),
+            Instruction::LoadGlobal(slot) | Instruction::StoreGlobal(slot) => module.globals.get(slot as usize).map(|global| global.name.clone()),
+            Instruction::CheckCall { function, .. } | Instruction::Call { function, .. } => module.functions.get(function as usize).map(|function| function.name.clone()),
+            Instruction::UndefinedVariable(index) | Instruction::UndefinedFunction(index) => module.names.get(index as usize).cloned(),
+            _ => None,
+        };
+        let text = match comment {
+            Some(comment) => format!("{:<16} ; {}", instruction.to_string(), comment),
+            None => instruction.to_string(),
+        };
+        writeln!(listing, "{:>6}  {:>4}  {}", offset, line_text, text).unwrap();
+    }
+}
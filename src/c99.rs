@@ -0,0 +1,418 @@
+//! Backend that translates C(-1) programs to standalone C99 source text.
+//!
+//! The translation keeps the structure of the program and only changes what C does differently:
+//! - `bool` becomes `_Bool` and `float` becomes `double`. Functions are declared before all
+//!   definitions, so they can be called in any order, and the program gets a C `main` function
+//!   that initializes the global variables and calls the `main` function of the program
+//! - names get a prefix so they cannot collide with C keywords or the C library: functions are
+//!   called `f_<name>`, global variables `g<slot>_<name>` and local variables `v<slot>_<name>`,
+//!   which also keeps variables with the same name in different scopes apart
+//! - variables that are created by an assignment are declared at the start of their function
+//! - `int` arithmetic wraps around and an integer division by zero prints the runtime error of the
+//!   [interpreter](crate::interpreter) to stderr and exits with status 1, both by calling small
+//!   helper functions
+//! - C does not define in which order operands and arguments are evaluated, so they are assigned
+//!   to temporary variables from left to right if one of them has side effects
+//! - `printf(expr)` becomes a call of C's `printf` with a format for the type of `expr`
+//!
+//! The program has to pass every [check](crate::analysis::analyze).
+
+use crate::analysis::{analyze, Analysis, Diagnostic};
+use crate::ast::{Assignment, BinaryOp, Block, Expr, ExprKind, FunctionCall, FunctionDefinition, Identifier, Program, Statement, StatementKind, Type, UnaryOp};
+use crate::resolve::BindingKind;
+use crate::symbols::FunctionTable;
+use std::fmt::Write;
+
+const PRELUDE: &str = r#"#include <math.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+/* C(-1) ints wrap around on overflow */
+static inline int c1_add(int a, int b) { return (int)((unsigned)a + (unsigned)b); }
+static inline int c1_sub(int a, int b) { return (int)((unsigned)a - (unsigned)b); }
+static inline int c1_mul(int a, int b) { return (int)((unsigned)a * (unsigned)b); }
+static inline int c1_neg(int a) { return (int)(0u - (unsigned)a); }
+
+static inline int c1_div(int a, int b, int line, int column) {
+    if (b == 0) {
+        fprintf(stderr, "line %d, column %d: division by zero\n", line, column);
+        exit(1);
+    }
+    return b == -1 ? c1_neg(a) : a / b;
+}
+
+/* every NaN is printed as "nan" */
+static inline double c1_printable(double value) { return value != value ? NAN : value; }
+"#;
+
+/// Translate a program to C99.
+///
+/// Returns the errors of the checks if the program is not correct.
+/// ```
+/// use cb_3::C1Parser;
+/// use cb_3::c99::translate;
+///
+/// let program = C1Parser::parse("void main() { x = 1.5; printf(x > 1); }").unwrap();
+/// let source = translate(&program).unwrap();
+/// assert!(source.contains("static void f_main(void) {\n    double v0_x;\n    v0_x = 1.5;\n"));
+/// assert!(source.contains("printf(\"%s\\n\", (v0_x > 1) ? \"true\" : \"false\");"));
+/// ```
+pub fn translate(program: &Program) -> Result<String, Vec<Diagnostic>> {
+    let analysis = analyze(program)?;
+    let mut translator = Translator { functions: FunctionTable::new(program), analysis, body: String::new(), temporaries: Vec::new(), indent: 1 };
+    let mut source = PRELUDE.to_string();
+
+    source.push('\n');
+    for declaration in &program.globals {
+        writeln!(source, "static {} {};", c_type(declaration.ty), translator.name(&declaration.name)).unwrap();
+    }
+    source.push('\n');
+    for function in &program.functions {
+        writeln!(source, "{};", translator.signature(function)).unwrap();
+    }
+
+    for function in &program.functions {
+        writeln!(source, "\n{} {{", translator.signature(function)).unwrap();
+        let mut declarations = String::new();
+        for statement in &function.body.statements {
+            translator.implicit_variables(statement, &mut declarations);
+        }
+        translator.block(&function.body);
+        translator.finish(&mut source, &declarations);
+    }
+
+    source.push_str("\nstatic void c1_init(void) {\n");
+    for declaration in &program.globals {
+        if let Some(value) = &declaration.value {
+            let value = translator.expr(value);
+            translator.line(format_args!("{} = {};", translator.name(&declaration.name), value));
+        }
+    }
+    translator.finish(&mut source, "");
+
+    source.push_str("\nint main(void) {\n    c1_init();\n");
+    match translator.functions.get("main").map(|main| main.return_type) {
+        Some(Type::Int) => source.push_str("    return f_main();\n}\n"),
+        _ => source.push_str("    f_main();\n    return 0;\n}\n"),
+    }
+    Ok(source)
+}
+
+fn c_type(ty: Type) -> &'static str {
+    match ty {
+        Type::Boolean => "_Bool",
+        Type::Float => "double",
+        Type::Int => "int",
+        Type::Void => "void",
+    }
+}
+
+/// Return whether evaluating the expression can change a variable or print something
+fn has_side_effects(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Int(_) | ExprKind::Float(_) | ExprKind::Bool(_) | ExprKind::Variable(_) => false,
+        ExprKind::Call(_) | ExprKind::Assign(_) => true,
+        ExprKind::Unary { operand, .. } => has_side_effects(operand),
+        ExprKind::Binary { lhs, rhs, .. } => has_side_effects(lhs) || has_side_effects(rhs),
+    }
+}
+
+struct Translator<'p> {
+    functions: FunctionTable<'p>,
+    analysis: Analysis,
+    /// Statements of the function that is translated
+    body: String,
+    /// Types of the temporary variables of the function that is translated
+    temporaries: Vec<Type>,
+    indent: usize,
+}
+
+impl Translator<'_> {
+    /// Return the C name of the variable the identifier refers to
+    fn name(&self, identifier: &Identifier) -> String {
+        let id = self.analysis.resolution.lookup(identifier).expect("the program is resolved");
+        let binding = self.analysis.resolution.binding(id);
+        match binding.kind {
+            BindingKind::Global => format!("g{}_{}", binding.slot, binding.name),
+            _ => format!("v{}_{}", binding.slot, binding.name),
+        }
+    }
+
+    fn signature(&self, function: &FunctionDefinition) -> String {
+        let parameters: Vec<String> =
+            function.parameters.iter().map(|parameter| format!("{} {}", c_type(parameter.ty), self.name(&parameter.name))).collect();
+        let parameters = if parameters.is_empty() { "void".to_string() } else { parameters.join(", ") };
+        format!("static {} f_{}({})", c_type(function.return_type), function.name.name, parameters)
+    }
+
+    /// Write the temporary variables, the declarations and the statements of the function that was
+    /// translated, and close its body
+    fn finish(&mut self, source: &mut String, declarations: &str) {
+        for (index, ty) in self.temporaries.drain(..).enumerate() {
+            writeln!(source, "    {} t{};", c_type(ty), index).unwrap();
+        }
+        source.push_str(declarations);
+        source.push_str(&self.body);
+        source.push_str("}\n");
+        self.body.clear();
+    }
+
+    fn line(&mut self, line: std::fmt::Arguments<'_>) {
+        writeln!(self.body, "{:indent$}{}", "", line, indent = 4 * self.indent).unwrap();
+    }
+
+    /// Declare the variables that are created by the assignments of the statement
+    fn implicit_variables(&self, statement: &Statement, declarations: &mut String) {
+        let mut declare = |identifier: &Identifier| {
+            let id = self.analysis.resolution.lookup(identifier).expect("the program is resolved");
+            let binding = self.analysis.resolution.binding(id);
+            if binding.kind == BindingKind::Implicit && binding.span == identifier.span {
+                writeln!(declarations, "    {} {};", c_type(self.analysis.types.variable(id)), self.name(identifier)).unwrap();
+            }
+        };
+        let mut assignments = Vec::new();
+        collect_assignments(statement, &mut assignments);
+        for assignment in assignments {
+            declare(&assignment.target);
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        for statement in &block.statements {
+            self.statement(statement);
+        }
+    }
+
+    /// Translate the branch or body of a statement, which is always put into braces
+    fn nested(&mut self, statement: &Statement) {
+        self.indent += 1;
+        match &statement.kind {
+            StatementKind::Block(block) => self.block(block),
+            _ => self.statement(statement),
+        }
+        self.indent -= 1;
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match &statement.kind {
+            StatementKind::Block(block) => {
+                self.line(format_args!("{{"));
+                self.indent += 1;
+                self.block(block);
+                self.indent -= 1;
+                self.line(format_args!("}}"));
+            }
+            StatementKind::Declaration(declaration) => {
+                let name = self.name(&declaration.name);
+                match &declaration.value {
+                    Some(value) => {
+                        let value = self.expr(value);
+                        self.line(format_args!("{} {} = {};", c_type(declaration.ty), name, value));
+                    }
+                    None => self.line(format_args!("{} {};", c_type(declaration.ty), name)),
+                }
+            }
+            StatementKind::If { condition, then_branch, else_branch } => {
+                let condition = self.expr(condition);
+                self.line(format_args!("if ({}) {{", condition));
+                self.nested(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.line(format_args!("}} else {{"));
+                    self.nested(else_branch);
+                }
+                self.line(format_args!("}}"));
+            }
+            StatementKind::For { init, condition, step, body } => {
+                let (init, condition, step) = (self.assignment(init), self.expr(condition), self.assignment(step));
+                self.line(format_args!("for ({}; {}; {}) {{", init, condition, step));
+                self.nested(body);
+                self.line(format_args!("}}"));
+            }
+            StatementKind::While { condition, body } => {
+                let condition = self.expr(condition);
+                self.line(format_args!("while ({}) {{", condition));
+                self.nested(body);
+                self.line(format_args!("}}"));
+            }
+            StatementKind::DoWhile { body, condition } => {
+                self.line(format_args!("do {{"));
+                self.nested(body);
+                let condition = self.expr(condition);
+                self.line(format_args!("}} while ({});", condition));
+            }
+            StatementKind::Return(Some(value)) => {
+                let value = self.expr(value);
+                self.line(format_args!("return {};", value));
+            }
+            StatementKind::Return(None) => self.line(format_args!("return;")),
+            StatementKind::Printf(value) => {
+                let ty = self.analysis.types.expr(value);
+                let value = self.expr(value);
+                match ty {
+                    Type::Float => self.line(format_args!("printf(\"%f\\n\", c1_printable({}));", value)),
+                    Type::Boolean => self.line(format_args!("printf(\"%s\\n\", {} ? \"true\" : \"false\");", value)),
+                    _ => self.line(format_args!("printf(\"%d\\n\", {});", value)),
+                }
+            }
+            StatementKind::Assignment(assignment) => {
+                let assignment = self.assignment(assignment);
+                self.line(format_args!("{};", assignment));
+            }
+            StatementKind::Call(call) => {
+                let call = self.call(call);
+                self.line(format_args!("{};", call));
+            }
+        }
+    }
+
+    fn assignment(&mut self, assignment: &Assignment) -> String {
+        let value = self.expr(&assignment.value);
+        format!("{} = {}", self.name(&assignment.target), value)
+    }
+
+    /// Translate the operands of a call or operator. If one of them has side effects, each one is
+    /// assigned to a temporary variable in order and the temporaries are returned together with
+    /// the assignments, which have to be sequenced with the comma operator before the operands
+    /// are used.
+    fn operands(&mut self, operands: &[&Expr]) -> (Vec<String>, Option<String>) {
+        let translated: Vec<String> = operands.iter().map(|operand| self.expr(operand)).collect();
+        if operands.len() < 2 || !operands.iter().any(|operand| has_side_effects(operand)) {
+            return (translated, None);
+        }
+        let mut assignments = Vec::new();
+        let mut temporaries = Vec::new();
+        for (operand, translated) in operands.iter().zip(translated) {
+            let temporary = format!("t{}", self.temporaries.len());
+            self.temporaries.push(self.analysis.types.expr(operand));
+            assignments.push(format!("{} = {}", temporary, translated));
+            temporaries.push(temporary);
+        }
+        (temporaries, Some(assignments.join(", ")))
+    }
+
+    /// Apply the sequenced assignments of [operands](Self::operands) before the expression
+    fn sequenced(expr: String, assignments: Option<String>) -> String {
+        match assignments {
+            Some(assignments) => format!("({}, {})", assignments, expr),
+            None => expr,
+        }
+    }
+
+    fn call(&mut self, call: &FunctionCall) -> String {
+        let arguments: Vec<&Expr> = call.arguments.iter().collect();
+        let (arguments, assignments) = self.operands(&arguments);
+        let function = self.functions.get(&call.name.name).expect("the functions are checked");
+        Self::sequenced(format!("f_{}({})", function.name.name, arguments.join(", ")), assignments)
+    }
+
+    fn expr(&mut self, expr: &Expr) -> String {
+        match &expr.kind {
+            ExprKind::Int(value) => value.to_string(),
+            ExprKind::Float(value) if value.is_infinite() => "HUGE_VAL".to_string(),
+            ExprKind::Float(value) => format!("{:?}", value),
+            ExprKind::Bool(value) => (*value as i32).to_string(),
+            ExprKind::Variable(identifier) => self.name(identifier),
+            ExprKind::Call(call) => self.call(call),
+            ExprKind::Assign(assignment) => format!("({})", self.assignment(assignment)),
+            ExprKind::Unary { op: UnaryOp::Neg, operand } => {
+                let translated = self.expr(operand);
+                match self.analysis.types.expr(operand) {
+                    Type::Int => format!("c1_neg({})", translated),
+                    _ => format!("(-{})", translated),
+                }
+            }
+            ExprKind::Binary { op: op @ (BinaryOp::And | BinaryOp::Or), lhs, rhs } => {
+                // && and || are sequenced in C as well
+                format!("({} {} {})", self.expr(lhs), op, self.expr(rhs))
+            }
+            ExprKind::Binary { op, lhs, rhs } => {
+                let int_operands = self.analysis.types.expr(lhs) == Type::Int && self.analysis.types.expr(rhs) == Type::Int;
+                let (operands, assignments) = self.operands(&[lhs, rhs]);
+                let (lhs, rhs) = (&operands[0], &operands[1]);
+                let translated = match op {
+                    BinaryOp::Add if int_operands => format!("c1_add({}, {})", lhs, rhs),
+                    BinaryOp::Sub if int_operands => format!("c1_sub({}, {})", lhs, rhs),
+                    BinaryOp::Mul if int_operands => format!("c1_mul({}, {})", lhs, rhs),
+                    BinaryOp::Div if int_operands => format!("c1_div({}, {}, {}, {})", lhs, rhs, expr.span.line, expr.span.column),
+                    _ => format!("({} {} {})", lhs, op, rhs),
+                };
+                Self::sequenced(translated, assignments)
+            }
+        }
+    }
+}
+
+/// Collect the assignments of a statement in the order of the source text
+fn collect_assignments<'p>(statement: &'p Statement, assignments: &mut Vec<&'p Assignment>) {
+    let expr = collect_expr_assignments;
+    match &statement.kind {
+        StatementKind::Block(block) => {
+            for statement in &block.statements {
+                collect_assignments(statement, assignments);
+            }
+        }
+        StatementKind::Declaration(declaration) => {
+            if let Some(value) = &declaration.value {
+                expr(value, assignments);
+            }
+        }
+        StatementKind::If { condition, then_branch, else_branch } => {
+            expr(condition, assignments);
+            collect_assignments(then_branch, assignments);
+            if let Some(else_branch) = else_branch {
+                collect_assignments(else_branch, assignments);
+            }
+        }
+        StatementKind::For { init, condition, step, body } => {
+            expr(&init.value, assignments);
+            assignments.push(init);
+            expr(condition, assignments);
+            expr(&step.value, assignments);
+            assignments.push(step);
+            collect_assignments(body, assignments);
+        }
+        StatementKind::While { condition, body } => {
+            expr(condition, assignments);
+            collect_assignments(body, assignments);
+        }
+        StatementKind::DoWhile { body, condition } => {
+            collect_assignments(body, assignments);
+            expr(condition, assignments);
+        }
+        StatementKind::Return(value) => {
+            if let Some(value) = value {
+                expr(value, assignments);
+            }
+        }
+        StatementKind::Printf(value) => expr(value, assignments),
+        StatementKind::Assignment(assignment) => {
+            expr(&assignment.value, assignments);
+            assignments.push(assignment);
+        }
+        StatementKind::Call(call) => {
+            for argument in &call.arguments {
+                expr(argument, assignments);
+            }
+        }
+    }
+}
+
+fn collect_expr_assignments<'p>(expr: &'p Expr, assignments: &mut Vec<&'p Assignment>) {
+    match &expr.kind {
+        ExprKind::Int(_) | ExprKind::Float(_) | ExprKind::Bool(_) | ExprKind::Variable(_) => {}
+        ExprKind::Call(call) => {
+            for argument in &call.arguments {
+                collect_expr_assignments(argument, assignments);
+            }
+        }
+        ExprKind::Assign(assignment) => {
+            collect_expr_assignments(&assignment.value, assignments);
+            assignments.push(assignment);
+        }
+        ExprKind::Unary { operand, .. } => collect_expr_assignments(operand, assignments),
+        ExprKind::Binary { lhs, rhs, .. } => {
+            collect_expr_assignments(lhs, assignments);
+            collect_expr_assignments(rhs, assignments);
+        }
+    }
+}
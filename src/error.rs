@@ -0,0 +1,157 @@
+use crate::{C1Token, Span};
+use std::error::Error;
+use std::fmt;
+
+/// A syntax error found by the [C1Parser](crate::C1Parser).
+///
+/// ```
+/// use cb_3::{C1Parser, C1Token, ParseErrorKind};
+///
+/// let error = C1Parser::parse("int f() { return 1 }").unwrap_err();
+/// assert_eq!(error.found(), Some(C1Token::RightBrace));
+/// assert_eq!(error.expected(), [C1Token::Semicolon]);
+/// assert_eq!((error.span.line, error.span.column), (1, 20));
+/// assert!(matches!(error.kind, ParseErrorKind::UnexpectedToken { .. }));
+/// assert_eq!(
+///     error.to_string(),
+///     "line 1, column 20: expected ';' after return statement, found '}'"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    /// Location of the offending token, or the end of the text if the input ended too early
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    /// The current token cannot continue the construct that is being parsed
+    UnexpectedToken {
+        /// The current token, `None` at the end of the input
+        found: Option<C1Token>,
+        /// All tokens that would have been accepted instead
+        expected: Vec<C1Token>,
+        /// Describes where the expected tokens belong, e.g. "after return statement"
+        context: &'static str,
+    },
+    /// An integer literal does not fit into the range of `int`
+    IntegerOutOfRange(String),
+    /// The current token is not a valid token, see [LexErrorKind]
+    Lexical(LexErrorKind),
+    /// The parser found more than [C1Parser::MAX_ERRORS](crate::C1Parser::MAX_ERRORS) errors and
+    /// ignored the rest of the input
+    TooManyErrors,
+}
+
+impl ParseError {
+    /// Return the token that caused the error, `None` if the input ended too early or the error is
+    /// not about an unexpected token
+    pub fn found(&self) -> Option<C1Token> {
+        match &self.kind {
+            ParseErrorKind::UnexpectedToken { found, .. } => *found,
+            ParseErrorKind::IntegerOutOfRange(_) | ParseErrorKind::Lexical(_) | ParseErrorKind::TooManyErrors => None,
+        }
+    }
+
+    /// Return the tokens that would have been accepted at the location of the error
+    pub fn expected(&self) -> &[C1Token] {
+        match &self.kind {
+            ParseErrorKind::UnexpectedToken { expected, .. } => expected,
+            ParseErrorKind::IntegerOutOfRange(_) | ParseErrorKind::Lexical(_) | ParseErrorKind::TooManyErrors => &[],
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.span.line, self.span.column)?;
+        match &self.kind {
+            ParseErrorKind::UnexpectedToken { found, expected, context } => {
+                f.write_str("expected ")?;
+                for (index, token) in expected.iter().enumerate() {
+                    if index == 0 {
+                        write!(f, "{}", token)?;
+                    } else if index + 1 == expected.len() {
+                        write!(f, " or {}", token)?;
+                    } else {
+                        write!(f, ", {}", token)?;
+                    }
+                }
+                write!(f, " {}, found ", context)?;
+                match found {
+                    Some(token) => write!(f, "{}", token),
+                    None => f.write_str("end of input"),
+                }
+            }
+            ParseErrorKind::IntegerOutOfRange(text) => {
+                write!(f, "integer literal {} does not fit into 'int'", text)
+            }
+            ParseErrorKind::Lexical(kind) => write!(f, "{}", kind),
+            ParseErrorKind::TooManyErrors => f.write_str("too many syntax errors, giving up"),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// A part of the text that the [C1Lexer](crate::C1Lexer) cannot turn into a token. The lexer
+/// returns a [C1Token::Error] for it and continues behind it.
+///
+/// ```
+/// use cb_3::{C1Lexer, C1Token, LexErrorKind};
+///
+/// let mut lexer = C1Lexer::new("x = 1.2.3 $");
+/// lexer.eat();
+/// lexer.eat();
+/// assert_eq!(lexer.current_token(), Some(C1Token::Error));
+/// let error = lexer.current_error().unwrap();
+/// assert_eq!(error.kind, LexErrorKind::MalformedNumber("1.2.3".to_string()));
+/// assert_eq!(error.to_string(), "line 1, column 5: malformed number 1.2.3");
+/// lexer.eat();
+/// assert_eq!(lexer.current_error().unwrap().kind, LexErrorKind::UnexpectedCharacter('$'));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    /// Location of the invalid text
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexErrorKind {
+    /// A character that cannot start a token
+    UnexpectedCharacter(char),
+    /// A string literal that is not closed before the end of its line
+    UnterminatedString,
+    /// A block comment that is not closed before the end of the text
+    UnterminatedComment,
+    /// A number with an exponent without digits or a second decimal point, like `1e` or `1.2.3`
+    MalformedNumber(String),
+    /// An integer literal that does not fit into 64 bits
+    IntegerOverflow(String),
+    /// A `\` in a string literal that is followed by a character other than `n`, `t`, `r`, `0`,
+    /// `\`, `"` or `'`
+    InvalidEscape(char),
+}
+
+impl fmt::Display for LexErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexErrorKind::UnexpectedCharacter(character) => write!(f, "unexpected character {:?}", character),
+            LexErrorKind::UnterminatedString => f.write_str("string literal is not closed with '\"'"),
+            LexErrorKind::UnterminatedComment => f.write_str("comment is not closed with '*/'"),
+            LexErrorKind::MalformedNumber(text) => write!(f, "malformed number {}", text),
+            LexErrorKind::IntegerOverflow(text) => write!(f, "integer literal {} does not fit into 64 bits", text),
+            LexErrorKind::InvalidEscape(character) => write!(f, "invalid escape sequence '\\{}'", character),
+        }
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.span.line, self.span.column, self.kind)
+    }
+}
+
+impl Error for LexError {}
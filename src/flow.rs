@@ -0,0 +1,155 @@
+//! Return-path analysis for functiondefinitions.
+//!
+//! A statement returns if every path through it ends in a return statement:
+//! - a return statement returns
+//! - a block returns if one of its statements returns, all statements after that one are
+//!   unreachable
+//! - an if statement returns if it has an else branch and both branches return
+//! - a do-while loop returns if its body returns, because the body is executed at least once
+//! - `for` and `while` loops never return, their conditions are not evaluated by the analysis
+//!
+//! Every non-void function has to return, a void function can end without a return statement.
+
+use crate::ast::{FunctionDefinition, Program, Statement, StatementKind, Type};
+use crate::Span;
+use std::error::Error;
+use std::fmt;
+
+/// A problem with the return statements of a function or an unreachable statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlowError {
+    pub kind: FlowErrorKind,
+    /// Location of the offending statement, or of the name of a function that does not return
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FlowErrorKind {
+    /// A non-void function can reach its end without a return statement
+    MissingReturn {
+        function: String,
+        expected: Type,
+    },
+    /// `return expr;` in a void function
+    ReturnValueInVoidFunction(String),
+    /// `return;` in a non-void function
+    MissingReturnValue {
+        function: String,
+        expected: Type,
+    },
+    /// A statement follows a statement that always returns. This is only a warning
+    UnreachableStatement,
+}
+
+impl FlowError {
+    /// Return whether the program can still be executed despite this problem
+    pub fn is_warning(&self) -> bool {
+        self.kind == FlowErrorKind::UnreachableStatement
+    }
+}
+
+impl fmt::Display for FlowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.span.line, self.span.column)?;
+        if self.is_warning() {
+            f.write_str("warning: ")?;
+        }
+        match &self.kind {
+            FlowErrorKind::MissingReturn { function, expected } => write!(
+                f,
+                "function '{}' has to return a value of type '{}' on every path",
+                function, expected
+            ),
+            FlowErrorKind::ReturnValueInVoidFunction(function) => write!(f, "void function '{}' cannot return a value", function),
+            FlowErrorKind::MissingReturnValue { function, expected } => {
+                write!(f, "function '{}' has to return a value of type '{}'", function, expected)
+            }
+            FlowErrorKind::UnreachableStatement => f.write_str("statement is unreachable"),
+        }
+    }
+}
+
+impl Error for FlowError {}
+
+/// Check the return statements of every function of the program.
+///
+/// Errors and warnings are returned in the order of the source text, a missing return is reported
+/// after the problems inside of the function.
+/// ```
+/// use cb_3::C1Parser;
+/// use cb_3::flow::{check_returns, FlowErrorKind};
+///
+/// let program = C1Parser::parse("int f(bool b) { if (b) { return 1; } }").unwrap();
+/// let errors = check_returns(&program);
+/// assert_eq!(errors[0].to_string(), "line 1, column 5: function 'f' has to return a value of type 'int' on every path");
+///
+/// let program = C1Parser::parse("int f() { return 1; printf(2); }").unwrap();
+/// let errors = check_returns(&program);
+/// assert_eq!(errors[0].kind, FlowErrorKind::UnreachableStatement);
+/// assert!(errors[0].is_warning());
+/// ```
+pub fn check_returns(program: &Program) -> Vec<FlowError> {
+    let mut errors = Vec::new();
+    for function in &program.functions {
+        let returns = statements(&function.body.statements, function, &mut errors);
+        if !returns && function.return_type != Type::Void {
+            errors.push(FlowError {
+                kind: FlowErrorKind::MissingReturn { function: function.name.name.clone(), expected: function.return_type },
+                span: function.name.span,
+            });
+        }
+    }
+    errors
+}
+
+/// Check a statementlist and return whether it returns on every path
+fn statements(statements: &[Statement], function: &FunctionDefinition, errors: &mut Vec<FlowError>) -> bool {
+    let mut returns = false;
+    for (index, statement) in statements.iter().enumerate() {
+        if returns {
+            // the remaining statements are still checked, but only reported as unreachable once
+            errors.push(FlowError { kind: FlowErrorKind::UnreachableStatement, span: statement.span });
+            for statement in &statements[index..] {
+                self::statement(statement, function, errors);
+            }
+            break;
+        }
+        returns = self::statement(statement, function, errors);
+    }
+    returns
+}
+
+/// Check a statement and return whether it returns on every path
+fn statement(statement: &Statement, function: &FunctionDefinition, errors: &mut Vec<FlowError>) -> bool {
+    match &statement.kind {
+        StatementKind::Block(block) => statements(&block.statements, function, errors),
+        StatementKind::If { then_branch, else_branch, .. } => {
+            let then_returns = self::statement(then_branch, function, errors);
+            match else_branch {
+                Some(else_branch) => self::statement(else_branch, function, errors) && then_returns,
+                None => false,
+            }
+        }
+        StatementKind::For { body, .. } | StatementKind::While { body, .. } => {
+            self::statement(body, function, errors);
+            false
+        }
+        StatementKind::DoWhile { body, .. } => self::statement(body, function, errors),
+        StatementKind::Return(value) => {
+            let name = &function.name.name;
+            match (value, function.return_type) {
+                (Some(value), Type::Void) => errors.push(FlowError {
+                    kind: FlowErrorKind::ReturnValueInVoidFunction(name.clone()),
+                    span: value.span,
+                }),
+                (None, expected) if expected != Type::Void => errors.push(FlowError {
+                    kind: FlowErrorKind::MissingReturnValue { function: name.clone(), expected },
+                    span: statement.span,
+                }),
+                _ => {}
+            }
+            true
+        }
+        StatementKind::Declaration(_) | StatementKind::Printf(_) | StatementKind::Assignment(_) | StatementKind::Call(_) => false,
+    }
+}
@@ -0,0 +1,260 @@
+//! Definite-assignment analysis for the variables of each function.
+//!
+//! A variable is definitely assigned at a read if every path from the start of the function to
+//! the read assigns it. Global variables and parameters are always assigned, a local declaration
+//! assigns its variable only if it has a value. Along the paths:
+//! - an if statement without else can skip its branch, an if statement with else assigns what both
+//!   branches assign
+//! - the body of a `for` or `while` loop can be skipped, the body of a `do`-`while` loop is executed
+//!   at least once
+//! - the right operand of `&&` and `||` can be skipped, but the right operand of `&&` is evaluated
+//!   whenever the whole condition is true
+//! - nothing after a return statement is reached, so a path that returns assigns every variable
+//!
+//! Conditions are not evaluated, so `if (true)` can still skip its branch. Each variable is
+//! reported only at its first read that is not definitely assigned. Variables are looked up with
+//! [resolve](crate::resolve::resolve), names that cannot be resolved are left to it.
+
+use crate::ast::{Assignment, BinaryOp, Block, Expr, ExprKind, FunctionDefinition, Identifier, Program, Statement, StatementKind};
+use crate::resolve::{resolve, BindingId, BindingKind, Resolution};
+use crate::Span;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+
+/// A read of a variable that is not definitely assigned
+#[derive(Debug, Clone, PartialEq)]
+pub struct InitError {
+    pub kind: InitErrorKind,
+    /// Location of the read
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InitErrorKind {
+    /// The variable is assigned on some paths to the read, or only after it
+    Unassigned(String),
+    /// The variable is declared, but never assigned
+    NeverAssigned(String),
+}
+
+impl InitError {
+    /// Return the name of the variable that is read
+    pub fn variable(&self) -> &str {
+        match &self.kind {
+            InitErrorKind::Unassigned(name) | InitErrorKind::NeverAssigned(name) => name,
+        }
+    }
+}
+
+impl fmt::Display for InitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.span.line, self.span.column)?;
+        match &self.kind {
+            InitErrorKind::Unassigned(name) => write!(f, "variable '{}' might be read before it is assigned", name),
+            InitErrorKind::NeverAssigned(name) => write!(f, "variable '{}' is never assigned", name),
+        }
+    }
+}
+
+impl Error for InitError {}
+
+/// Check that every variable read in a function of the program is definitely assigned.
+///
+/// Errors are returned in the order of the reads in the program.
+/// ```
+/// use cb_3::C1Parser;
+/// use cb_3::init::check_initialization;
+///
+/// let program = C1Parser::parse("void f(bool b) { if (b) { x = 1; } printf(x); int y; printf(y); }").unwrap();
+/// let errors = check_initialization(&program);
+/// assert_eq!(errors[0].to_string(), "line 1, column 43: variable 'x' might be read before it is assigned");
+/// assert_eq!((errors[1].variable(), errors[1].span.line), ("y", 1));
+/// ```
+pub fn check_initialization(program: &Program) -> Vec<InitError> {
+    let (resolution, _) = resolve(program);
+    let mut errors = Vec::new();
+    for function in &program.functions {
+        let mut checker = Checker { resolution: &resolution, known: HashSet::new(), reads: Vec::new() };
+        checker.function(function, &mut Assigned::Some(HashSet::new()));
+        for (id, span) in checker.reads {
+            let name = resolution.binding(id).name.clone();
+            let kind = if checker.known.contains(&id) {
+                InitErrorKind::Unassigned(name)
+            } else {
+                InitErrorKind::NeverAssigned(name)
+            };
+            errors.push(InitError { kind, span });
+        }
+    }
+    errors
+}
+
+/// The variables that are definitely assigned at some point of a function
+#[derive(Debug, Clone)]
+enum Assigned {
+    Some(HashSet<BindingId>),
+    /// The point cannot be reached, so every variable counts as assigned
+    Unreachable,
+}
+
+impl Assigned {
+    fn contains(&self, id: BindingId) -> bool {
+        match self {
+            Assigned::Some(ids) => ids.contains(&id),
+            Assigned::Unreachable => true,
+        }
+    }
+
+    fn insert(&mut self, id: BindingId) {
+        if let Assigned::Some(ids) = self {
+            ids.insert(id);
+        }
+    }
+
+    fn remove(&mut self, id: BindingId) {
+        if let Assigned::Some(ids) = self {
+            ids.remove(&id);
+        }
+    }
+
+    /// The variables that are assigned on both paths that meet
+    fn join(self, other: Self) -> Self {
+        match (self, other) {
+            (Assigned::Some(ids), Assigned::Some(other)) => Assigned::Some(ids.intersection(&other).copied().collect()),
+            (Assigned::Unreachable, assigned) | (assigned, Assigned::Unreachable) => assigned,
+        }
+    }
+}
+
+struct Checker<'r> {
+    resolution: &'r Resolution,
+    /// Every variable of the function that is assigned somewhere
+    known: HashSet<BindingId>,
+    /// The first read of each variable that is not definitely assigned
+    reads: Vec<(BindingId, Span)>,
+}
+
+impl Checker<'_> {
+    fn function(&mut self, function: &FunctionDefinition, assigned: &mut Assigned) {
+        for parameter in &function.parameters {
+            self.assign(&parameter.name, assigned);
+        }
+        self.block(&function.body, assigned);
+    }
+
+    /// Mark the variable the identifier refers to as assigned
+    fn assign(&mut self, identifier: &Identifier, assigned: &mut Assigned) {
+        if let Some(id) = self.resolution.lookup(identifier) {
+            self.known.insert(id);
+            assigned.insert(id);
+        }
+    }
+
+    fn block(&mut self, block: &Block, assigned: &mut Assigned) {
+        for statement in &block.statements {
+            self.statement(statement, assigned);
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement, assigned: &mut Assigned) {
+        match &statement.kind {
+            StatementKind::Block(block) => self.block(block, assigned),
+            StatementKind::Declaration(declaration) => match &declaration.value {
+                Some(value) => {
+                    self.expr(value, assigned);
+                    self.assign(&declaration.name, assigned);
+                }
+                None => {
+                    if let Some(id) = self.resolution.lookup(&declaration.name) {
+                        assigned.remove(id);
+                    }
+                }
+            },
+            StatementKind::If { condition, then_branch, else_branch } => {
+                let mut then_assigned = self.condition(condition, assigned);
+                self.statement(then_branch, &mut then_assigned);
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch, assigned);
+                }
+                *assigned = then_assigned.join(assigned.clone());
+            }
+            StatementKind::For { init, condition, step, body } => {
+                self.assignment(init, assigned);
+                let mut body_assigned = self.condition(condition, assigned);
+                self.statement(body, &mut body_assigned);
+                self.assignment(step, &mut body_assigned);
+            }
+            StatementKind::While { condition, body } => {
+                let mut body_assigned = self.condition(condition, assigned);
+                self.statement(body, &mut body_assigned);
+            }
+            StatementKind::DoWhile { body, condition } => {
+                self.statement(body, assigned);
+                self.expr(condition, assigned);
+            }
+            StatementKind::Return(value) => {
+                if let Some(value) = value {
+                    self.expr(value, assigned);
+                }
+                *assigned = Assigned::Unreachable;
+            }
+            StatementKind::Printf(value) => self.expr(value, assigned),
+            StatementKind::Assignment(assignment) => self.assignment(assignment, assigned),
+            StatementKind::Call(call) => {
+                for argument in &call.arguments {
+                    self.expr(argument, assigned);
+                }
+            }
+        }
+    }
+
+    /// Check a condition, update `assigned` to the variables that are assigned after it and return
+    /// the variables that are assigned if it is true
+    fn condition(&mut self, condition: &Expr, assigned: &mut Assigned) -> Assigned {
+        match &condition.kind {
+            ExprKind::Binary { op: BinaryOp::And, lhs, rhs } => {
+                let mut when_true = self.condition(lhs, assigned);
+                self.condition(rhs, &mut when_true)
+            }
+            _ => {
+                self.expr(condition, assigned);
+                assigned.clone()
+            }
+        }
+    }
+
+    fn assignment(&mut self, assignment: &Assignment, assigned: &mut Assigned) {
+        self.expr(&assignment.value, assigned);
+        self.assign(&assignment.target, assigned);
+    }
+
+    fn expr(&mut self, expr: &Expr, assigned: &mut Assigned) {
+        match &expr.kind {
+            ExprKind::Int(_) | ExprKind::Float(_) | ExprKind::Bool(_) => {}
+            ExprKind::Variable(identifier) => {
+                if let Some(id) = self.resolution.lookup(identifier) {
+                    let global = self.resolution.binding(id).kind == BindingKind::Global;
+                    if !global && !assigned.contains(id) && self.reads.iter().all(|(read, _)| *read != id) {
+                        self.reads.push((id, identifier.span));
+                    }
+                }
+            }
+            ExprKind::Call(call) => {
+                for argument in &call.arguments {
+                    self.expr(argument, assigned);
+                }
+            }
+            ExprKind::Assign(assignment) => self.assignment(assignment, assigned),
+            ExprKind::Unary { operand, .. } => self.expr(operand, assigned),
+            ExprKind::Binary { op: BinaryOp::And | BinaryOp::Or, lhs, rhs } => {
+                self.expr(lhs, assigned);
+                self.expr(rhs, &mut assigned.clone());
+            }
+            ExprKind::Binary { lhs, rhs, .. } => {
+                self.expr(lhs, assigned);
+                self.expr(rhs, assigned);
+            }
+        }
+    }
+}
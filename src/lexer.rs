@@ -1,4 +1,7 @@
+use crate::{LexError, LexErrorKind};
 use logos::{Lexer, Logos};
+use std::collections::VecDeque;
+use std::fmt;
 
 #[derive(Logos, Debug, PartialEq, Copy, Clone)]
 pub enum C1Token {
@@ -108,22 +111,28 @@ pub enum C1Token {
     #[regex("[0-9]+")]
     ConstInt,
 
-    #[regex(r"(\d+\.\d+)|(\.\d+([eE]([-+])?\d+)?)|(\d+[eE]([-+])?\d+)")]
+    #[regex(r"(\d+\.\d+|\.\d+)([eE][-+]?\d+)?|\d+[eE][-+]?\d+")]
+    // Malformed numbers are matched as a whole, so that they become a single error token: an
+    // exponent without digits, a second decimal point or a decimal point without digits behind it
+    #[regex(r"(\d+|\d*\.\d+)[eE][-+]?|\d*\.\d+([eE][-+]?\d+)?\.[0-9.]*|\d+[eE][-+]?\d+\.[0-9.]*|\d+\.\.?[0-9.]*", |_| false)]
     ConstFloat,
 
     #[regex("true|false")]
     ConstBoolean,
 
-    #[regex("\"[^\n\"]*\"")]
+    #[token("\"", string)]
     ConstString,
 
     #[regex("[a-zA-Z]+[0-9a-zA-Z]*")]
     Identifier,
 
-    #[regex(r"/\*[^\*/]*\*/", logos::skip)]
+    /// A block comment, which the [C1Lexer] skips after counting its line breaks. An unterminated
+    /// comment is an error
+    #[token("/*", block_comment)]
     CComment,
 
-    #[regex("//[^\n]*(\n)?", logos::skip)]
+    /// The line break behind the comment is lexed on its own, so it is counted
+    #[regex("//[^\n]*", logos::skip)]
     CPPComment,
 
     // We can also use this variant to define whitespace,
@@ -140,10 +149,184 @@ pub enum C1Token {
     Error,
 }
 
+/// Consume a block comment behind its opening `/*`. The content is arbitrary, so the comment ends at
+/// the first `*/`. Without one it extends to the end of the text and is an error.
+fn block_comment(lexer: &mut Lexer<C1Token>) -> bool {
+    match lexer.remainder().find("*/") {
+        Some(end) => {
+            lexer.bump(end + 2);
+            true
+        }
+        None => {
+            lexer.bump(lexer.remainder().len());
+            false
+        }
+    }
+}
+
+/// Return the end of the trivia behind a token that ends at `start`: the whitespace and comments up
+/// to and including the next line break, or up to the next token
+fn trailing_trivia_end(source: &str, start: usize) -> usize {
+    let mut end = start;
+    loop {
+        let rest = &source[end..];
+        end += if rest.starts_with('\n') {
+            return end + 1;
+        } else if rest.starts_with([' ', '\t', '\x0c']) {
+            1
+        } else if rest.starts_with("//") {
+            rest.find('\n').unwrap_or(rest.len())
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            // an unterminated comment is an error token
+            match comment.find("*/") {
+                Some(comment_end) => comment_end + 4,
+                None => return end,
+            }
+        } else {
+            return end;
+        };
+    }
+}
+
+/// Consume a string literal behind its opening `"`. A `\` escapes the character behind it, so `\"`
+/// does not close the literal. Without a closing `"` on the same line, the literal extends to the
+/// end of the line and is an error.
+fn string(lexer: &mut Lexer<C1Token>) -> bool {
+    let mut characters = lexer.remainder().char_indices();
+    while let Some((index, character)) = characters.next() {
+        match character {
+            '"' => {
+                lexer.bump(index + 1);
+                return true;
+            }
+            '\n' => {
+                lexer.bump(index);
+                return false;
+            }
+            '\\' if !lexer.remainder()[index + 1..].starts_with('\n') => {
+                characters.next();
+            }
+            _ => {}
+        }
+    }
+    lexer.bump(lexer.remainder().len());
+    false
+}
+
+/// Decode the value of a literal token with the given text and location
+fn literal(token_type: C1Token, text: &str, span: Span) -> Result<Option<Literal>, Vec<LexError>> {
+    let literal = match token_type {
+        C1Token::ConstInt => match text.parse() {
+            Ok(value) => Literal::Int(value),
+            Err(_) => return Err(vec![LexError { kind: LexErrorKind::IntegerOverflow(text.to_string()), span }]),
+        },
+        C1Token::ConstFloat => Literal::Float(text.parse().expect("every match of the ConstFloat regex is a valid float")),
+        C1Token::ConstBoolean => Literal::Bool(text == "true"),
+        C1Token::ConstString => Literal::String(unescape(&text[1..text.len() - 1], span)?),
+        _ => return Ok(None),
+    };
+    Ok(Some(literal))
+}
+
+/// Replace the escape sequences of the content of a string literal by the characters they stand
+/// for. Reports every invalid escape sequence at its location, given the location of the literal
+fn unescape(content: &str, span: Span) -> Result<String, Vec<LexError>> {
+    let mut value = String::with_capacity(content.len());
+    let mut errors = Vec::new();
+    let mut characters = content.char_indices();
+    while let Some((index, character)) = characters.next() {
+        if character != '\\' {
+            value.push(character);
+            continue;
+        }
+        let escaped = characters.next().map(|(_, escaped)| escaped).expect("the lexer does not end a string after a '\\'");
+        match escaped {
+            'n' => value.push('\n'),
+            't' => value.push('\t'),
+            'r' => value.push('\r'),
+            '0' => value.push('\0'),
+            '\\' | '"' | '\'' => value.push(escaped),
+            _ => {
+                // the content starts behind the opening '"'
+                let start = span.start + 1 + index;
+                let column = span.column + 1 + content[..index].chars().count();
+                let span = Span { start, end: start + 1 + escaped.len_utf8(), line: span.line, column };
+                errors.push(LexError { kind: LexErrorKind::InvalidEscape(escaped), span });
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(value)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Describe why the text of an error token is not a token
+fn classify_error(text: &str) -> LexErrorKind {
+    let mut characters = text.chars();
+    match (characters.next(), characters.next()) {
+        _ if text.starts_with("/*") => LexErrorKind::UnterminatedComment,
+        (Some('"'), _) => LexErrorKind::UnterminatedString,
+        (Some('0'..='9'), _) | (Some('.'), Some('0'..='9')) => LexErrorKind::MalformedNumber(text.to_string()),
+        (Some(character), _) => LexErrorKind::UnexpectedCharacter(character),
+        (None, _) => unreachable!("error tokens are not empty"),
+    }
+}
+
+/// Tokens with a fixed spelling are shown as they appear in the source text, e.g. `';'`, all other
+/// tokens are described by their class, e.g. `identifier`.
+impl fmt::Display for C1Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let spelling = match self {
+            C1Token::KwBoolean => "'bool'",
+            C1Token::KwDo => "'do'",
+            C1Token::KwElse => "'else'",
+            C1Token::KwFloat => "'float'",
+            C1Token::KwFor => "'for'",
+            C1Token::KwIf => "'if'",
+            C1Token::KwInt => "'int'",
+            C1Token::KwPrintf => "'printf'",
+            C1Token::KwReturn => "'return'",
+            C1Token::KwVoid => "'void'",
+            C1Token::KwWhile => "'while'",
+            C1Token::Plus => "'+'",
+            C1Token::Minus => "'-'",
+            C1Token::Asterisk => "'*'",
+            C1Token::Slash => "'/'",
+            C1Token::Assign => "'='",
+            C1Token::Equal => "'=='",
+            C1Token::NotEqual => "'!='",
+            C1Token::Less => "'<'",
+            C1Token::Greater => "'>'",
+            C1Token::LessEqual => "'<='",
+            C1Token::GreaterEqual => "'>='",
+            C1Token::And => "'&&'",
+            C1Token::Or => "'||'",
+            C1Token::Comma => "','",
+            C1Token::Semicolon => "';'",
+            C1Token::LeftParenthesis => "'('",
+            C1Token::RightParenthesis => "')'",
+            C1Token::LeftBrace => "'{'",
+            C1Token::RightBrace => "'}'",
+            C1Token::ConstInt => "integer literal",
+            C1Token::ConstFloat => "float literal",
+            C1Token::ConstBoolean => "boolean literal",
+            C1Token::ConstString => "string literal",
+            C1Token::Identifier => "identifier",
+            C1Token::CComment | C1Token::CPPComment => "comment",
+            C1Token::Whitespace => "whitespace",
+            C1Token::Linebreak => "line break",
+            C1Token::Error => "invalid token",
+        };
+        f.write_str(spelling)
+    }
+}
+
 /// # Overview
 /// Extended lexer based on the logos crate. The lexer keeps track of the current token and the next token
-/// in the lexed text. Furthermore, the lexer keeps track of the line number in which each token is
-/// located, and of the text associated with each token.
+/// in the lexed text, and can look further ahead with [C1Lexer::peek_nth] or return to a [Mark]. Furthermore, the lexer keeps track of the line number in which each token is
+/// located, of its exact [Span], and of the text associated with each token.
 ///
 /// # Examples
 /// ```
@@ -174,27 +357,85 @@ pub enum C1Token {
 /// assert_eq!(lexer.peek_text(), Some("x"));
 /// assert_eq!(lexer.peek_line_number(), Some(2));
 /// ```
+#[derive(Clone)]
 pub struct C1Lexer<'a> {
     logos_lexer: Lexer<'a, C1Token>,
     logos_line_number: usize,
-    current_token: Option<TokenData<'a>>,
-    peek_token: Option<TokenData<'a>>,
+    logos_line_start: usize,
+    /// Ring buffer of lexed tokens, starting with the oldest token that is still needed: the current
+    /// token, or the token of the oldest mark
+    tokens: VecDeque<TokenData<'a>>,
+    /// Number of tokens that have been dropped from the front of the buffer
+    offset: usize,
+    /// Index of the current token, counted from the start of the text
+    position: usize,
+    /// Positions of the marks that have been neither reset nor released
+    marks: Vec<usize>,
+    /// Whether the tokens carry their trivia, see [C1Lexer::with_trivia]
+    trivia: bool,
+    /// End of the trailing trivia of the most recently lexed token
+    trivia_end: usize,
+    /// Trivia behind the last token, once the text has been lexed completely
+    end_trivia: Option<&'a str>,
+}
+
+/// Checkpoint of a [C1Lexer] that it can return to with [C1Lexer::reset]. Every mark has to be given
+/// back with [C1Lexer::reset] or [C1Lexer::release], because the lexer keeps all tokens behind the
+/// oldest mark.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Mark {
+    position: usize,
 }
 
 impl<'a> C1Lexer<'a> {
     /// Initialize a new C1Lexer for the given string slice
-    pub fn new(text: &'a str) -> C1Lexer {
+    pub fn new(text: &'a str) -> C1Lexer<'a> {
+        C1Lexer::create(text, false)
+    }
+
+    /// Private constructor behind [C1Lexer::new] and [C1Lexer::with_trivia]
+    fn create(text: &'a str, trivia: bool) -> C1Lexer<'a> {
         let mut lexer = C1Lexer {
             logos_lexer: C1Token::lexer(text),
             logos_line_number: 1,
-            current_token: None,
-            peek_token: None,
+            logos_line_start: 0,
+            tokens: VecDeque::new(),
+            offset: 0,
+            position: 0,
+            marks: Vec::new(),
+            trivia,
+            trivia_end: 0,
+            end_trivia: None,
         };
-        lexer.current_token = lexer.next_token();
-        lexer.peek_token = lexer.next_token();
+        lexer.fill(1);
         lexer
     }
 
+    /// Initialize a new C1Lexer for the given string slice whose tokens carry the whitespace, line
+    /// breaks and comments around them. The trailing trivia of a token reaches up to and including
+    /// the next line break, everything after that is the leading trivia of the following token.
+    /// Concatenating the trivia and the text of all tokens, followed by the [C1Lexer::end_trivia],
+    /// reproduces the text.
+    /// ```
+    /// use cb_3::C1Lexer;
+    /// let mut lexer = C1Lexer::with_trivia("x = 1; // one\n\n  /* two */ y\n");
+    ///
+    /// assert_eq!(lexer.current_leading_trivia(), Some(""));
+    /// assert_eq!(lexer.current_trailing_trivia(), Some(" "));
+    /// lexer.eat();
+    /// lexer.eat();
+    /// lexer.eat();
+    /// assert_eq!(lexer.current_text(), Some(";"));
+    /// assert_eq!(lexer.current_trailing_trivia(), Some(" // one\n"));
+    /// lexer.eat();
+    /// assert_eq!(lexer.current_leading_trivia(), Some("\n  /* two */ "));
+    /// assert_eq!(lexer.current_trailing_trivia(), Some("\n"));
+    /// assert_eq!(lexer.end_trivia(), Some(""));
+    /// ```
+    pub fn with_trivia(text: &'a str) -> C1Lexer<'a> {
+        C1Lexer::create(text, true)
+    }
+
     /// Return the C1Token variant of the current token without consuming it.
     /// ```
     /// use cb_3::{C1Lexer, C1Token};
@@ -207,7 +448,7 @@ impl<'a> C1Lexer<'a> {
     /// assert_eq!(lexer.current_text(), Some("current"));
     /// ```
     pub fn current_token(&self) -> Option<C1Token> {
-        self.current_token.token_type()
+        self.token(0).token_type()
     }
 
     /// Return the C1Token variant of the next token without consuming it.
@@ -222,27 +463,115 @@ impl<'a> C1Lexer<'a> {
     /// assert_eq!(lexer.peek_text(), Some("next"));
     /// ```
     pub fn peek_token(&self) -> Option<C1Token> {
-        self.peek_token.token_type()
+        self.token(1).token_type()
     }
 
     /// Return the text of the current token
     pub fn current_text(&self) -> Option<&str> {
-        self.current_token.text()
+        self.token(0).text()
     }
 
     /// Return the text of the next token
     pub fn peek_text(&self) -> Option<&str> {
-        self.peek_token.text()
+        self.token(1).text()
     }
 
     /// Return the line number where the current token is located
     pub fn current_line_number(&self) -> Option<usize> {
-        self.current_token.line_number()
+        self.token(0).line_number()
     }
 
     /// Return the line number where the next token is located
     pub fn peek_line_number(&self) -> Option<usize> {
-        self.peek_token.line_number()
+        self.token(1).line_number()
+    }
+
+    /// Return the location of the current token, i.e. its byte range in the text together with the
+    /// line and column of its first character.
+    /// ```
+    /// use cb_3::{C1Lexer, Span};
+    /// let lexer = C1Lexer::new("int main\n  (");
+    ///
+    /// assert_eq!(lexer.current_span(), Some(Span { start: 0, end: 3, line: 1, column: 1 }));
+    /// assert_eq!(lexer.peek_span(), Some(Span { start: 4, end: 8, line: 1, column: 5 }));
+    /// ```
+    pub fn current_span(&self) -> Option<Span> {
+        self.token(0).span()
+    }
+
+    /// Return the location of the next token
+    /// ```
+    /// use cb_3::{C1Lexer, Span};
+    /// let mut lexer = C1Lexer::new("int main\n  (");
+    /// lexer.eat();
+    ///
+    /// assert_eq!(lexer.peek_span(), Some(Span { start: 11, end: 12, line: 2, column: 3 }));
+    /// ```
+    pub fn peek_span(&self) -> Option<Span> {
+        self.token(1).span()
+    }
+
+    /// Return the value of the current token if it is a literal.
+    /// ```
+    /// use cb_3::{C1Lexer, Literal};
+    /// let mut lexer = C1Lexer::new("42 .5e1 true \"a\\tb\" x");
+    ///
+    /// assert_eq!(lexer.current_literal(), Some(&Literal::Int(42)));
+    /// assert_eq!(lexer.peek_literal(), Some(&Literal::Float(5.0)));
+    /// lexer.eat();
+    /// lexer.eat();
+    /// assert_eq!(lexer.current_literal(), Some(&Literal::Bool(true)));
+    /// assert_eq!(lexer.peek_literal(), Some(&Literal::String("a\tb".to_string())));
+    /// lexer.eat();
+    /// lexer.eat();
+    /// assert_eq!(lexer.current_literal(), None);
+    /// ```
+    pub fn current_literal(&self) -> Option<&Literal> {
+        self.token(0)?.token_literal.as_ref()
+    }
+
+    /// Return the value of the next token if it is a literal
+    pub fn peek_literal(&self) -> Option<&Literal> {
+        self.token(1)?.token_literal.as_ref()
+    }
+
+    /// Return the trivia in front of the current token. Without [C1Lexer::with_trivia] it is empty
+    pub fn current_leading_trivia(&self) -> Option<&'a str> {
+        self.token(0).map(|data| data.token_leading_trivia)
+    }
+
+    /// Return the trivia behind the current token. Without [C1Lexer::with_trivia] it is empty
+    pub fn current_trailing_trivia(&self) -> Option<&'a str> {
+        self.token(0).map(|data| data.token_trailing_trivia)
+    }
+
+    /// Return the trivia behind the trailing trivia of the last token, or the whole text if it
+    /// has no tokens. It is known once the last token is the current or the next token.
+    pub fn end_trivia(&self) -> Option<&'a str> {
+        self.end_trivia
+    }
+
+    /// Return the first lexical error if the current token is a [C1Token::Error]
+    pub fn current_error(&self) -> Option<LexError> {
+        self.current_errors().first().cloned()
+    }
+
+    /// Return all lexical errors of the current token. Only a string literal can have more than
+    /// one, for every invalid escape sequence.
+    pub fn current_errors(&self) -> &[LexError] {
+        self.token(0).map_or(&[], |data| &data.token_errors)
+    }
+
+    /// Return the empty location directly behind the last character of the text
+    pub(crate) fn end_span(&self) -> Span {
+        let source = self.logos_lexer.source();
+        let line_start = source.rfind('\n').map_or(0, |index| index + 1);
+        Span {
+            start: source.len(),
+            end: source.len(),
+            line: source.matches('\n').count() + 1,
+            column: source[line_start..].chars().count() + 1,
+        }
     }
 
     /// Drop the current token and retrieve the next token in the text.
@@ -266,8 +595,100 @@ impl<'a> C1Lexer<'a> {
     /// assert_eq!(lexer.peek_text(), None);
     /// ```
     pub fn eat(&mut self) {
-        self.current_token = self.peek_token.take();
-        self.peek_token = self.next_token();
+        if self.token(0).is_some() {
+            self.position += 1;
+        }
+        self.drop_unneeded();
+        self.fill(1);
+    }
+
+    /// Return the C1Token variant of the token `n` tokens behind the current one, so that
+    /// `peek_nth(0)` is the current token and `peek_nth(1)` the next token.
+    /// ```
+    /// use cb_3::{C1Lexer, C1Token};
+    /// let mut lexer = C1Lexer::new("int f(");
+    ///
+    /// assert_eq!(lexer.peek_nth(0), Some(C1Token::KwInt));
+    /// assert_eq!(lexer.peek_nth(2), Some(C1Token::LeftParenthesis));
+    /// assert_eq!(lexer.peek_nth(3), None);
+    /// assert_eq!(lexer.current_token(), Some(C1Token::KwInt));
+    /// ```
+    pub fn peek_nth(&mut self, n: usize) -> Option<C1Token> {
+        self.fill(n);
+        self.token(n).token_type()
+    }
+
+    /// Remember the current position, so that [C1Lexer::reset] can return to it after tokens have
+    /// been eaten speculatively.
+    /// ```
+    /// use cb_3::C1Lexer;
+    /// let mut lexer = C1Lexer::new("a b c");
+    /// lexer.eat();
+    ///
+    /// let mark = lexer.mark();
+    /// lexer.eat();
+    /// lexer.eat();
+    /// assert_eq!(lexer.current_text(), None);
+    ///
+    /// lexer.reset(mark);
+    /// assert_eq!(lexer.current_text(), Some("b"));
+    /// assert_eq!(lexer.peek_text(), Some("c"));
+    /// ```
+    pub fn mark(&mut self) -> Mark {
+        self.marks.push(self.position);
+        Mark { position: self.position }
+    }
+
+    /// Return to the position of the mark, giving the mark back
+    pub fn reset(&mut self, mark: Mark) {
+        self.position = mark.position;
+        self.release(mark);
+    }
+
+    /// Give a mark back without returning to it, once the speculatively eaten tokens are accepted
+    pub fn release(&mut self, mark: Mark) {
+        let index = self.marks.iter().rposition(|&position| position == mark.position).expect("a mark is given back once");
+        self.marks.swap_remove(index);
+        self.drop_unneeded();
+    }
+
+    /// Drop the tokens before the current one that no mark needs anymore
+    fn drop_unneeded(&mut self) {
+        let keep = self.marks.iter().copied().min().unwrap_or(self.position).min(self.position);
+        while self.offset < keep {
+            self.tokens.pop_front();
+            self.offset += 1;
+        }
+    }
+
+    /// Return the buffered token `n` tokens behind the current one
+    fn token(&self, n: usize) -> Option<&TokenData<'a>> {
+        self.tokens.get(self.position - self.offset + n)
+    }
+
+    /// Lex ahead until the buffer holds the token `n` tokens behind the current one, or the text ends
+    fn fill(&mut self, n: usize) {
+        while self.tokens.len() <= self.position - self.offset + n {
+            match self.next_token() {
+                Some(mut data) => {
+                    if self.trivia {
+                        let source = self.logos_lexer.source();
+                        let span = data.token_span;
+                        let trivia_end = trailing_trivia_end(source, span.end);
+                        data.token_leading_trivia = &source[self.trivia_end..span.start];
+                        data.token_trailing_trivia = &source[span.end..trivia_end];
+                        self.trivia_end = trivia_end;
+                    }
+                    self.tokens.push_back(data)
+                }
+                None => {
+                    if self.trivia {
+                        self.end_trivia = Some(&self.logos_lexer.source()[self.trivia_end..]);
+                    }
+                    break;
+                }
+            }
+        }
     }
 
     /// Private method for reading the next token from the logos::Lexer and extracting the required data
@@ -279,40 +700,126 @@ impl<'a> C1Lexer<'a> {
                 C1Token::Linebreak => {
                     // If the token is a linebreak, increase the line number and get the next token
                     self.logos_line_number += 1;
+                    self.logos_line_start = self.logos_lexer.span().end;
+                    self.next_token()
+                }
+                C1Token::CComment => {
+                    // A block comment is skipped, but the line breaks inside of it are counted
+                    self.count_lines();
                     self.next_token()
                 }
-                _ => Some(TokenData {
-                    // If the token is not a linebreak, initialize and return a TokenData instance
-                    token_type: c1_token,
-                    token_text: self.logos_lexer.slice(),
-                    token_line: self.logos_line_number,
-                }),
+                C1Token::Error => {
+                    let mut data = self.token_data(c1_token);
+                    data.token_errors = vec![LexError { kind: classify_error(data.token_text), span: data.token_span }];
+                    // an unterminated comment can span lines
+                    self.count_lines();
+                    Some(data)
+                }
+                // If the token is not a linebreak, initialize and return a TokenData instance. A
+                // literal whose value cannot be decoded becomes an error
+                _ => {
+                    let mut data = self.token_data(c1_token);
+                    match literal(c1_token, data.token_text, data.token_span) {
+                        Ok(literal) => data.token_literal = literal,
+                        Err(errors) => {
+                            data.token_type = C1Token::Error;
+                            data.token_errors = errors;
+                        }
+                    }
+                    Some(data)
+                }
             }
         } else {
             None
         }
     }
+
+    /// Count the line breaks inside the token the logos::Lexer is at
+    fn count_lines(&mut self) {
+        let start = self.logos_lexer.span().start;
+        for (index, _) in self.logos_lexer.slice().match_indices('\n') {
+            self.logos_line_number += 1;
+            self.logos_line_start = start + index + 1;
+        }
+    }
+
+    /// Extract the data of the token the logos::Lexer is at
+    fn token_data(&self, token_type: C1Token) -> TokenData<'a> {
+        let range = self.logos_lexer.span();
+        let source = self.logos_lexer.source();
+        TokenData {
+            token_type,
+            token_text: self.logos_lexer.slice(),
+            token_line: self.logos_line_number,
+            token_span: Span {
+                start: range.start,
+                end: range.end,
+                line: self.logos_line_number,
+                column: source[self.logos_line_start..range.start].chars().count() + 1,
+            },
+            token_literal: None,
+            token_errors: Vec::new(),
+            token_leading_trivia: "",
+            token_trailing_trivia: "",
+        }
+    }
 }
 
 /// Hidden struct for capsuling the data associated with a token.
+#[derive(Clone)]
 struct TokenData<'a> {
     token_type: C1Token,
     token_text: &'a str,
     token_line: usize,
+    token_span: Span,
+    /// Value of a literal
+    token_literal: Option<Literal>,
+    /// Why the token is a [C1Token::Error]
+    token_errors: Vec<LexError>,
+    /// Whitespace, line breaks and comments in front of the token, in trivia mode
+    token_leading_trivia: &'a str,
+    /// Whitespace and comments behind the token up to the next line break, in trivia mode
+    token_trailing_trivia: &'a str,
+}
+
+/// The value of a literal token
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Int(i64),
+    /// Floats that are too large for `f64` are infinite
+    Float(f64),
+    Bool(bool),
+    /// The content of a string literal, with its escape sequences replaced
+    String(String),
+}
+
+/// Location of a token in the lexed text
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Default)]
+pub struct Span {
+    /// Byte offset of the first character of the token
+    pub start: usize,
+    /// Byte offset directly behind the last character of the token
+    pub end: usize,
+    /// Line of the first character, starting at 1
+    pub line: usize,
+    /// Column of the first character within its line, counted in characters and starting at 1
+    pub column: usize,
 }
 
 /// Hidden trait that makes it possible to implemented the required getter functionality directly for
-/// Option<TokenData>.
+/// Option<&TokenData>.
 trait TokenDataProvider<'a> {
     /// Return the type of the token, aka. its C1Token variant.
     fn token_type(&self) -> Option<C1Token>;
     /// Return the text of the token
-    fn text(&self) -> Option<&str>;
+    fn text(&self) -> Option<&'a str>;
     /// Return the line number of the token
     fn line_number(&self) -> Option<usize>;
+    /// Return the location of the token
+    fn span(&self) -> Option<Span>;
 }
 
-impl<'a> TokenDataProvider<'a> for Option<TokenData<'a>> {
+impl<'a> TokenDataProvider<'a> for Option<&TokenData<'a>> {
     fn token_type(&self) -> Option<C1Token> {
         self.as_ref().map(|data| data.token_type)
     }
@@ -324,12 +831,16 @@ impl<'a> TokenDataProvider<'a> for Option<TokenData<'a>> {
     fn line_number(&self) -> Option<usize> {
         self.as_ref().map(|data| data.token_line)
     }
+
+    fn span(&self) -> Option<Span> {
+        self.as_ref().map(|data| data.token_span)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::lexer::C1Lexer;
-    use crate::C1Token;
+    use crate::lexer::{C1Lexer, Literal, Span};
+    use crate::{C1Token, LexError, LexErrorKind};
 
     #[test]
     fn lines_are_counted() {
@@ -384,4 +895,280 @@ mod tests {
         let lexer = C1Lexer::new("33E+2");
         assert_eq!(lexer.current_token(), Some(C1Token::ConstFloat));
     }
+
+    #[test]
+    fn spans_are_tracked() {
+        let mut lexer = C1Lexer::new("a = 1;\n\tif (ä==b)");
+        assert_eq!(lexer.current_span(), Some(Span { start: 0, end: 1, line: 1, column: 1 }));
+        for _ in 0..4 {
+            lexer.eat();
+        }
+        // 'if' follows a tab on the second line
+        assert_eq!(lexer.current_span(), Some(Span { start: 8, end: 10, line: 2, column: 2 }));
+        lexer.eat();
+        lexer.eat();
+        // the non-ASCII character is lexed as an error token, but columns count it only once
+        assert_eq!(lexer.current_span(), Some(Span { start: 12, end: 14, line: 2, column: 6 }));
+        assert_eq!(lexer.peek_span(), Some(Span { start: 14, end: 16, line: 2, column: 7 }));
+    }
+
+    #[test]
+    fn block_comments_may_contain_anything() {
+        let mut lexer = C1Lexer::new("a /* a*b / c **/ b /** doc\n * comment\n */ c /*/ still a comment */ d");
+        let mut names = Vec::new();
+        while let Some(text) = lexer.current_text() {
+            assert_eq!(lexer.current_token(), Some(C1Token::Identifier));
+            names.push(text.to_string());
+            lexer.eat();
+        }
+        assert_eq!(names, ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn lines_are_counted_inside_comments() {
+        let mut lexer = C1Lexer::new("a /* one\ntwo\n  */ b // three\n  c");
+        lexer.eat();
+        assert_eq!(lexer.current_span(), Some(Span { start: 18, end: 19, line: 3, column: 6 }));
+        assert_eq!(lexer.peek_span(), Some(Span { start: 31, end: 32, line: 4, column: 3 }));
+    }
+
+    /// Lex the whole text and return the errors
+    fn errors(text: &str) -> Vec<LexError> {
+        let mut lexer = C1Lexer::new(text);
+        let mut errors = Vec::new();
+        while lexer.current_token().is_some() {
+            errors.extend_from_slice(lexer.current_errors());
+            lexer.eat();
+        }
+        errors
+    }
+
+    #[test]
+    fn unterminated_comment_is_an_error() {
+        let mut lexer = C1Lexer::new("a\n  /* no end\n*");
+        lexer.eat();
+        assert_eq!(lexer.current_token(), Some(C1Token::Error));
+        let span = Span { start: 4, end: 15, line: 2, column: 3 };
+        assert_eq!(lexer.current_error(), Some(LexError { kind: LexErrorKind::UnterminatedComment, span }));
+        assert_eq!(lexer.peek_token(), None);
+
+        // the "*/" needs its own "*"
+        assert_eq!(errors("/*/")[0].kind, LexErrorKind::UnterminatedComment);
+    }
+
+    #[test]
+    fn lexing_continues_after_errors() {
+        let errors = errors("a $ \"text\" @b;\n\"open\nc = 1e + 1.2.3 + 4.;\n.5e-");
+        let found: Vec<(LexErrorKind, usize, usize)> = errors.into_iter().map(|error| (error.kind, error.span.line, error.span.column)).collect();
+        assert_eq!(
+            found,
+            [
+                (LexErrorKind::UnexpectedCharacter('$'), 1, 3),
+                (LexErrorKind::UnexpectedCharacter('@'), 1, 12),
+                (LexErrorKind::UnterminatedString, 2, 1),
+                (LexErrorKind::MalformedNumber("1e".to_string()), 3, 5),
+                (LexErrorKind::MalformedNumber("1.2.3".to_string()), 3, 10),
+                (LexErrorKind::MalformedNumber("4.".to_string()), 3, 18),
+                (LexErrorKind::MalformedNumber(".5e-".to_string()), 4, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn valid_numbers_are_not_malformed() {
+        for text in ["1", "1.5", ".5", "1.5e3", "1.5E+3", ".5e-3", "2e10"] {
+            let lexer = C1Lexer::new(text);
+            assert_eq!(lexer.current_text(), Some(text));
+            assert_eq!(lexer.current_error(), None);
+        }
+        // a number ends before a letter that cannot start an exponent
+        let lexer = C1Lexer::new("1x");
+        assert_eq!(lexer.current_token(), Some(C1Token::ConstInt));
+        assert_eq!(lexer.peek_text(), Some("x"));
+    }
+
+    #[test]
+    fn end_span_points_behind_the_text() {
+        let lexer = C1Lexer::new("x\nyz ");
+        assert_eq!(lexer.end_span(), Span { start: 5, end: 5, line: 2, column: 4 });
+    }
+
+    fn literals(text: &str) -> Vec<Literal> {
+        let mut lexer = C1Lexer::new(text);
+        let mut literals = Vec::new();
+        while lexer.current_token().is_some() {
+            literals.extend(lexer.current_literal().cloned());
+            lexer.eat();
+        }
+        literals
+    }
+
+    #[test]
+    fn literals_are_decoded() {
+        assert_eq!(
+            literals("x = 0 + 007 + 9223372036854775807; b = true != false;"),
+            [Literal::Int(0), Literal::Int(7), Literal::Int(i64::MAX), Literal::Bool(true), Literal::Bool(false)]
+        );
+        let floats = literals("1.5 .25 2.5e3 .5E-1 1e+2 3E0 1e400");
+        assert_eq!(floats[..6], [1.5, 0.25, 2500.0, 0.05, 100.0, 3.0].map(Literal::Float));
+        assert_eq!(floats[6], Literal::Float(f64::INFINITY));
+    }
+
+    #[test]
+    fn strings_are_unescaped() {
+        assert_eq!(
+            literals(r#""" "plain" "a\tb\nc" "\"quoted\"" "\\" "\'\r\0" "ä\\n""#),
+            ["", "plain", "a\tb\nc", "\"quoted\"", "\\", "'\r\0", "ä\\n"].map(|text| Literal::String(text.to_string()))
+        );
+    }
+
+    #[test]
+    fn integer_overflow_is_an_error() {
+        let mut lexer = C1Lexer::new("x = 9223372036854775808;");
+        lexer.eat();
+        lexer.eat();
+        assert_eq!(lexer.current_token(), Some(C1Token::Error));
+        assert_eq!(lexer.current_literal(), None);
+        let error = lexer.current_error().unwrap();
+        assert_eq!(error.kind, LexErrorKind::IntegerOverflow("9223372036854775808".to_string()));
+        assert_eq!(error.to_string(), "line 1, column 5: integer literal 9223372036854775808 does not fit into 64 bits");
+    }
+
+    #[test]
+    fn invalid_escapes_are_reported_at_their_position() {
+        let errors = errors("a;\n  \"ä\\q and \\\\ \\%\" \"\\\"\" b");
+        let found: Vec<(LexErrorKind, Span)> = errors.into_iter().map(|error| (error.kind, error.span)).collect();
+        assert_eq!(
+            found,
+            [
+                (LexErrorKind::InvalidEscape('q'), Span { start: 8, end: 10, line: 2, column: 5 }),
+                (LexErrorKind::InvalidEscape('%'), Span { start: 18, end: 20, line: 2, column: 15 }),
+            ]
+        );
+        assert_eq!(C1Lexer::new("\"\\q\"").current_error().unwrap().to_string(), "line 1, column 2: invalid escape sequence '\\q'");
+    }
+
+    #[test]
+    fn escaped_quotes_do_not_close_strings() {
+        let mut lexer = C1Lexer::new("\"a\\\" b\" \"c\\\nd\"");
+        assert_eq!(lexer.current_literal(), Some(&Literal::String("a\" b".to_string())));
+        lexer.eat();
+        assert_eq!(lexer.current_error().unwrap().kind, LexErrorKind::UnterminatedString);
+    }
+
+    #[test]
+    fn peeking_ahead_does_not_change_the_current_tokens() {
+        let mut lexer = C1Lexer::new("a\nb c\n/* d */ e");
+        assert_eq!(lexer.peek_nth(3), Some(C1Token::Identifier));
+        assert_eq!(lexer.peek_nth(4), None);
+        assert_eq!(lexer.current_text(), Some("a"));
+        assert_eq!(lexer.peek_text(), Some("b"));
+        lexer.eat();
+        lexer.eat();
+        assert_eq!(lexer.current_span(), Some(Span { start: 4, end: 5, line: 2, column: 3 }));
+        assert_eq!(lexer.peek_span(), Some(Span { start: 14, end: 15, line: 3, column: 9 }));
+        assert_eq!(lexer.peek_nth(2), None);
+        lexer.eat();
+        lexer.eat();
+        assert_eq!(lexer.peek_nth(0), None);
+        lexer.eat();
+        assert_eq!(lexer.current_token(), None);
+    }
+
+    #[test]
+    fn marks_can_be_nested() {
+        let mut lexer = C1Lexer::new("a b c d e");
+        let outer = lexer.mark();
+        lexer.eat();
+        let inner = lexer.mark();
+        lexer.eat();
+        lexer.eat();
+        lexer.reset(inner);
+        assert_eq!(lexer.current_text(), Some("b"));
+        lexer.eat();
+        lexer.reset(outer);
+        assert_eq!(lexer.current_text(), Some("a"));
+        assert_eq!(lexer.peek_text(), Some("b"));
+    }
+
+    #[test]
+    fn released_tokens_are_dropped() {
+        let mut lexer = C1Lexer::new("a b c d");
+        let mark = lexer.mark();
+        lexer.eat();
+        lexer.eat();
+        assert_eq!(lexer.tokens.len(), 4);
+        lexer.release(mark);
+        assert_eq!(lexer.tokens.len(), 2);
+        assert_eq!(lexer.current_text(), Some("c"));
+        // a mark at the end of the text still works
+        lexer.eat();
+        lexer.eat();
+        let mark = lexer.mark();
+        lexer.eat();
+        lexer.reset(mark);
+        assert_eq!(lexer.current_token(), None);
+        assert!(lexer.marks.is_empty());
+    }
+
+    /// Concatenate all tokens with their trivia
+    fn reconstruct(text: &str) -> String {
+        let mut lexer = C1Lexer::with_trivia(text);
+        let mut result = String::new();
+        while let Some(token) = lexer.current_text() {
+            result.push_str(lexer.current_leading_trivia().unwrap());
+            result.push_str(token);
+            result.push_str(lexer.current_trailing_trivia().unwrap());
+            lexer.eat();
+        }
+        result + lexer.end_trivia().unwrap()
+    }
+
+    #[test]
+    fn trivia_is_attached_to_tokens() {
+        let mut lexer = C1Lexer::with_trivia("  /* a\n b */ x /*c*/ // d\n\ny /* e\n */\n$ ");
+        assert_eq!(lexer.current_leading_trivia(), Some("  /* a\n b */ "));
+        assert_eq!(lexer.current_trailing_trivia(), Some(" /*c*/ // d\n"));
+        assert_eq!(lexer.end_trivia(), None);
+        lexer.eat();
+        // a comment across lines ends the trailing trivia only at the line break behind it
+        assert_eq!(lexer.current_leading_trivia(), Some("\n"));
+        assert_eq!(lexer.current_trailing_trivia(), Some(" /* e\n */\n"));
+        lexer.eat();
+        assert_eq!(lexer.current_token(), Some(C1Token::Error));
+        assert_eq!((lexer.current_leading_trivia(), lexer.current_trailing_trivia()), (Some(""), Some(" ")));
+        assert_eq!(lexer.end_trivia(), Some(""));
+
+        assert_eq!(C1Lexer::with_trivia(" // only\n").end_trivia(), Some(" // only\n"));
+        // without trivia mode, there is none
+        let lexer = C1Lexer::new(" x ");
+        assert_eq!((lexer.current_leading_trivia(), lexer.current_trailing_trivia(), lexer.end_trivia()), (Some(""), Some(""), None));
+    }
+
+    #[test]
+    fn trivia_reconstructs_the_corpus() {
+        let mut corpus = Vec::new();
+        for directory in ["tests/data", "tests", "src"] {
+            for entry in std::fs::read_dir(directory).unwrap() {
+                let path = entry.unwrap().path();
+                if let Ok(text) = std::fs::read_to_string(&path) {
+                    corpus.push((path, text));
+                }
+            }
+        }
+        assert!(corpus.iter().any(|(path, _)| path.ends_with("beispiel.c-1")));
+        for (path, text) in &corpus {
+            assert_eq!(&reconstruct(text), text, "{} is not reconstructed", path.display());
+        }
+
+        // every prefix and every insertion of a fragment that changes how the rest is lexed
+        let example = std::fs::read_to_string("tests/data/beispiel.c-1").unwrap();
+        for (index, _) in example.char_indices() {
+            assert_eq!(reconstruct(&example[..index]), example[..index]);
+            for fragment in ["/*", "*/", "//", "\"", "\\", "\n", " ", "\r", "1.", "ä"] {
+                let text = format!("{}{}{}", &example[..index], fragment, &example[index..]);
+                assert_eq!(reconstruct(&text), text);
+            }
+        }
+    }
 }
@@ -1,12 +1,32 @@
+pub use error::{LexError, LexErrorKind, ParseError, ParseErrorKind};
 pub use lexer::C1Lexer;
 pub use lexer::C1Token;
+pub use lexer::Literal;
+pub use lexer::Mark;
+pub use lexer::Span;
 pub use parser::C1Parser;
 
+pub mod analysis;
+pub mod ast;
+pub mod bytecode;
+pub mod c99;
+pub mod c1b;
+mod error;
+pub mod flow;
+pub mod init;
+pub mod interpreter;
 mod lexer;
+pub mod llvm;
 
-// Type definition for the Result that is being used by the parser. You may change it to anything
-// you want
-pub type ParseResult = Result<(), String>;
+// Type definition for the Result that is being used by the parser. Without a type argument it is
+// the result of parsing a whole program.
+pub type ParseResult<T = ast::Program> = Result<T, ParseError>;
 
 // You will need a re-export of your C1Parser definition. Here is an example:
 mod parser;
+pub mod resolve;
+pub mod symbols;
+pub mod typeck;
+pub mod vm;
+pub mod wasm;
+pub mod x86_64;
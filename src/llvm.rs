@@ -0,0 +1,458 @@
+//! Backend that translates C(-1) programs to textual LLVM IR.
+//!
+//! The generated module can be compiled with `llc` or `clang` and linked with the C library:
+//! - `int` is `i32`, `float` is `double` and `bool` is `i1`. Arithmetic on `i32` wraps around
+//! - every variable gets an `alloca` in the entry block of its function, global variables are
+//!   internal globals. Values are named `%<name>.<slot>` for variables and `%<name>.arg` for the
+//!   incoming parameters, so they cannot collide with the numbered temporaries and the labels
+//! - relational operators become `icmp` or `fcmp`. `fcmp` uses the ordered predicates, except for
+//!   `!=`, which is true if an operand is NaN
+//! - `&&` and `||` branch around their right operand and merge the result with a `phi`
+//! - every function definition becomes a function `@c1.<name>`, and the module gets a C `main`
+//!   function that initializes the global variables and calls the `main` function of the program
+//! - `printf` calls runtime functions that are part of the module and print like the
+//!   [interpreter](crate::interpreter), an integer division by zero prints its runtime error to
+//!   stderr and exits with status 1
+//!
+//! The program has to pass every [check](crate::analysis::analyze).
+
+use crate::analysis::{analyze, Analysis, Diagnostic};
+use crate::ast::{Assignment, BinaryOp, Block, Expr, ExprKind, FunctionCall, FunctionDefinition, Identifier, Program, Statement, StatementKind, Type, UnaryOp};
+use crate::resolve::BindingKind;
+use crate::symbols::FunctionTable;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+const RUNTIME: &str = r#"
+@.format_int = private unnamed_addr constant [4 x i8] c"%d\0A\00"
+@.format_float = private unnamed_addr constant [4 x i8] c"%f\0A\00"
+@.nan = private unnamed_addr constant [4 x i8] c"nan\00"
+@.true = private unnamed_addr constant [5 x i8] c"true\00"
+@.false = private unnamed_addr constant [6 x i8] c"false\00"
+@.format_division = private unnamed_addr constant [38 x i8] c"line %d, column %d: division by zero\0A\00"
+
+declare i32 @printf(ptr, ...)
+declare i32 @puts(ptr)
+declare i32 @dprintf(i32, ptr, ...)
+declare void @exit(i32) noreturn
+
+define private void @c1_print_int(i32 %value) {
+  call i32 (ptr, ...) @printf(ptr @.format_int, i32 %value)
+  ret void
+}
+
+define private void @c1_print_float(double %value) {
+  %is_nan = fcmp uno double %value, %value
+  br i1 %is_nan, label %nan, label %number
+nan:
+  call i32 @puts(ptr @.nan)
+  ret void
+number:
+  call i32 (ptr, ...) @printf(ptr @.format_float, double %value)
+  ret void
+}
+
+define private void @c1_print_bool(i1 %value) {
+  %text = select i1 %value, ptr @.true, ptr @.false
+  call i32 @puts(ptr %text)
+  ret void
+}
+
+define private i32 @c1_div(i32 %lhs, i32 %rhs, i32 %line, i32 %column) {
+  %is_zero = icmp eq i32 %rhs, 0
+  br i1 %is_zero, label %zero, label %nonzero
+zero:
+  call i32 (i32, ptr, ...) @dprintf(i32 2, ptr @.format_division, i32 %line, i32 %column)
+  call void @exit(i32 1)
+  unreachable
+nonzero:
+  %is_minus_one = icmp eq i32 %rhs, -1
+  br i1 %is_minus_one, label %negate, label %divide
+negate:
+  %negated = sub i32 0, %lhs
+  ret i32 %negated
+divide:
+  %quotient = sdiv i32 %lhs, %rhs
+  ret i32 %quotient
+}
+"#;
+
+/// Translate a program to LLVM IR.
+///
+/// Returns the errors of the checks if the program is not correct.
+/// ```
+/// use cb_3::C1Parser;
+/// use cb_3::llvm::generate;
+///
+/// let program = C1Parser::parse("bool f(float x) { return x < 2; } void main() { printf(f(1.5)); }").unwrap();
+/// let ir = generate(&program).unwrap();
+/// assert!(ir.contains("define i1 @c1.f(double %x.arg) {\nentry:\n  %x.0 = alloca double\n"));
+/// assert!(ir.contains("  %2 = fcmp olt double %0, %1\n  ret i1 %2\n"));
+/// ```
+pub fn generate(program: &Program) -> Result<String, Vec<Diagnostic>> {
+    let analysis = analyze(program)?;
+    let mut generator = Generator {
+        functions: FunctionTable::new(program),
+        analysis,
+        body: String::new(),
+        variables: BTreeMap::new(),
+        values: 0,
+        labels: 0,
+        block: String::new(),
+        terminated: false,
+    };
+    let mut ir = String::new();
+    for (slot, declaration) in program.globals.iter().enumerate() {
+        let ty = declaration.ty;
+        writeln!(ir, "@c1.global.{} = internal global {} {}", slot, ir_type(ty), zero(ty)).unwrap();
+    }
+
+    for function in &program.functions {
+        let parameters: Vec<String> =
+            function.parameters.iter().map(|parameter| format!("{} %{}.arg", ir_type(parameter.ty), parameter.name.name)).collect();
+        writeln!(ir, "\ndefine {} @c1.{}({}) {{", ir_type(function.return_type), function.name.name, parameters.join(", ")).unwrap();
+        generator.start();
+        for parameter in &function.parameters {
+            let (pointer, ty) = generator.variable(&parameter.name);
+            generator.instruction(format_args!("store {} %{}.arg, ptr {}", ir_type(ty), parameter.name.name, pointer));
+        }
+        generator.block(&function.body, function);
+        if !generator.terminated {
+            match function.return_type {
+                Type::Void => generator.terminate(format_args!("ret void")),
+                // every path returns, so this block cannot be reached
+                _ => generator.terminate(format_args!("unreachable")),
+            }
+        }
+        generator.finish(&mut ir);
+    }
+
+    ir.push_str("\ndefine private void @c1_init() {\n");
+    generator.start();
+    for declaration in &program.globals {
+        if let Some(value) = &declaration.value {
+            let value = generator.converted(value, declaration.ty);
+            generator.store(&declaration.name, &value);
+        }
+    }
+    generator.terminate(format_args!("ret void"));
+    generator.finish(&mut ir);
+
+    ir.push_str("\ndefine i32 @main() {\n  call void @c1_init()\n");
+    match generator.functions.get("main").map(|main| main.return_type) {
+        Some(Type::Int) => ir.push_str("  %status = call i32 @c1.main()\n  ret i32 %status\n}\n"),
+        _ => ir.push_str("  call void @c1.main()\n  ret i32 0\n}\n"),
+    }
+    ir.push_str(RUNTIME);
+    Ok(ir)
+}
+
+fn ir_type(ty: Type) -> &'static str {
+    match ty {
+        Type::Boolean => "i1",
+        Type::Float => "double",
+        Type::Int => "i32",
+        Type::Void => "void",
+    }
+}
+
+fn zero(ty: Type) -> &'static str {
+    match ty {
+        Type::Boolean => "false",
+        Type::Float => "0.0",
+        _ => "0",
+    }
+}
+
+struct Generator<'p> {
+    functions: FunctionTable<'p>,
+    analysis: Analysis,
+    /// Instructions of the function that is generated, after its entry label
+    body: String,
+    /// Names and types of the local variables of the function, by their slot
+    variables: BTreeMap<usize, (String, Type)>,
+    /// Number of temporaries of the function
+    values: usize,
+    /// Number of labels of the program
+    labels: usize,
+    /// Label of the block that is generated
+    block: String,
+    /// Whether the current block already ended with a terminator
+    terminated: bool,
+}
+
+impl Generator<'_> {
+    fn ty(&self, expr: &Expr) -> Type {
+        self.analysis.types.expr(expr)
+    }
+
+    fn start(&mut self) {
+        self.block = "entry".to_string();
+        self.terminated = false;
+        self.values = 0;
+    }
+
+    /// Write the allocas and the blocks of the function that was generated, and close its body
+    fn finish(&mut self, ir: &mut String) {
+        ir.push_str("entry:\n");
+        for (name, ty) in std::mem::take(&mut self.variables).into_values() {
+            writeln!(ir, "  {} = alloca {}", name, ir_type(ty)).unwrap();
+        }
+        ir.push_str(&std::mem::take(&mut self.body));
+        ir.push_str("}\n");
+    }
+
+    fn label(&mut self, kind: &str) -> String {
+        self.labels += 1;
+        format!("{}{}", kind, self.labels)
+    }
+
+    /// Start the block with the label, falling through from the current block
+    fn place(&mut self, label: &str) {
+        if !self.terminated {
+            writeln!(self.body, "  br label %{}", label).unwrap();
+        }
+        writeln!(self.body, "{}:", label).unwrap();
+        self.block = label.to_string();
+        self.terminated = false;
+    }
+
+    fn instruction(&mut self, instruction: std::fmt::Arguments<'_>) {
+        if self.terminated {
+            // the code after a return statement is never executed, but it needs a block
+            let label = self.label("dead");
+            self.place(&label);
+        }
+        writeln!(self.body, "  {}", instruction).unwrap();
+    }
+
+    /// Emit an instruction that computes a value and return the name of the value
+    fn value(&mut self, instruction: std::fmt::Arguments<'_>) -> String {
+        let name = format!("%{}", self.values);
+        self.instruction(format_args!("{} = {}", name, instruction));
+        self.values += 1;
+        name
+    }
+
+    fn terminate(&mut self, instruction: std::fmt::Arguments<'_>) {
+        self.instruction(instruction);
+        self.terminated = true;
+    }
+
+    /// Return the pointer to the variable the identifier refers to and its type
+    fn variable(&mut self, identifier: &Identifier) -> (String, Type) {
+        let id = self.analysis.resolution.lookup(identifier).expect("the program is resolved");
+        let binding = self.analysis.resolution.binding(id);
+        let ty = self.analysis.types.variable(id);
+        if binding.kind == BindingKind::Global {
+            return (format!("@c1.global.{}", binding.slot), ty);
+        }
+        let name = format!("%{}.{}", binding.name, binding.slot);
+        self.variables.insert(binding.slot, (name.clone(), ty));
+        (name, ty)
+    }
+
+    fn store(&mut self, identifier: &Identifier, value: &str) {
+        let (pointer, ty) = self.variable(identifier);
+        self.instruction(format_args!("store {} {}, ptr {}", ir_type(ty), value, pointer));
+    }
+
+    /// Generate an expression and convert its value to the type
+    fn converted(&mut self, expr: &Expr, ty: Type) -> String {
+        let value = self.expr(expr);
+        self.convert(value, self.ty(expr), ty)
+    }
+
+    fn convert(&mut self, value: String, from: Type, to: Type) -> String {
+        if from == Type::Int && to == Type::Float {
+            self.value(format_args!("sitofp i32 {} to double", value))
+        } else {
+            value
+        }
+    }
+
+    fn block(&mut self, block: &Block, function: &FunctionDefinition) {
+        for statement in &block.statements {
+            self.statement(statement, function);
+        }
+    }
+
+    /// Branch to the first label if the condition is true and to the second one otherwise
+    fn branch(&mut self, condition: &Expr, then_label: &str, else_label: &str) {
+        let condition = self.expr(condition);
+        self.terminate(format_args!("br i1 {}, label %{}, label %{}", condition, then_label, else_label));
+    }
+
+    fn statement(&mut self, statement: &Statement, function: &FunctionDefinition) {
+        match &statement.kind {
+            StatementKind::Block(block) => self.block(block, function),
+            StatementKind::Declaration(declaration) => match &declaration.value {
+                Some(value) => {
+                    let value = self.converted(value, declaration.ty);
+                    self.store(&declaration.name, &value);
+                }
+                None => {
+                    self.variable(&declaration.name);
+                }
+            },
+            StatementKind::If { condition, then_branch, else_branch } => {
+                let (then_label, else_label, end) = (self.label("then"), self.label("else"), self.label("end"));
+                let else_target = if else_branch.is_some() { &else_label } else { &end };
+                self.branch(condition, &then_label, else_target);
+                self.place(&then_label);
+                self.statement(then_branch, function);
+                if let Some(else_branch) = else_branch {
+                    if !self.terminated {
+                        self.terminate(format_args!("br label %{}", end));
+                    }
+                    self.place(&else_label);
+                    self.statement(else_branch, function);
+                }
+                self.place(&end);
+            }
+            StatementKind::For { init, condition, step, body } => {
+                let (loop_label, body_label, end) = (self.label("loop"), self.label("body"), self.label("end"));
+                self.assignment(init);
+                self.place(&loop_label);
+                self.branch(condition, &body_label, &end);
+                self.place(&body_label);
+                self.statement(body, function);
+                self.assignment(step);
+                self.terminate(format_args!("br label %{}", loop_label));
+                self.place(&end);
+            }
+            StatementKind::While { condition, body } => {
+                let (loop_label, body_label, end) = (self.label("loop"), self.label("body"), self.label("end"));
+                self.place(&loop_label);
+                self.branch(condition, &body_label, &end);
+                self.place(&body_label);
+                self.statement(body, function);
+                self.terminate(format_args!("br label %{}", loop_label));
+                self.place(&end);
+            }
+            StatementKind::DoWhile { body, condition } => {
+                let (body_label, end) = (self.label("body"), self.label("end"));
+                self.place(&body_label);
+                self.statement(body, function);
+                self.branch(condition, &body_label, &end);
+                self.place(&end);
+            }
+            StatementKind::Return(Some(value)) => {
+                let value = self.converted(value, function.return_type);
+                self.terminate(format_args!("ret {} {}", ir_type(function.return_type), value));
+            }
+            StatementKind::Return(None) => self.terminate(format_args!("ret void")),
+            StatementKind::Printf(value) => {
+                let ty = self.ty(value);
+                let value = self.expr(value);
+                let function = match ty {
+                    Type::Float => "c1_print_float",
+                    Type::Boolean => "c1_print_bool",
+                    _ => "c1_print_int",
+                };
+                self.instruction(format_args!("call void @{}({} {})", function, ir_type(ty), value));
+            }
+            StatementKind::Assignment(assignment) => {
+                self.assignment(assignment);
+            }
+            StatementKind::Call(call) => {
+                self.call(call);
+            }
+        }
+    }
+
+    /// Store the value of the assignment and return it
+    fn assignment(&mut self, assignment: &Assignment) -> String {
+        let (_, ty) = self.variable(&assignment.target);
+        let value = self.converted(&assignment.value, ty);
+        self.store(&assignment.target, &value);
+        value
+    }
+
+    /// Call a function and return its value, which is empty for void functions
+    fn call(&mut self, call: &FunctionCall) -> String {
+        let function = self.functions.get(&call.name.name).expect("the functions are checked");
+        let mut arguments = Vec::new();
+        for (argument, parameter) in call.arguments.iter().zip(&function.parameters) {
+            let value = self.converted(argument, parameter.ty);
+            arguments.push(format!("{} {}", ir_type(parameter.ty), value));
+        }
+        let ty = ir_type(function.return_type);
+        if function.return_type == Type::Void {
+            self.instruction(format_args!("call void @c1.{}({})", function.name.name, arguments.join(", ")));
+            String::new()
+        } else {
+            self.value(format_args!("call {} @c1.{}({})", ty, function.name.name, arguments.join(", ")))
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) -> String {
+        match &expr.kind {
+            ExprKind::Int(value) => value.to_string(),
+            ExprKind::Float(value) => format!("{:#018X}", value.to_bits()).replace("0X", "0x"),
+            ExprKind::Bool(value) => value.to_string(),
+            ExprKind::Variable(identifier) => {
+                let (pointer, ty) = self.variable(identifier);
+                self.value(format_args!("load {}, ptr {}", ir_type(ty), pointer))
+            }
+            ExprKind::Call(call) => self.call(call),
+            ExprKind::Assign(assignment) => self.assignment(assignment),
+            ExprKind::Unary { op: UnaryOp::Neg, operand } => {
+                let value = self.expr(operand);
+                match self.ty(operand) {
+                    Type::Float => self.value(format_args!("fneg double {}", value)),
+                    _ => self.value(format_args!("sub i32 0, {}", value)),
+                }
+            }
+            ExprKind::Binary { op: op @ (BinaryOp::And | BinaryOp::Or), lhs, rhs } => {
+                let (rhs_label, end) = (self.label("rhs"), self.label("end"));
+                let lhs = self.expr(lhs);
+                let lhs_block = self.block.clone();
+                match op {
+                    BinaryOp::And => self.terminate(format_args!("br i1 {}, label %{}, label %{}", lhs, rhs_label, end)),
+                    _ => self.terminate(format_args!("br i1 {}, label %{}, label %{}", lhs, end, rhs_label)),
+                }
+                self.place(&rhs_label);
+                let rhs = self.expr(rhs);
+                let rhs_block = self.block.clone();
+                self.place(&end);
+                let decided = *op == BinaryOp::Or;
+                self.value(format_args!("phi i1 [ {}, %{} ], [ {}, %{} ]", decided, lhs_block, rhs, rhs_block))
+            }
+            ExprKind::Binary { op, lhs, rhs } => {
+                let float = self.ty(lhs) == Type::Float || self.ty(rhs) == Type::Float;
+                let operand_type = if float { Type::Float } else { self.ty(lhs) };
+                let lhs = self.converted(lhs, operand_type);
+                let rhs = self.converted(rhs, operand_type);
+                let ty = ir_type(operand_type);
+                if !float && *op == BinaryOp::Div {
+                    let (line, column) = (expr.span.line, expr.span.column);
+                    return self.value(format_args!("call i32 @c1_div(i32 {}, i32 {}, i32 {}, i32 {})", lhs, rhs, line, column));
+                }
+                let instruction = match (op, float) {
+                    (BinaryOp::Add, false) => "add",
+                    (BinaryOp::Sub, false) => "sub",
+                    (BinaryOp::Mul, false) => "mul",
+                    (BinaryOp::Equal, false) => "icmp eq",
+                    (BinaryOp::NotEqual, false) => "icmp ne",
+                    (BinaryOp::Less, false) => "icmp slt",
+                    (BinaryOp::LessEqual, false) => "icmp sle",
+                    (BinaryOp::Greater, false) => "icmp sgt",
+                    (BinaryOp::GreaterEqual, false) => "icmp sge",
+                    (BinaryOp::Add, true) => "fadd",
+                    (BinaryOp::Sub, true) => "fsub",
+                    (BinaryOp::Mul, true) => "fmul",
+                    (BinaryOp::Div, true) => "fdiv",
+                    (BinaryOp::Equal, true) => "fcmp oeq",
+                    (BinaryOp::NotEqual, true) => "fcmp une",
+                    (BinaryOp::Less, true) => "fcmp olt",
+                    (BinaryOp::LessEqual, true) => "fcmp ole",
+                    (BinaryOp::Greater, true) => "fcmp ogt",
+                    (BinaryOp::GreaterEqual, true) => "fcmp oge",
+                    (BinaryOp::Div | BinaryOp::And | BinaryOp::Or, _) => unreachable!("handled above"),
+                };
+                self.value(format_args!("{} {} {}, {}", instruction, ty, lhs, rhs))
+            }
+        }
+    }
+}
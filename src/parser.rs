@@ -1,295 +1,629 @@
-use crate::{C1Lexer, C1Token, ParseResult};
-use crate::C1Token::{And, Assign, Asterisk, ConstBoolean, ConstFloat, ConstInt, Equal, Error, Greater, GreaterEqual, Identifier, KwBoolean, KwFloat, KwIf, KwInt, KwPrintf, KwReturn, KwVoid, LeftBrace, LeftParenthesis, Less, LessEqual, Minus, NotEqual, Or, Plus, RightBrace, RightParenthesis, Semicolon, Slash};
+use crate::ast::{Assignment, BinaryOp, Block, Declaration, Expr, ExprKind, FunctionCall, FunctionDefinition, Identifier, Parameter, Program, Statement, StatementKind, Type, UnaryOp};
+use crate::{C1Lexer, C1Token, Literal, ParseError, ParseErrorKind, ParseResult, Span};
+use crate::C1Token::{And, Assign, Asterisk, Comma, ConstBoolean, ConstFloat, ConstInt, Equal, Greater, GreaterEqual, Identifier as Id, KwBoolean, KwDo, KwElse, KwFloat, KwFor, KwIf, KwInt, KwPrintf, KwReturn, KwVoid, KwWhile, LeftBrace, LeftParenthesis, Less, LessEqual, Minus, NotEqual, Or, Plus, RightBrace, RightParenthesis, Semicolon, Slash};
+
+/// Tokens at which the parser resumes after a syntax error inside a statementlist. A type keyword
+/// starts a declassignment or the next functiondefinition.
+const SYNC_TOKENS: [C1Token; 6] = [Semicolon, RightBrace, KwBoolean, KwFloat, KwInt, KwVoid];
 
 pub struct C1Parser<'a> {
     lexer: C1Lexer<'a>,
+    /// Location of the most recently consumed token, used to find the end of a node
+    previous_span: Span,
+    /// Syntax errors the parser has recovered from so far
+    errors: Vec<ParseError>,
 }
 
 impl<'a> C1Parser<'a> {
+    /// Maximum number of reported syntax errors. If there are more, the last reported error is
+    /// [TooManyErrors](crate::ParseErrorKind::TooManyErrors) and the rest of the input is ignored
+    pub const MAX_ERRORS: usize = 25;
+
     pub fn parse(input: &str) -> ParseResult {
-        let mut lexer = C1Lexer::new(input);
-        let mut parser = C1Parser::new(lexer);
+        let lexer = C1Lexer::new(input);
+        let parser = C1Parser::new(lexer);
         parser.parse_program()
     }
 
+    /// Parse the whole input and report every syntax error instead of stopping at the first one.
+    ///
+    /// After an error the parser skips ahead to the next `;`, `}` or type keyword and continues
+    /// from there. The returned program contains everything that
+    /// could be parsed; the errors are in the order of their location. Errors that directly follow
+    /// from a previous one are not reported, and at most [C1Parser::MAX_ERRORS] errors are reported.
+    /// ```
+    /// use cb_3::{C1Parser, C1Token};
+    ///
+    /// let (program, errors) = C1Parser::parse_with_recovery(
+    ///     "void main() { a = 1 b = 2; printf(a) }"
+    /// );
+    /// assert_eq!(program.functions.len(), 1);
+    /// assert_eq!(errors.len(), 2);
+    /// assert_eq!(errors[0].expected(), [C1Token::Semicolon]);
+    /// assert_eq!(errors[1].found(), Some(C1Token::RightBrace));
+    /// ```
+    pub fn parse_with_recovery(input: &str) -> (Program, Vec<ParseError>) {
+        let lexer = C1Lexer::new(input);
+        let mut parser = C1Parser::new(lexer);
+        let program = parser.parse_definitions();
+        (program, parser.errors)
+    }
+
     pub fn new(lexer: C1Lexer<'a>) -> Self {
         Self {
-            lexer
+            lexer,
+            previous_span: Span::default(),
+            errors: Vec::new(),
         }
     }
-    /// program ::= ( functiondefinition )* <EOF>
+    /// program ::= ( declassignment ";" | functiondefinition )* <EOF>
     pub fn parse_program(mut self) -> ParseResult {
-        if self.peek_token() != Some(Error) {
-            self.parse_funcdef()?;
+        let program = self.parse_definitions();
+        match self.errors.into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(program),
+        }
+    }
+
+    /// ( declassignment ";" | functiondefinition )* <EOF>
+    /// A global declassignment or the head of a functiondefinition with a syntax error is dropped,
+    /// and parsing continues at the next type keyword.
+    fn parse_definitions(&mut self) -> Program {
+        let mut globals = Vec::new();
+        let mut functions = Vec::new();
+        while self.lexer.current_token().is_some() {
+            match self.parse_definition() {
+                Ok(Definition::Global(declaration)) => globals.push(declaration),
+                Ok(Definition::Function(function)) => functions.push(function),
+                Err(error) => {
+                    if self.recover(error).is_err() {
+                        break;
+                    }
+                    while !matches!(self.lexer.current_token(), None | Some(KwBoolean | KwFloat | KwInt | KwVoid)) {
+                        self.eat();
+                    }
+                }
+            }
+        }
+        Program { globals, functions }
+    }
+
+    /// declassignment ";" | functiondefinition
+    /// Both start with type <ID>, only the token after that tells them apart.
+    fn parse_definition(&mut self) -> ParseResult<Definition> {
+        let start = self.current_span();
+        let ty = self.parse_type("at start of definition")?; // type
+        let name = self.parse_identifier("after type")?; // <ID>
+        match self.lexer.current_token() {
+            Some(LeftParenthesis) => Ok(Definition::Function(self.parse_funcdef(start, ty, name)?)), // functiondefinition
+            Some(Assign | Semicolon) => {
+                let declaration = self.parse_declassignment(start, ty, name)?; // declassignment
+                self.check_semicolon("after declaration")?; // ";"
+                Ok(Definition::Global(declaration))
+            }
+            _ => Err(self.error(&[LeftParenthesis, Assign, Semicolon], "after name in definition")),
+        }
+    }
+
+    /// functiondefinition  ::= type <ID> "(" ( parameterlist )? ")" "{" statementlist "}"
+    /// type and <ID> have already been parsed by the caller, the definition started at `start`.
+    fn parse_funcdef(&mut self, start: Span, return_type: Type, name: Identifier) -> ParseResult<FunctionDefinition> {
+        self.check_token(LeftParenthesis, "after function name")?; // "("
+        let parameters = match self.lexer.current_token() { // ( parameterlist )?
+            Some(RightParenthesis) => Vec::new(),
+            Some(KwBoolean | KwFloat | KwInt | KwVoid) => self.parse_parameterlist()?,
+            _ => return Err(self.error(&[KwBoolean, KwFloat, KwInt, KwVoid, RightParenthesis], "in parameter list")),
+        };
+        if self.lexer.current_token() == Some(RightParenthesis) { // ")"
+            self.eat();
         } else {
-            return Err(format!("File is empty!"));
+            return Err(self.error(&[Comma, RightParenthesis], "to close parameter list"));
         }
-        while self.peek_token() != None {
-            self.parse_funcdef()?;
+        let body = self.parse_braced_statementlist("to open function body")?; // "{" statementlist "}"
+        Ok(FunctionDefinition { return_type, name, parameters, body, span: self.span_from(start) })
+    }
+
+    /// parameterlist       ::= type <ID> ( "," type <ID> )*
+    fn parse_parameterlist(&mut self) -> ParseResult<Vec<Parameter>> {
+        let mut parameters = vec![self.parse_parameter()?]; // type <ID>
+        while self.lexer.current_token() == Some(Comma) { // ( "," type <ID> )*
+            self.eat();
+            parameters.push(self.parse_parameter()?);
         }
-        Ok(())
+        Ok(parameters)
     }
 
-    /// functiondefinition  ::= type <ID> "(" ")" "{" statementlist "}"
-    fn parse_funcdef(&mut self) -> ParseResult {
-        self.parse_type()?; //type
-        self.check_token(Identifier)?; // <ID>
-        self.check_token(LeftParenthesis)?; // "("
-        self.check_token(RightParenthesis)?; // "("
-        self.check_token(LeftBrace)?; //"{"
-        self.parse_statementlist()?; //  statementlist
-        self.check_token(RightBrace) //"}"
+    /// type <ID>
+    fn parse_parameter(&mut self) -> ParseResult<Parameter> {
+        let start = self.current_span();
+        let ty = self.parse_type("in parameter list")?; // type
+        let name = self.parse_identifier("after parameter type")?; // <ID>
+        Ok(Parameter { ty, name, span: self.span_from(start) })
     }
-    /// functioncall ::= <ID> "(" ")"
-    fn parse_functioncall(&mut self) -> ParseResult {
-        self.check_token(Identifier)?; // <ID>
-        self.check_token(LeftParenthesis)?; // "("
-        self.check_token(RightParenthesis) // ")"
+
+    /// functioncall        ::= <ID> "(" ( assignment ( "," assignment )* )? ")"
+    fn parse_functioncall(&mut self) -> ParseResult<FunctionCall> {
+        let start = self.current_span();
+        let name = self.parse_identifier("as function name")?; // <ID>
+        self.check_token(LeftParenthesis, "after function name")?; // "("
+        let mut arguments = Vec::new();
+        if self.lexer.current_token() != Some(RightParenthesis) { // ( assignment ( "," assignment )* )?
+            arguments.push(self.parse_assignment()?);
+            while self.lexer.current_token() == Some(Comma) {
+                self.eat();
+                arguments.push(self.parse_assignment()?);
+            }
+        }
+        if self.lexer.current_token() == Some(RightParenthesis) { // ")"
+            self.eat();
+        } else {
+            return Err(self.error(&[Comma, RightParenthesis], "to close argument list"));
+        }
+        Ok(FunctionCall { name, arguments, span: self.span_from(start) })
+    }
+
+    /// "{" statementlist "}"
+    fn parse_braced_statementlist(&mut self, context: &'static str) -> ParseResult<Block> {
+        let start = self.current_span();
+        self.check_token(LeftBrace, context)?; // "{"
+        let statements = self.parse_statementlist()?; // statementlist
+        if let Err(error) = self.check_token(RightBrace, "to close block") { // "}"
+            // the statementlist only stops early at the start of the next functiondefinition or at
+            // the end of the input, so the block is treated as if it was closed
+            self.recover(error)?;
+        }
+        Ok(Block { statements, span: self.span_from(start) })
     }
 
     /// statementlist       ::= ( block )*
     /// statementlists are always surrounded by "{" and "}", we will use this to find out how often a block has to be parsed
     /// there doesn't have to be a block (see the '*'). In this case there are just two empty braces like so: '{}'
-    fn parse_statementlist(&mut self) -> ParseResult {
-        while self.lexer.current_token() != Some(RightBrace) {
-            self.parse_block()?;
+    /// A statement with a syntax error is dropped, and parsing continues after the next ";" or at the
+    /// next "}" or type keyword. The statementlist also ends early if the next functiondefinition
+    /// starts, because its "}" is missing.
+    fn parse_statementlist(&mut self) -> ParseResult<Vec<Statement>> {
+        let mut statements = Vec::new();
+        while !matches!(self.lexer.current_token(), None | Some(RightBrace)) && !self.at_funcdef() {
+            match self.parse_block() {
+                Ok(statement) => statements.push(statement),
+                Err(error) => {
+                    self.recover(error)?;
+                    self.synchronize();
+                }
+            }
         }
-        Ok(())
+        Ok(statements)
     }
 
     /// block               ::= "{" statementlist "}" | statement
-    fn parse_block(&mut self) -> ParseResult {
+    fn parse_block(&mut self) -> ParseResult<Statement> {
         if self.lexer.current_token() == Some(LeftBrace) { // "{" statementlist "}"
-            self.check_token(LeftBrace)?;
-            self.parse_statementlist()?;
-            self.check_token(RightBrace)
+            let block = self.parse_braced_statementlist("to open block")?;
+            let span = block.span;
+            Ok(Statement { kind: StatementKind::Block(block), span })
         } else {
             self.parse_statement() //statement
         }
     }
 
-    ///statement           ::= ifstatement
+    ///statement           ::= declassignment ";"
+    ///                       | ifstatement
+    ///                       | forstatement
+    ///                       | whilestatement
+    ///                       | dowhilestatement ";"
     ///                       | returnstatement ";"
     ///                       | printf ";"
     ///                       | statassignment ";"
     ///                       | functioncall ";"
-    fn parse_statement(&mut self) -> ParseResult {
+    fn parse_statement(&mut self) -> ParseResult<Statement> {
         let lookahead = self.peek_token();
         let current = self.lexer.current_token();
-        match current {
-            Some(KwIf) => self.parse_if(), //ifstatement
+        let start = self.current_span();
+        let kind = match current {
+            Some(KwBoolean | KwFloat | KwInt | KwVoid) => {
+                let ty = self.parse_type("at start of declaration")?;
+                let name = self.parse_identifier("after type")?;
+                let declaration = self.parse_declassignment(start, ty, name)?; //declassignment
+                self.check_semicolon("after declaration")?; // ";"
+                StatementKind::Declaration(declaration)
+            }
+            Some(KwIf) => self.parse_if()?, //ifstatement
+            Some(KwFor) => self.parse_for()?, //forstatement
+            Some(KwWhile) => self.parse_while()?, //whilestatement
+            Some(KwDo) => {
+                let statement = self.parse_dowhile()?; //dowhilestatement
+                self.check_semicolon("after do-while statement")?; // ";"
+                statement
+            }
             Some(KwReturn) => {
-                self.parse_return()?; //returnstatement
-                self.check_token(Semicolon)
-            } // ";"
+                let value = self.parse_return()?; //returnstatement
+                self.check_semicolon("after return statement")?; // ";"
+                StatementKind::Return(value)
+            }
             Some(KwPrintf) => {
-                self.parse_printf()?; //printf
-                self.check_token(Semicolon)
-            } // ";"
-            Some(Identifier) =>
+                let value = self.parse_printf()?; //printf
+                self.check_semicolon("after printf statement")?; // ";"
+                StatementKind::Printf(value)
+            }
+            Some(Id) =>
                 if lookahead == Some(Assign) { //statassignment ";"
-                    self.parse_statassignment()?;
-                    self.check_token(Semicolon)
+                    let assignment = self.parse_statassignment()?;
+                    self.check_semicolon("after assignment")?;
+                    StatementKind::Assignment(assignment)
                 } else if lookahead == Some(LeftParenthesis) {  //funccall ";"
-                    self.parse_functioncall()?;
-                    self.check_token(Semicolon)
-                } else { return Err(format!("Error found while trying to parse statement: {:?} at {:?}", self.lexer.current_text(), self.lexer.current_line_number())); },
-            _ => return Err(format!("Error found statement return error: {:?} at {:?}", self.lexer.current_text(), self.lexer.current_line_number())) // none of the above
-        }
+                    let call = self.parse_functioncall()?;
+                    self.check_semicolon("after function call")?;
+                    StatementKind::Call(call)
+                } else {
+                    self.eat();
+                    return Err(self.error(&[Assign, LeftParenthesis], "after identifier"));
+                },
+            _ => return Err(self.error(&[LeftBrace, KwBoolean, KwFloat, KwInt, KwVoid, KwIf, KwFor, KwWhile, KwDo, KwReturn, KwPrintf, Id], "at start of statement")) // none of the above
+        };
+        Ok(Statement { kind, span: self.span_from(start) })
     }
 
-    /// ifstatement         ::= <KW_IF> "(" assignment ")" block
-    fn parse_if(&mut self) -> ParseResult {
-        self.check_token(KwIf)?; // <KW_IF>>
-        self.check_token(LeftParenthesis)?; // "("
-        self.parse_assignment()?; // assignment
-        self.check_token(RightParenthesis)?; // ")"
-        self.parse_block()?; // block
-        Ok(())
+    /// ifstatement         ::= <KW_IF> "(" assignment ")" block ( <KW_ELSE> block )?
+    /// An <KW_ELSE> always belongs to the innermost ifstatement that does not have one yet, because the
+    /// then-block is parsed completely before we look for it.
+    fn parse_if(&mut self) -> ParseResult<StatementKind> {
+        self.check_token(KwIf, "at start of if statement")?; // <KW_IF>>
+        self.check_token(LeftParenthesis, "after 'if'")?; // "("
+        let condition = self.parse_assignment()?; // assignment
+        self.check_token(RightParenthesis, "to close if condition")?; // ")"
+        let then_branch = Box::new(self.parse_block()?); // block
+        let else_branch = if self.lexer.current_token() == Some(KwElse) { // ( <KW_ELSE> block )?
+            self.eat();
+            Some(Box::new(self.parse_block()?))
+        } else {
+            None
+        };
+        Ok(StatementKind::If { condition, then_branch, else_branch })
+    }
+    /// forstatement        ::= <KW_FOR> "(" statassignment ";" assignment ";" statassignment ")" block
+    fn parse_for(&mut self) -> ParseResult<StatementKind> {
+        self.check_token(KwFor, "at start of for statement")?; // <KW_FOR>
+        self.check_token(LeftParenthesis, "after 'for'")?; // "("
+        let init = Box::new(self.parse_statassignment()?); // statassignment
+        self.check_token(Semicolon, "after for initialization")?; // ";"
+        let condition = self.parse_assignment()?; // assignment
+        self.check_token(Semicolon, "after for condition")?; // ";"
+        let step = Box::new(self.parse_statassignment()?); // statassignment
+        self.check_token(RightParenthesis, "to close for header")?; // ")"
+        let body = Box::new(self.parse_block()?); // block
+        Ok(StatementKind::For { init, condition, step, body })
+    }
+
+    /// whilestatement      ::= <KW_WHILE> "(" assignment ")" block
+    fn parse_while(&mut self) -> ParseResult<StatementKind> {
+        self.check_token(KwWhile, "at start of while statement")?; // <KW_WHILE>
+        self.check_token(LeftParenthesis, "after 'while'")?; // "("
+        let condition = self.parse_assignment()?; // assignment
+        self.check_token(RightParenthesis, "to close while condition")?; // ")"
+        let body = Box::new(self.parse_block()?); // block
+        Ok(StatementKind::While { condition, body })
+    }
+
+    /// dowhilestatement    ::= <KW_DO> block <KW_WHILE> "(" assignment ")"
+    fn parse_dowhile(&mut self) -> ParseResult<StatementKind> {
+        self.check_token(KwDo, "at start of do-while statement")?; // <KW_DO>
+        let body = Box::new(self.parse_block()?); // block
+        self.check_token(KwWhile, "after body of do-while statement")?; // <KW_WHILE>
+        self.check_token(LeftParenthesis, "after 'while'")?; // "("
+        let condition = self.parse_assignment()?; // assignment
+        self.check_token(RightParenthesis, "to close while condition")?; // ")"
+        Ok(StatementKind::DoWhile { body, condition })
     }
+
     /// returnstatement     ::= <KW_RETURN> ( assignment )?
     /// returnstatements are always followed by a ';', so this way we can check for the '?'
-    fn parse_return(&mut self) -> ParseResult {
-        self.check_token(KwReturn)?; // <KW_RETURN>
+    fn parse_return(&mut self) -> ParseResult<Option<Expr>> {
+        self.check_token(KwReturn, "at start of return statement")?; // <KW_RETURN>
         if self.lexer.current_token() != Some(Semicolon) { // ( assignment )?
-            self.parse_assignment()?;
+            Ok(Some(self.parse_assignment()?))
+        } else {
+            Ok(None)
         }
-        Ok(())
     }
 
     /// printf              ::= <KW_PRINTF> "(" assignment ")"
-    fn parse_printf(&mut self) -> ParseResult {
-        self.check_token(KwPrintf)?; // <KW_PRINTF>
-        self.check_token(LeftParenthesis)?; // "("
-        self.parse_assignment()?; // assignment
-        self.check_token(RightParenthesis) // "("
-    }
-
-    ///type                ::= <KW_BOOLEAN>
-//                       | <KW_FLOAT>
-//                       | <KW_INT>
-//                       | <KW_VOID>
-    fn parse_type(&mut self) -> ParseResult {
+    fn parse_printf(&mut self) -> ParseResult<Expr> {
+        self.check_token(KwPrintf, "at start of printf statement")?; // <KW_PRINTF>
+        self.check_token(LeftParenthesis, "after 'printf'")?; // "("
+        let value = self.parse_assignment()?; // assignment
+        self.check_token(RightParenthesis, "to close printf argument")?; // ")"
+        Ok(value)
+    }
+
+    /// type                ::= <KW_BOOLEAN>
+    ///                       | <KW_FLOAT>
+    ///                       | <KW_INT>
+    ///                       | <KW_VOID>
+    fn parse_type(&mut self, context: &'static str) -> ParseResult<Type> {
         let current = self.lexer.current_token();
-        match current {
-            Some(KwBoolean) => self.check_token(KwBoolean)?, //<KW_BOOLEAN>
-            Some(KwFloat) => self.check_token(KwFloat)?, //<KW_FLOAT>
-            Some(KwInt) => self.check_token(KwInt)?, //<KW_INT>
-            Some(KwVoid) => self.check_token(KwVoid)?, //<KW_VOID>
-            _ => return Err(format!("Error found: {:?} at {:?}", self.lexer.current_text(), self.lexer.current_line_number())) //None of the above
-        }
-        Ok(())
+        let ty = match current {
+            Some(KwBoolean) => Type::Boolean, //<KW_BOOLEAN>
+            Some(KwFloat) => Type::Float, //<KW_FLOAT>
+            Some(KwInt) => Type::Int, //<KW_INT>
+            Some(KwVoid) => Type::Void, //<KW_VOID>
+            _ => return Err(self.error(&[KwBoolean, KwFloat, KwInt, KwVoid], context)) //None of the above
+        };
+        self.eat();
+        Ok(ty)
     }
+    /// declassignment      ::= type <ID> ( "=" assignment )?
+    /// type and <ID> have already been parsed by the caller, the declaration started at `start`.
+    fn parse_declassignment(&mut self, start: Span, ty: Type, name: Identifier) -> ParseResult<Declaration> {
+        let value = match self.lexer.current_token() { // ( "=" assignment )?
+            Some(Assign) => {
+                self.eat();
+                Some(self.parse_assignment()?)
+            }
+            Some(Semicolon) => None,
+            _ => return Err(self.error(&[Assign, Semicolon], "after declared variable")),
+        };
+        Ok(Declaration { ty, name, value, span: self.span_from(start) })
+    }
+
     /// statassignment      ::= <ID> "=" assignment
-    fn parse_statassignment(&mut self) -> ParseResult {
-        self.check_token(Identifier)?; // <ID>
-        self.check_token(Assign)?; // "="
-        self.parse_assignment() // assignment
+    fn parse_statassignment(&mut self) -> ParseResult<Assignment> {
+        let target = self.parse_identifier("as assignment target")?; // <ID>
+        self.check_token(Assign, "after assignment target")?; // "="
+        let value = self.parse_assignment()?; // assignment
+        Ok(Assignment { target, value })
     }
 
     /// assignment          ::= ( ( <ID> "=" assignment ) | expr )
     /// This sucks because there is also a case where an expr starts with a simpexpr which might start with a term which might begin with an ID
-    fn parse_assignment(&mut self) -> ParseResult {
-        if self.lexer.current_token() == Some(Identifier) && self.peek_token() == Some(Assign) { //( <ID> "=" assignment )
-            self.check_token(Identifier)?; // <ID>
-            self.check_token(Assign)?; // "="
-            self.parse_assignment() // assignment
+    fn parse_assignment(&mut self) -> ParseResult<Expr> {
+        if self.lexer.current_token() == Some(Id) && self.peek_token() == Some(Assign) { //( <ID> "=" assignment )
+            let start = self.current_span();
+            let assignment = self.parse_statassignment()?; // <ID> "=" assignment
+            Ok(Expr { kind: ExprKind::Assign(Box::new(assignment)), span: self.span_from(start) })
         } else { // | expr
             self.parse_expression()
         }
     }
 
     /// expr                ::= simpexpr ( ( "==" | "!=" | "<=" | ">=" | "<" | ">" ) simpexpr )?
-    fn parse_expression(&mut self) -> ParseResult {
-        self.parse_simpexpr()?; //simpexpr
+    fn parse_expression(&mut self) -> ParseResult<Expr> {
+        let lhs = self.parse_simpexpr()?; //simpexpr
         // If the next part exists, it defenitely starts with one of the following: ( "==" | "!=" | "<=" | ">=" | "<" | ">" )
-        match self.lexer.current_token() {
-            Some(Equal) => {
-                self.check_token(Equal)?;
-                self.parse_simpexpr()
-            } // "==" simpexpr
-            Some(NotEqual) => {
-                self.check_token(NotEqual)?;
-                self.parse_simpexpr()
-            } // "!=" simpexpr
-            Some(LessEqual) => {
-                self.check_token(LessEqual)?;
-                self.parse_simpexpr()
-            } // "<=" simpexpr
-            Some(GreaterEqual) => {
-                self.check_token(GreaterEqual)?;
-                self.parse_simpexpr()
-            } // ">=" simpexpr
-            Some(Less) => {
-                self.check_token(Less)?;
-                self.parse_simpexpr()
-            } // "<" simpexpr
-            Some(Greater) => {
-                self.check_token(Greater)?;
-                self.parse_simpexpr()
-            } // ">" simpexpr
-            _ => Ok(()) // there was no second part
-        }
+        let op = match self.lexer.current_token() {
+            Some(Equal) => BinaryOp::Equal, // "==" simpexpr
+            Some(NotEqual) => BinaryOp::NotEqual, // "!=" simpexpr
+            Some(LessEqual) => BinaryOp::LessEqual, // "<=" simpexpr
+            Some(GreaterEqual) => BinaryOp::GreaterEqual, // ">=" simpexpr
+            Some(Less) => BinaryOp::Less, // "<" simpexpr
+            Some(Greater) => BinaryOp::Greater, // ">" simpexpr
+            _ => return Ok(lhs) // there was no second part
+        };
+        self.eat();
+        let rhs = self.parse_simpexpr()?;
+        Ok(binary(op, lhs, rhs))
     }
 
     /// simpexpr            ::= ( "-" )? term ( ( "+" | "-" | "||" ) term )*
-    fn parse_simpexpr(&mut self) -> ParseResult {
-        if self.lexer.current_token() == Some(Minus) { // ( "-" )?
-            self.check_token(Minus)?;
-        }
-        self.parse_term()?; // term
-        let mut whilecondition = self.whilechecker(C1Token::Plus, C1Token::Minus, C1Token::Or);
-        while whilecondition == true { // ( ( "+" | "-" | "||" ) term )*
-            match self.lexer.current_token() {
-                Some(Plus) => {
-                    self.check_token(Plus)?; // "+" term
-                    self.parse_term()?;
-                }
-                Some(Minus) => {
-                    self.check_token(Minus)?; // "-" term
-                    self.parse_term()?;
-                }
-                Some(Or) => {
-                    self.check_token(Or)?; // "||" term
-                    self.parse_term()?;
-                }
-                _ => return Err(format!("Error found: {:?} at {:?}", self.lexer.current_text(), self.lexer.current_line_number())) //this should never occur since we explicitely checked for it before entering the match
-            }
-            whilecondition = self.whilechecker(C1Token::Plus, C1Token::Minus, C1Token::Or);
+    fn parse_simpexpr(&mut self) -> ParseResult<Expr> {
+        let mut expr = if self.lexer.current_token() == Some(Minus) { // ( "-" )?
+            let start = self.current_span();
+            self.eat();
+            let operand = Box::new(self.parse_term()?); // term
+            Expr { kind: ExprKind::Unary { op: UnaryOp::Neg, operand }, span: self.span_from(start) }
+        } else {
+            self.parse_term()? // term
+        };
+        while self.whilechecker(Plus, Minus, Or) { // ( ( "+" | "-" | "||" ) term )*
+            let op = match self.lexer.current_token() {
+                Some(Plus) => BinaryOp::Add, // "+" term
+                Some(Minus) => BinaryOp::Sub, // "-" term
+                _ => BinaryOp::Or, // "||" term
+            };
+            self.eat();
+            let rhs = self.parse_term()?;
+            expr = binary(op, expr, rhs);
         }
-        Ok(()) //no last part
+        Ok(expr) //no last part
     }
 
     /// term                ::= factor ( ( "*" | "/" | "&&" ) factor )*
-    fn parse_term(&mut self) -> ParseResult {
-        self.parse_factor()?; // factor
-        let mut whilecondition = self.whilechecker(C1Token::Asterisk, C1Token::Slash, C1Token::And);
-        while whilecondition == true { // ( ( "*" | "/" | "&&" ) factor )*
-            match self.lexer.current_token() {
-                Some(Asterisk) => {
-                    self.check_token(Asterisk)?; // "*" factor
-                    self.parse_factor()?;
-                }
-                Some(Slash) => {
-                    self.check_token(Slash)?; // "/" factor
-                    self.parse_factor()?;
-                }
-                Some(And) => {
-                    self.check_token(And)?; // "&&" factor
-                    self.parse_factor()?;
-                }
-                _ => return Err(format!("Error found while parsing term: {:?} at {:?}", self.lexer.current_text(), self.lexer.current_line_number())) //this should never occur since we explicitely checked for it before entering the match
-            }
-            whilecondition = self.whilechecker(C1Token::Asterisk, C1Token::Slash, C1Token::And);
+    fn parse_term(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.parse_factor()?; // factor
+        while self.whilechecker(Asterisk, Slash, And) { // ( ( "*" | "/" | "&&" ) factor )*
+            let op = match self.lexer.current_token() {
+                Some(Asterisk) => BinaryOp::Mul, // "*" factor
+                Some(Slash) => BinaryOp::Div, // "/" factor
+                _ => BinaryOp::And, // "&&" factor
+            };
+            self.eat();
+            let rhs = self.parse_factor()?;
+            expr = binary(op, expr, rhs);
         }
-        Ok(()) // no last part
+        Ok(expr) // no last part
     }
 
-    ///factor              ::= <CONST_INT>
-//                       | <CONST_FLOAT>
-//                       | <CONST_BOOLEAN>
-//                       | functioncall
-//                       | <ID>
-//                       | "(" assignment ")"
-
-    fn parse_factor(&mut self) -> ParseResult {
-        match self.lexer.current_token() {
-            Some(ConstInt) => self.check_token(ConstInt), // <CONST_INT>
-            Some(ConstFloat) => self.check_token(ConstFloat), // <CONST_FLOAT>
-            Some(ConstBoolean) => self.check_token(ConstBoolean), // <CONST_BOOLEAN>
-            Some(Identifier) => if self.peek_token() == Some(LeftParenthesis) { //this might be ambiguous? there should never be a "(" if it's just the <ID>, but I'm not sure. ||| functioncall | <ID>
-                self.parse_functioncall() // functioncall
+    /// factor              ::= <CONST_INT>
+    ///                       | <CONST_FLOAT>
+    ///                       | <CONST_BOOLEAN>
+    ///                       | functioncall
+    ///                       | <ID>
+    ///                       | "(" assignment ")"
+    fn parse_factor(&mut self) -> ParseResult<Expr> {
+        let start = self.current_span();
+        let kind = match (self.lexer.current_token(), self.lexer.current_literal()) {
+            (Some(ConstInt), Some(&Literal::Int(value))) => match i32::try_from(value) { // <CONST_INT>
+                Ok(value) => ExprKind::Int(value),
+                Err(_) => return Err(ParseError { kind: ParseErrorKind::IntegerOutOfRange(self.lexer.current_text().unwrap_or_default().to_string()), span: self.current_span() }),
+            },
+            (Some(ConstFloat), Some(&Literal::Float(value))) => ExprKind::Float(value), // <CONST_FLOAT>
+            (Some(ConstBoolean), Some(&Literal::Bool(value))) => ExprKind::Bool(value), // <CONST_BOOLEAN>
+            (Some(Id), _) => if self.peek_token() == Some(LeftParenthesis) { //this might be ambiguous? there should never be a "(" if it's just the <ID>, but I'm not sure. ||| functioncall | <ID>
+                let call = self.parse_functioncall()?;
+                return Ok(Expr { kind: ExprKind::Call(call), span: self.span_from(start) }) // functioncall
             } else {
-                self.check_token(Identifier) // <ID>
+                let identifier = self.parse_identifier("in expression")?;
+                return Ok(Expr { span: identifier.span, kind: ExprKind::Variable(identifier) }) // <ID>
             },
-            Some(LeftParenthesis) => {
-                self.check_token(LeftParenthesis)?;
-                self.parse_assignment()?;
-                self.check_token(RightParenthesis)
+            (Some(LeftParenthesis), _) => {
+                self.eat();
+                let mut expr = self.parse_assignment()?;
+                self.check_token(RightParenthesis, "to close parenthesized expression")?;
+                expr.span = self.span_from(start); // the parentheses belong to the expression
+                return Ok(expr)
             } // "(" assignment ")"
-            _ => return Err(format!("Error found while parsing factor: {:?} at {:?}", self.lexer.current_text(), self.lexer.current_line_number())) //not a factor
-        }
+            _ => return Err(self.error(&[ConstInt, ConstFloat, ConstBoolean, Id, LeftParenthesis], "in expression")) //not a factor
+        };
+        self.eat();
+        Ok(Expr { kind, span: self.span_from(start) })
     }
+
+    /// <ID>
+    fn parse_identifier(&mut self, context: &'static str) -> ParseResult<Identifier> {
+        let start = self.current_span();
+        let name = self.lexer.current_text().unwrap_or_default().to_string();
+        self.check_token(Id, context)?;
+        Ok(Identifier { name, span: self.span_from(start) })
+    }
+
     ///check_and_eat
-    fn check_token(&mut self, token: C1Token) -> ParseResult {
+    /// The context describes where the token belongs and completes the error message, e.g. "after return statement"
+    fn check_token(&mut self, token: C1Token, context: &'static str) -> ParseResult<()> {
         if self.lexer.current_token() == Some(token) { // "}"
-            self.lexer.eat();
+            self.eat();
         } else {
-            return Err(format!("Error found in eat function: {:?} at {:?}", self.lexer.current_text(), self.lexer.current_line_number()));
+            return Err(self.error(&[token], context));
         }
         Ok(())
     }
 
+    /// ";" at the end of a statement. If it is missing but the statement is followed by a line break or
+    /// a "}", the error is recorded and parsing continues as if the ";" was there.
+    fn check_semicolon(&mut self, context: &'static str) -> ParseResult<()> {
+        match self.check_token(Semicolon, context) {
+            Err(error) if self.lexer.current_token() == Some(RightBrace) || self.current_span().line > self.previous_span.line => self.recover(error),
+            result => result,
+        }
+    }
+
+    /// Error for an unexpected current token, listing the tokens that would have been accepted instead.
+    /// If the current token is not a valid token, the error describes why.
+    fn error(&self, expected: &[C1Token], context: &'static str) -> ParseError {
+        if let Some(error) = self.lexer.current_error() {
+            return ParseError { kind: ParseErrorKind::Lexical(error.kind), span: error.span };
+        }
+        ParseError {
+            kind: ParseErrorKind::UnexpectedToken {
+                found: self.lexer.current_token(),
+                expected: expected.to_vec(),
+                context,
+            },
+            span: self.current_span(),
+        }
+    }
+
+    /// Location of the current token, or the end of the text if all tokens have been consumed
+    fn current_span(&self) -> Span {
+        self.lexer.current_span().unwrap_or_else(|| self.lexer.end_span())
+    }
+
     fn peek_token(&self) -> Option<C1Token> {
         self.lexer.peek_token()
     }
+
+    /// Check whether the current token starts a functiondefinition, i.e. whether the tokens are
+    /// type <ID> "(".
+    fn at_funcdef(&mut self) -> bool {
+        matches!(self.lexer.current_token(), Some(KwBoolean | KwFloat | KwInt | KwVoid))
+            && self.peek_token() == Some(Id)
+            && self.lexer.peek_nth(2) == Some(LeftParenthesis)
+    }
+
+    /// Record a syntax error and continue parsing. Fails once the parser has given up, so that the
+    /// error travels up to parse_definitions.
+    fn recover(&mut self, error: ParseError) -> ParseResult<()> {
+        // an error at the same location as the previous one is a consequence of it
+        if self.errors.last().map(|last| last.span) == Some(error.span) && !self.gave_up() {
+            return Ok(());
+        }
+        if self.report(error.clone()) {
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+
+    /// Record an error unless the limit is reached. The error that would exceed the limit is
+    /// replaced with TooManyErrors, after which the parser gives up and `false` is returned.
+    fn report(&mut self, error: ParseError) -> bool {
+        if self.gave_up() {
+            return false;
+        }
+        if self.errors.len() + 1 == Self::MAX_ERRORS {
+            self.errors.push(ParseError { kind: ParseErrorKind::TooManyErrors, span: error.span });
+            return false;
+        }
+        self.errors.push(error);
+        true
+    }
+
+    fn gave_up(&self) -> bool {
+        self.errors.len() == Self::MAX_ERRORS
+    }
+
+    /// Skip tokens until the parser reaches one of the SYNC_TOKENS. A ";" is consumed as it ends the
+    /// broken statement. Blocks that are opened while skipping are skipped as a whole, so that their
+    /// "}" does not end the surrounding statementlist.
+    fn synchronize(&mut self) {
+        let mut depth = 0;
+        while let Some(token) = self.lexer.current_token() {
+            match token {
+                LeftBrace => depth += 1,
+                RightBrace if depth > 0 => depth -= 1,
+                _ if depth == 0 && SYNC_TOKENS.contains(&token) => {
+                    if token == Semicolon {
+                        self.eat();
+                    }
+                    return;
+                }
+                _ => {}
+            }
+            self.eat();
+        }
+    }
+
+    /// Consume the current token and remember where it ended. An invalid token that is skipped after
+    /// a syntax error is still reported, so that every lexical error surfaces.
+    fn eat(&mut self) {
+        for error in self.lexer.current_errors().to_vec() {
+            if self.errors.iter().all(|reported| reported.span != error.span) {
+                self.report(ParseError { kind: ParseErrorKind::Lexical(error.kind), span: error.span });
+            }
+        }
+        if let Some(span) = self.lexer.current_span() {
+            self.previous_span = span;
+        }
+        self.lexer.eat();
+    }
+
+    /// Location of a node that starts at `start` and ends with the most recently consumed token
+    fn span_from(&self, start: Span) -> Span {
+        Span { end: self.previous_span.end.max(start.start), ..start }
+    }
+
     fn whilechecker(&self, token: C1Token, token2: C1Token, token3: C1Token) -> bool {
-        return if self.lexer.current_token() == Some(token) {
-            true
-        } else if self.lexer.current_token() == Some(token2) {
-            true
-        } else if self.lexer.current_token() == Some(token3) {
-            true
-        } else { false }
-    }
-}
\ No newline at end of file
+        let current = self.lexer.current_token();
+        current == Some(token) || current == Some(token2) || current == Some(token3)
+    }
+}
+
+/// Combine two operands into a binary expression that covers both of them
+fn binary(op: BinaryOp, lhs: Expr, rhs: Expr) -> Expr {
+    let span = Span { end: rhs.span.end, ..lhs.span };
+    Expr { kind: ExprKind::Binary { op, lhs: Box::new(lhs), rhs: Box::new(rhs) }, span }
+}
+
+/// Result of parsing one entry of a program
+enum Definition {
+    Global(Declaration),
+    Function(FunctionDefinition),
+}
@@ -0,0 +1,325 @@
+//! Name resolution for variables.
+//!
+//! Every variable of a program is a [Binding], and every occurrence of a variable name is resolved
+//! to the binding it refers to. The scopes are:
+//! - global variables are declared by the declassignments at the top level. They are visible in all
+//!   functions and in the initial values of the global variables declared after them
+//! - every function has a function scope that contains its parameters, the declarations directly
+//!   in its body and its implicit variables. An assignment to a name that does not refer to any
+//!   visible binding creates an implicit variable in the function scope
+//! - every nested `{ statementlist }` and every branch and body of an if statement or loop has its
+//!   own block scope for the variables declared in it. A declaration shadows variables of the same
+//!   name from enclosing scopes and from the global scope
+//!
+//! A name refers to the binding that is visible at its position in the source text, so a variable is
+//! visible from the end of its declassignment, or from its first assignment for implicit variables,
+//! to the end of its scope. Declaring a name twice in the same scope is an error, and so is reading a
+//! name that does not refer to any binding.
+
+use crate::ast::{Assignment, Block, Declaration, Expr, ExprKind, FunctionDefinition, Identifier, Program, Statement, StatementKind, Type};
+use crate::Span;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// Identifies a [Binding] of a [Resolution]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BindingId(usize);
+
+/// A variable of the program
+#[derive(Debug, Clone, PartialEq)]
+pub struct Binding {
+    pub name: String,
+    pub kind: BindingKind,
+    /// The declared type, `None` for implicit variables
+    pub ty: Option<Type>,
+    /// Location of the name in the declassignment or parameter, or of the first assignment of an
+    /// implicit variable
+    pub span: Span,
+    /// Position of the variable among the global variables, or among all variables of its function
+    /// with the parameters first
+    pub slot: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BindingKind {
+    Global,
+    Parameter,
+    /// A variable declared by a declassignment in a function
+    Local,
+    /// A variable created by an assignment
+    Implicit,
+}
+
+/// The bindings of a program and the binding of every resolved variable name
+#[derive(Debug, Clone, Default)]
+pub struct Resolution {
+    bindings: Vec<Binding>,
+    /// Bindings of the identifiers, by their span
+    uses: HashMap<Span, BindingId>,
+    /// Number of variable slots of the functions, by their span
+    frame_sizes: HashMap<Span, usize>,
+    globals: usize,
+}
+
+impl Resolution {
+    pub fn binding(&self, id: BindingId) -> &Binding {
+        &self.bindings[id.0]
+    }
+
+    /// Return all bindings in the order of the source text
+    pub fn bindings(&self) -> impl Iterator<Item = (BindingId, &Binding)> {
+        self.bindings.iter().enumerate().map(|(index, binding)| (BindingId(index), binding))
+    }
+
+    /// Return the binding a variable name in a declassignment, parameter, assignment or expression
+    /// refers to, `None` if it could not be resolved
+    pub fn lookup(&self, identifier: &Identifier) -> Option<BindingId> {
+        self.uses.get(&identifier.span).copied()
+    }
+
+    /// Return the number of variable slots a call of the function needs
+    pub fn frame_size(&self, function: &FunctionDefinition) -> usize {
+        self.frame_sizes.get(&function.span).copied().unwrap_or(0)
+    }
+
+    /// Return the number of global variables
+    pub fn globals(&self) -> usize {
+        self.globals
+    }
+}
+
+/// A variable name that cannot be resolved
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolveError {
+    pub kind: ResolveErrorKind,
+    /// Location of the variable name
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolveErrorKind {
+    /// A name is read that does not refer to any visible variable
+    UndefinedVariable(String),
+    /// A name is declared again in the same scope, `previous` is the location of the first binding
+    DuplicateVariable {
+        name: String,
+        previous: Span,
+    },
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.span.line, self.span.column)?;
+        match &self.kind {
+            ResolveErrorKind::UndefinedVariable(name) => write!(f, "variable '{}' is not defined", name),
+            ResolveErrorKind::DuplicateVariable { name, previous } => {
+                write!(f, "variable '{}' is already defined in line {}", name, previous.line)
+            }
+        }
+    }
+}
+
+impl Error for ResolveError {}
+
+/// Resolve every variable name of the program.
+///
+/// The resolution is complete except for the names that caused errors. A name that is declared
+/// twice gets a new binding that is used from then on. Errors are returned in the order of the
+/// source text.
+/// ```
+/// use cb_3::C1Parser;
+/// use cb_3::ast::StatementKind;
+/// use cb_3::resolve::{resolve, BindingKind};
+///
+/// let program = C1Parser::parse("int a; void f() { a = 1; b = 2; { int a = b; } }").unwrap();
+/// let (resolution, errors) = resolve(&program);
+/// assert_eq!(errors, []);
+/// let kinds: Vec<BindingKind> = resolution.bindings().map(|(_, binding)| binding.kind).collect();
+/// assert_eq!(kinds, [BindingKind::Global, BindingKind::Implicit, BindingKind::Local]);
+/// match &program.functions[0].body.statements[0].kind {
+///     StatementKind::Assignment(assignment) => {
+///         let id = resolution.lookup(&assignment.target).unwrap();
+///         assert_eq!(resolution.binding(id).kind, BindingKind::Global);
+///     }
+///     _ => unreachable!(),
+/// }
+/// ```
+pub fn resolve(program: &Program) -> (Resolution, Vec<ResolveError>) {
+    let mut resolver = Resolver { resolution: Resolution::default(), errors: Vec::new(), globals: HashMap::new(), scopes: Vec::new(), frame_size: 0 };
+    for declaration in &program.globals {
+        if let Some(value) = &declaration.value {
+            resolver.expr(value);
+        }
+        let slot = resolver.resolution.globals;
+        resolver.resolution.globals += 1;
+        let id = resolver.bind(&declaration.name, BindingKind::Global, Some(declaration.ty), slot);
+        if let Some(previous) = resolver.globals.insert(&declaration.name.name, id) {
+            resolver.duplicate(&declaration.name, previous);
+        }
+    }
+    for function in &program.functions {
+        resolver.function(function);
+    }
+    (resolver.resolution, resolver.errors)
+}
+
+struct Resolver<'p> {
+    resolution: Resolution,
+    errors: Vec<ResolveError>,
+    globals: HashMap<&'p str, BindingId>,
+    /// The function scope followed by the nested block scopes, empty outside of functions
+    scopes: Vec<HashMap<&'p str, BindingId>>,
+    /// Number of variable slots of the function that is resolved
+    frame_size: usize,
+}
+
+impl<'p> Resolver<'p> {
+    /// Create a binding for the identifier
+    fn bind(&mut self, identifier: &Identifier, kind: BindingKind, ty: Option<Type>, slot: usize) -> BindingId {
+        let id = BindingId(self.resolution.bindings.len());
+        self.resolution.bindings.push(Binding { name: identifier.name.clone(), kind, ty, span: identifier.span, slot });
+        self.resolution.uses.insert(identifier.span, id);
+        id
+    }
+
+    fn duplicate(&mut self, identifier: &Identifier, previous: BindingId) {
+        let previous = self.resolution.binding(previous).span;
+        self.errors.push(ResolveError {
+            kind: ResolveErrorKind::DuplicateVariable { name: identifier.name.clone(), previous },
+            span: identifier.span,
+        });
+    }
+
+    /// Create a binding for the identifier in the innermost scope
+    fn declare(&mut self, identifier: &'p Identifier, kind: BindingKind, ty: Option<Type>) {
+        let id = self.bind(identifier, kind, ty, self.frame_size);
+        self.frame_size += 1;
+        let scope = if kind == BindingKind::Implicit { self.scopes.first_mut() } else { self.scopes.last_mut() };
+        let previous = scope.expect("variables are only declared in functions").insert(&identifier.name, id);
+        if let Some(previous) = previous {
+            self.duplicate(identifier, previous);
+        }
+    }
+
+    /// Return the binding that is visible for the name
+    fn lookup(&self, name: &str) -> Option<BindingId> {
+        self.scopes.iter().rev().chain(std::iter::once(&self.globals)).find_map(|scope| scope.get(name)).copied()
+    }
+
+    fn function(&mut self, function: &'p FunctionDefinition) {
+        self.scopes.push(HashMap::new());
+        self.frame_size = 0;
+        for parameter in &function.parameters {
+            self.declare(&parameter.name, BindingKind::Parameter, Some(parameter.ty));
+        }
+        for statement in &function.body.statements {
+            self.statement(statement);
+        }
+        self.resolution.frame_sizes.insert(function.span, self.frame_size);
+        self.scopes.clear();
+    }
+
+    fn block(&mut self, block: &'p Block) {
+        for statement in &block.statements {
+            self.statement(statement);
+        }
+    }
+
+    /// Resolve a statement in a new block scope
+    fn scoped(&mut self, statement: &'p Statement) {
+        self.scopes.push(HashMap::new());
+        self.statement(statement);
+        self.scopes.pop();
+    }
+
+    fn statement(&mut self, statement: &'p Statement) {
+        match &statement.kind {
+            StatementKind::Block(block) => {
+                self.scopes.push(HashMap::new());
+                self.block(block);
+                self.scopes.pop();
+            }
+            StatementKind::Declaration(declaration) => self.declaration(declaration),
+            StatementKind::If { condition, then_branch, else_branch } => {
+                self.expr(condition);
+                self.scoped(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.scoped(else_branch);
+                }
+            }
+            StatementKind::For { init, condition, step, body } => {
+                self.assignment(init);
+                self.expr(condition);
+                self.assignment(step);
+                self.scoped(body);
+            }
+            StatementKind::While { condition, body } => {
+                self.expr(condition);
+                self.scoped(body);
+            }
+            StatementKind::DoWhile { body, condition } => {
+                self.scoped(body);
+                self.expr(condition);
+            }
+            StatementKind::Return(value) => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            StatementKind::Printf(value) => self.expr(value),
+            StatementKind::Assignment(assignment) => self.assignment(assignment),
+            StatementKind::Call(call) => {
+                for argument in &call.arguments {
+                    self.expr(argument);
+                }
+            }
+        }
+    }
+
+    fn declaration(&mut self, declaration: &'p Declaration) {
+        if let Some(value) = &declaration.value {
+            self.expr(value);
+        }
+        self.declare(&declaration.name, BindingKind::Local, Some(declaration.ty));
+    }
+
+    fn assignment(&mut self, assignment: &'p Assignment) {
+        self.expr(&assignment.value);
+        let target = &assignment.target;
+        match self.lookup(&target.name) {
+            Some(id) => {
+                self.resolution.uses.insert(target.span, id);
+            }
+            None if self.scopes.is_empty() => self.undefined(target),
+            None => self.declare(target, BindingKind::Implicit, None),
+        }
+    }
+
+    fn undefined(&mut self, identifier: &Identifier) {
+        self.errors.push(ResolveError { kind: ResolveErrorKind::UndefinedVariable(identifier.name.clone()), span: identifier.span });
+    }
+
+    fn expr(&mut self, expr: &'p Expr) {
+        match &expr.kind {
+            ExprKind::Int(_) | ExprKind::Float(_) | ExprKind::Bool(_) => {}
+            ExprKind::Variable(identifier) => match self.lookup(&identifier.name) {
+                Some(id) => {
+                    self.resolution.uses.insert(identifier.span, id);
+                }
+                None => self.undefined(identifier),
+            },
+            ExprKind::Call(call) => {
+                for argument in &call.arguments {
+                    self.expr(argument);
+                }
+            }
+            ExprKind::Assign(assignment) => self.assignment(assignment),
+            ExprKind::Unary { operand, .. } => self.expr(operand),
+            ExprKind::Binary { lhs, rhs, .. } => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+        }
+    }
+}
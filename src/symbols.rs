@@ -0,0 +1,276 @@
+//! Function symbol table and the checks between functiondefinitions and their call sites.
+
+use crate::ast::{Expr, ExprKind, FunctionCall, FunctionDefinition, Program, Statement, StatementKind, Type};
+use crate::Span;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// All functions defined by a program, looked up by name.
+///
+/// If a name is defined more than once, the first functiondefinition wins.
+pub struct FunctionTable<'a> {
+    functions: HashMap<&'a str, &'a FunctionDefinition>,
+}
+
+impl<'a> FunctionTable<'a> {
+    /// Collect the functiondefinitions of the given program
+    pub fn new(program: &'a Program) -> Self {
+        let mut functions = HashMap::new();
+        for function in &program.functions {
+            functions.entry(function.name.name.as_str()).or_insert(function);
+        }
+        FunctionTable { functions }
+    }
+
+    /// Return the functiondefinition with the given name
+    pub fn get(&self, name: &str) -> Option<&'a FunctionDefinition> {
+        self.functions.get(name).copied()
+    }
+
+    /// Return the defined name that is closest to `name`, if it is close enough to be a likely typo
+    pub fn suggest(&self, name: &str) -> Option<&'a str> {
+        let limit = name.chars().count().max(3) / 3;
+        self.functions
+            .keys()
+            .map(|candidate| (edit_distance(name, candidate), *candidate))
+            .filter(|(distance, _)| *distance <= limit)
+            .min()
+            .map(|(_, candidate)| candidate)
+    }
+}
+
+/// Edit distance between two names, counted in inserted, removed, replaced or swapped adjacent
+/// characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    // distances[i][j] is the distance between the first i characters of a and the first j of b
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    distances[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let replace = distances[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = replace.min(distances[i - 1][j] + 1).min(distances[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+/// A functioncall or functiondefinition that does not fit to the other functiondefinitions
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolError {
+    pub kind: SymbolErrorKind,
+    /// Location of the call or definition, the default span for [SymbolErrorKind::MissingMain]
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SymbolErrorKind {
+    /// A call passes a different number of arguments than the function has parameters
+    ArityMismatch {
+        function: String,
+        expected: usize,
+        found: usize,
+    },
+    /// A call refers to a function that is not defined
+    UndefinedFunction {
+        name: String,
+        /// A defined function with a similar name
+        suggestion: Option<String>,
+    },
+    /// A function is defined again, `previous` is the location of the name of the first definition
+    DuplicateFunction {
+        name: String,
+        previous: Span,
+    },
+    /// The program does not define a `main` function
+    MissingMain,
+    /// `main` has to return `int` or `void`
+    MainReturnType(Type),
+    /// `main` cannot take parameters
+    MainParameters(usize),
+}
+
+impl fmt::Display for SymbolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.kind != SymbolErrorKind::MissingMain {
+            write!(f, "line {}, column {}: ", self.span.line, self.span.column)?;
+        }
+        match &self.kind {
+            SymbolErrorKind::ArityMismatch { function, expected, found } => write!(
+                f,
+                "function '{}' takes {} argument{} but {} {} supplied",
+                function,
+                expected,
+                if *expected == 1 { "" } else { "s" },
+                found,
+                if *found == 1 { "was" } else { "were" }
+            ),
+            SymbolErrorKind::UndefinedFunction { name, suggestion } => {
+                write!(f, "function '{}' is not defined", name)?;
+                match suggestion {
+                    Some(suggestion) => write!(f, ", did you mean '{}'?", suggestion),
+                    None => Ok(()),
+                }
+            }
+            SymbolErrorKind::DuplicateFunction { name, previous } => {
+                write!(f, "function '{}' is already defined in line {}", name, previous.line)
+            }
+            SymbolErrorKind::MissingMain => f.write_str("the program does not define a 'main' function"),
+            SymbolErrorKind::MainReturnType(ty) => write!(f, "'main' has to return 'int' or 'void', not '{}'", ty),
+            SymbolErrorKind::MainParameters(count) => {
+                write!(f, "'main' cannot take parameters, but it has {}", count)
+            }
+        }
+    }
+}
+
+impl Error for SymbolError {}
+
+/// Check the functiondefinitions of the program and every functioncall against the
+/// functiondefinition it refers to.
+///
+/// Duplicate definitions are reported first, then problems with `main` and then the errors of the
+/// calls in the order of the program.
+/// ```
+/// use cb_3::C1Parser;
+/// use cb_3::symbols::{check_functions, SymbolErrorKind};
+///
+/// let program = C1Parser::parse("int f(int a) { return a; } void main() { f(1, 2); g(); }").unwrap();
+/// let errors = check_functions(&program);
+/// assert_eq!(
+///     errors[0].kind,
+///     SymbolErrorKind::ArityMismatch { function: "f".to_string(), expected: 1, found: 2 }
+/// );
+/// assert_eq!(errors[1].to_string(), "line 1, column 51: function 'g' is not defined, did you mean 'f'?");
+/// ```
+pub fn check_functions(program: &Program) -> Vec<SymbolError> {
+    let table = FunctionTable::new(program);
+    let mut errors = Vec::new();
+    for function in &program.functions {
+        let first = table.get(&function.name.name).unwrap();
+        if !std::ptr::eq(first, function) {
+            errors.push(SymbolError {
+                kind: SymbolErrorKind::DuplicateFunction { name: function.name.name.clone(), previous: first.name.span },
+                span: function.name.span,
+            });
+        }
+    }
+    match table.get("main") {
+        Some(main) => {
+            if !matches!(main.return_type, Type::Int | Type::Void) {
+                errors.push(SymbolError { kind: SymbolErrorKind::MainReturnType(main.return_type), span: main.span });
+            }
+            if !main.parameters.is_empty() {
+                errors.push(SymbolError { kind: SymbolErrorKind::MainParameters(main.parameters.len()), span: main.parameters[0].span });
+            }
+        }
+        None => errors.push(SymbolError { kind: SymbolErrorKind::MissingMain, span: Span::default() }),
+    }
+    for value in program.globals.iter().filter_map(|global| global.value.as_ref()) {
+        visit_calls_in_expr(value, &mut |call| check_call(&table, call, &mut errors));
+    }
+    for function in &program.functions {
+        for statement in &function.body.statements {
+            visit_calls_in_statement(statement, &mut |call| check_call(&table, call, &mut errors));
+        }
+    }
+    errors
+}
+
+fn check_call(table: &FunctionTable, call: &FunctionCall, errors: &mut Vec<SymbolError>) {
+    match table.get(&call.name.name) {
+        Some(function) => {
+            if function.parameters.len() != call.arguments.len() {
+                errors.push(SymbolError {
+                    kind: SymbolErrorKind::ArityMismatch {
+                        function: call.name.name.clone(),
+                        expected: function.parameters.len(),
+                        found: call.arguments.len(),
+                    },
+                    span: call.span,
+                });
+            }
+        }
+        None => errors.push(SymbolError {
+            kind: SymbolErrorKind::UndefinedFunction {
+                name: call.name.name.clone(),
+                suggestion: table.suggest(&call.name.name).map(str::to_string),
+            },
+            span: call.span,
+        }),
+    }
+}
+
+/// Call `visit` for every functioncall in the statement, outer calls before the calls in their
+/// arguments
+fn visit_calls_in_statement(statement: &Statement, visit: &mut impl FnMut(&FunctionCall)) {
+    match &statement.kind {
+        StatementKind::Block(block) => {
+            for statement in &block.statements {
+                visit_calls_in_statement(statement, visit);
+            }
+        }
+        StatementKind::If { condition, then_branch, else_branch } => {
+            visit_calls_in_expr(condition, visit);
+            visit_calls_in_statement(then_branch, visit);
+            if let Some(else_branch) = else_branch {
+                visit_calls_in_statement(else_branch, visit);
+            }
+        }
+        StatementKind::For { init, condition, step, body } => {
+            visit_calls_in_expr(&init.value, visit);
+            visit_calls_in_expr(condition, visit);
+            visit_calls_in_expr(&step.value, visit);
+            visit_calls_in_statement(body, visit);
+        }
+        StatementKind::While { condition, body } => {
+            visit_calls_in_expr(condition, visit);
+            visit_calls_in_statement(body, visit);
+        }
+        StatementKind::DoWhile { body, condition } => {
+            visit_calls_in_statement(body, visit);
+            visit_calls_in_expr(condition, visit);
+        }
+        StatementKind::Return(value) => {
+            if let Some(value) = value {
+                visit_calls_in_expr(value, visit);
+            }
+        }
+        StatementKind::Declaration(declaration) => {
+            if let Some(value) = &declaration.value {
+                visit_calls_in_expr(value, visit);
+            }
+        }
+        StatementKind::Printf(value) => visit_calls_in_expr(value, visit),
+        StatementKind::Assignment(assignment) => visit_calls_in_expr(&assignment.value, visit),
+        StatementKind::Call(call) => visit_call(call, visit),
+    }
+}
+
+fn visit_calls_in_expr(expr: &Expr, visit: &mut impl FnMut(&FunctionCall)) {
+    match &expr.kind {
+        ExprKind::Int(_) | ExprKind::Float(_) | ExprKind::Bool(_) | ExprKind::Variable(_) => {}
+        ExprKind::Call(call) => visit_call(call, visit),
+        ExprKind::Assign(assignment) => visit_calls_in_expr(&assignment.value, visit),
+        ExprKind::Unary { operand, .. } => visit_calls_in_expr(operand, visit),
+        ExprKind::Binary { lhs, rhs, .. } => {
+            visit_calls_in_expr(lhs, visit);
+            visit_calls_in_expr(rhs, visit);
+        }
+    }
+}
+
+fn visit_call(call: &FunctionCall, visit: &mut impl FnMut(&FunctionCall)) {
+    visit(call);
+    for argument in &call.arguments {
+        visit_calls_in_expr(argument, visit);
+    }
+}
@@ -0,0 +1,402 @@
+//! Static type checker for C(-1) programs.
+//!
+//! The checker infers the type of every expression and reports every place where a value of the
+//! wrong type is used. The typing rules are:
+//! - literals have the type of their token, a variable has its declared type, and a call has the
+//!   return type of the called function
+//! - `-`, `+`, `*` and `/` take `int` and `float` operands. If both operands are `int` the result is
+//!   `int`, otherwise the `int` operand is promoted to `float` and the result is `float`
+//! - `<`, `<=`, `>` and `>=` take `int` and `float` operands with the same promotion, `==` and `!=`
+//!   additionally compare two `bool` operands. All of them result in `bool`
+//! - `&&` and `||` take and result in `bool`
+//! - a value can be stored in a variable, passed as an argument or returned from a function if it
+//!   has the required type or if it is an `int` and `float` is required. An assignment has the
+//!   type of its variable
+//! - conditions of `if`, `for`, `while` and `do`-`while` have to be `bool`
+//! - `printf` takes any value except the result of a void function
+//! - variables and parameters cannot be declared `void`
+//!
+//! A variable that is assigned without being declared gets the type of the first value assigned to
+//! it, in the order of the source text. Every later value has to have exactly that type, an `int`
+//! is not promoted, because the [interpreter](crate::interpreter) stores it unchanged. Variables
+//! are looked up with [resolve](crate::resolve::resolve), names that cannot be resolved are left to
+//! it. Calls of undefined functions and calls with the wrong number of arguments are left to
+//! [check_functions](crate::symbols::check_functions), return statements without a value and
+//! return statements in void functions to [check_returns](crate::flow::check_returns). An
+//! expression whose type depends on such an error is reported as [TypeErrorKind::UnknownType] if
+//! there is no other type error, so that every expression of a program that passes the check has
+//! a type.
+
+use crate::ast::{Assignment, BinaryOp, Declaration, Expr, ExprKind, FunctionCall, FunctionDefinition, Program, Statement, StatementKind, Type, UnaryOp};
+use crate::resolve::{resolve, BindingId, Resolution};
+use crate::symbols::FunctionTable;
+use crate::Span;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// The types of all expressions and variables of a well-typed program
+#[derive(Debug, Clone, Default)]
+pub struct TypeTable {
+    expressions: HashMap<Span, Type>,
+    variables: HashMap<BindingId, Type>,
+}
+
+impl TypeTable {
+    /// Return the type of an expression of the checked program
+    pub fn expr(&self, expr: &Expr) -> Type {
+        self.expressions[&expr.span]
+    }
+
+    /// Return the declared or inferred type of a variable of the checked program
+    pub fn variable(&self, id: BindingId) -> Type {
+        self.variables[&id]
+    }
+}
+
+/// A value of the wrong type
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeError {
+    pub kind: TypeErrorKind,
+    /// Location of the expression or statement with the wrong type
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeErrorKind {
+    /// A value is stored, passed or returned where a different type is required
+    Mismatch {
+        expected: Type,
+        found: Type,
+    },
+    /// The condition of an `if` or a loop is not `bool`
+    NonBooleanCondition(Type),
+    InvalidOperand {
+        op: UnaryOp,
+        operand: Type,
+    },
+    InvalidOperands {
+        op: BinaryOp,
+        lhs: Type,
+        rhs: Type,
+    },
+    /// `printf` is given the result of a void function
+    PrintVoid,
+    /// A variable or parameter is declared with type `void`
+    VoidVariable(String),
+    /// The type of an expression cannot be determined because it uses an undefined function or
+    /// variable, or an implicit variable before its first assignment
+    UnknownType,
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.span.line, self.span.column)?;
+        match &self.kind {
+            TypeErrorKind::Mismatch { expected, found } => write!(f, "expected a value of type '{}', found '{}'", expected, found),
+            TypeErrorKind::NonBooleanCondition(found) => write!(f, "condition must be of type 'bool', found '{}'", found),
+            TypeErrorKind::InvalidOperand { op, operand } => write!(f, "operator '{}' cannot be applied to '{}'", op, operand),
+            TypeErrorKind::InvalidOperands { op, lhs, rhs } => write!(f, "operator '{}' cannot be applied to '{}' and '{}'", op, lhs, rhs),
+            TypeErrorKind::PrintVoid => f.write_str("printf cannot print the result of a void function"),
+            TypeErrorKind::VoidVariable(name) => write!(f, "variable '{}' cannot be of type 'void'", name),
+            TypeErrorKind::UnknownType => f.write_str("the type of the expression cannot be determined"),
+        }
+    }
+}
+
+impl Error for TypeError {}
+
+/// Check the types of the whole program and return the type of every expression.
+///
+/// All errors are reported in the order of the source text. An expression whose type cannot be
+/// determined because of an earlier error does not cause further errors.
+/// ```
+/// use cb_3::C1Parser;
+/// use cb_3::ast::{StatementKind, Type};
+/// use cb_3::typeck::{check_types, TypeErrorKind};
+///
+/// let program = C1Parser::parse("float f() { x = 1; return x * 2.5; }").unwrap();
+/// let types = check_types(&program).unwrap();
+/// match &program.functions[0].body.statements[1].kind {
+///     StatementKind::Return(Some(value)) => assert_eq!(types.expr(value), Type::Float),
+///     _ => unreachable!(),
+/// }
+///
+/// let program = C1Parser::parse("void f() { if (1) printf(2); }").unwrap();
+/// let errors = check_types(&program).unwrap_err();
+/// assert_eq!(errors[0].kind, TypeErrorKind::NonBooleanCondition(Type::Int));
+/// ```
+pub fn check_types(program: &Program) -> Result<TypeTable, Vec<TypeError>> {
+    let mut checker = Checker {
+        functions: FunctionTable::new(program),
+        resolution: resolve(program).0,
+        variables: HashMap::new(),
+        types: TypeTable::default(),
+        errors: Vec::new(),
+        untyped: Vec::new(),
+    };
+    for declaration in &program.globals {
+        checker.declaration(declaration);
+    }
+    for function in &program.functions {
+        checker.function(function);
+    }
+    if checker.errors.is_empty() && !checker.untyped.is_empty() {
+        Err(checker.untyped.into_iter().map(|span| TypeError { kind: TypeErrorKind::UnknownType, span }).collect())
+    } else if checker.errors.is_empty() {
+        for (id, binding) in checker.resolution.bindings() {
+            let ty = binding.ty.or_else(|| checker.variables.get(&id).copied().flatten());
+            if let Some(ty) = ty {
+                checker.types.variables.insert(id, ty);
+            }
+        }
+        Ok(checker.types)
+    } else {
+        Err(checker.errors)
+    }
+}
+
+/// Return whether a value of type `found` can be stored where `expected` is required
+fn assignable(found: Type, expected: Type) -> bool {
+    found == expected || (found == Type::Int && expected == Type::Float)
+}
+
+fn is_number(ty: Type) -> bool {
+    matches!(ty, Type::Int | Type::Float)
+}
+
+struct Checker<'p> {
+    functions: FunctionTable<'p>,
+    resolution: Resolution,
+    /// Types of the variables, `None` if it is unknown because of an error that was already reported
+    variables: HashMap<BindingId, Option<Type>>,
+    types: TypeTable,
+    errors: Vec<TypeError>,
+    /// Expressions without a type that did not cause an error
+    untyped: Vec<Span>,
+}
+
+impl<'p> Checker<'p> {
+    fn error(&mut self, kind: TypeErrorKind, span: Span) {
+        self.errors.push(TypeError { kind, span });
+    }
+
+    /// Record an expression whose type is unknown because of an error that another check reports
+    fn untyped(&mut self, span: Span) -> Option<Type> {
+        self.untyped.push(span);
+        None
+    }
+
+    fn function(&mut self, function: &'p FunctionDefinition) {
+        for parameter in &function.parameters {
+            if parameter.ty == Type::Void {
+                self.error(TypeErrorKind::VoidVariable(parameter.name.name.clone()), parameter.span);
+            }
+        }
+        for statement in &function.body.statements {
+            self.statement(statement, function);
+        }
+    }
+
+    fn statement(&mut self, statement: &'p Statement, function: &'p FunctionDefinition) {
+        match &statement.kind {
+            StatementKind::Block(block) => {
+                for statement in &block.statements {
+                    self.statement(statement, function);
+                }
+            }
+            StatementKind::Declaration(declaration) => self.declaration(declaration),
+            StatementKind::If { condition, then_branch, else_branch } => {
+                self.condition(condition);
+                self.statement(then_branch, function);
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch, function);
+                }
+            }
+            StatementKind::For { init, condition, step, body } => {
+                self.assignment(init);
+                self.condition(condition);
+                self.assignment(step);
+                self.statement(body, function);
+            }
+            StatementKind::While { condition, body } => {
+                self.condition(condition);
+                self.statement(body, function);
+            }
+            StatementKind::DoWhile { body, condition } => {
+                self.statement(body, function);
+                self.condition(condition);
+            }
+            StatementKind::Return(Some(value)) => {
+                if function.return_type == Type::Void {
+                    self.expr(value);
+                } else {
+                    self.expect(value, function.return_type);
+                }
+            }
+            StatementKind::Return(None) => {}
+            StatementKind::Printf(value) => {
+                if self.expr(value) == Some(Type::Void) {
+                    self.error(TypeErrorKind::PrintVoid, value.span);
+                }
+            }
+            StatementKind::Assignment(assignment) => {
+                self.assignment(assignment);
+            }
+            StatementKind::Call(call) => {
+                self.call(call);
+            }
+        }
+    }
+
+    fn declaration(&mut self, declaration: &'p Declaration) {
+        if declaration.ty == Type::Void {
+            self.error(TypeErrorKind::VoidVariable(declaration.name.name.clone()), declaration.span);
+            if let Some(value) = &declaration.value {
+                self.expr(value);
+            }
+        } else if let Some(value) = &declaration.value {
+            self.expect(value, declaration.ty);
+        }
+    }
+
+    fn condition(&mut self, condition: &'p Expr) {
+        match self.expr(condition) {
+            Some(Type::Boolean) | None => {}
+            Some(found) => self.error(TypeErrorKind::NonBooleanCondition(found), condition.span),
+        }
+    }
+
+    /// Check that the expression can be converted to the expected type
+    fn expect(&mut self, expr: &'p Expr, expected: Type) {
+        if let Some(found) = self.expr(expr) {
+            if !assignable(found, expected) {
+                self.error(TypeErrorKind::Mismatch { expected, found }, expr.span);
+            }
+        }
+    }
+
+    /// Return the type of a variable, the outer `None` if it is an implicit variable that has not
+    /// been assigned yet
+    fn variable(&self, id: BindingId) -> Option<Option<Type>> {
+        match self.resolution.binding(id).ty {
+            Some(Type::Void) => Some(None),
+            Some(ty) => Some(Some(ty)),
+            None => self.variables.get(&id).copied(),
+        }
+    }
+
+    fn is_implicit(&self, id: BindingId) -> bool {
+        self.resolution.binding(id).ty.is_none()
+    }
+
+    /// Check an assignment and return the type of its variable. The first assignment to an
+    /// implicit variable determines its type.
+    fn assignment(&mut self, assignment: &'p Assignment) -> Option<Type> {
+        let id = match self.resolution.lookup(&assignment.target) {
+            Some(id) => id,
+            None => {
+                self.expr(&assignment.value);
+                return None;
+            }
+        };
+        match self.variable(id) {
+            Some(Some(ty)) if self.is_implicit(id) => {
+                if let Some(found) = self.expr(&assignment.value) {
+                    if found != ty {
+                        self.error(TypeErrorKind::Mismatch { expected: ty, found }, assignment.value.span);
+                    }
+                }
+                Some(ty)
+            }
+            Some(Some(ty)) => {
+                self.expect(&assignment.value, ty);
+                Some(ty)
+            }
+            Some(None) => {
+                self.expr(&assignment.value);
+                None
+            }
+            None => {
+                let mut ty = self.expr(&assignment.value);
+                if ty == Some(Type::Void) {
+                    self.error(TypeErrorKind::VoidVariable(assignment.target.name.clone()), assignment.value.span);
+                    ty = None;
+                }
+                self.variables.insert(id, ty);
+                ty
+            }
+        }
+    }
+
+    fn call(&mut self, call: &'p FunctionCall) -> Option<Type> {
+        let function = self.functions.get(&call.name.name);
+        for (index, argument) in call.arguments.iter().enumerate() {
+            match function.and_then(|function| function.parameters.get(index)) {
+                Some(parameter) => self.expect(argument, parameter.ty),
+                None => {
+                    self.expr(argument);
+                }
+            }
+        }
+        function.map(|function| function.return_type)
+    }
+
+    /// Infer the type of an expression, `None` if it depends on an error that was already reported
+    /// or on an undefined function
+    fn expr(&mut self, expr: &'p Expr) -> Option<Type> {
+        let ty = match &expr.kind {
+            ExprKind::Int(_) => Type::Int,
+            ExprKind::Float(_) => Type::Float,
+            ExprKind::Bool(_) => Type::Boolean,
+            ExprKind::Variable(identifier) => {
+                // an implicit variable can only be read after its first assignment
+                match self.resolution.lookup(identifier).and_then(|id| self.variable(id).flatten()) {
+                    Some(ty) => ty,
+                    None => return self.untyped(expr.span),
+                }
+            }
+            ExprKind::Call(call) => match self.call(call) {
+                Some(ty) => ty,
+                None => return self.untyped(expr.span),
+            },
+            ExprKind::Assign(assignment) => match self.assignment(assignment) {
+                Some(ty) => ty,
+                None => return self.untyped(expr.span),
+            },
+            ExprKind::Unary { op, operand } => match self.expr(operand)? {
+                operand if is_number(operand) => operand,
+                operand => {
+                    self.error(TypeErrorKind::InvalidOperand { op: *op, operand }, expr.span);
+                    return None;
+                }
+            },
+            ExprKind::Binary { op, lhs, rhs } => {
+                let (lhs, rhs) = (self.expr(lhs), self.expr(rhs));
+                let (lhs, rhs) = (lhs?, rhs?);
+                match op {
+                    BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div if is_number(lhs) && is_number(rhs) => {
+                        if lhs == Type::Int && rhs == Type::Int {
+                            Type::Int
+                        } else {
+                            Type::Float
+                        }
+                    }
+                    BinaryOp::And | BinaryOp::Or if lhs == Type::Boolean && rhs == Type::Boolean => Type::Boolean,
+                    BinaryOp::Equal | BinaryOp::NotEqual if lhs == Type::Boolean && rhs == Type::Boolean => Type::Boolean,
+                    BinaryOp::Equal | BinaryOp::NotEqual | BinaryOp::Less | BinaryOp::LessEqual | BinaryOp::Greater | BinaryOp::GreaterEqual
+                        if is_number(lhs) && is_number(rhs) =>
+                    {
+                        Type::Boolean
+                    }
+                    _ => {
+                        self.error(TypeErrorKind::InvalidOperands { op: *op, lhs, rhs }, expr.span);
+                        return None;
+                    }
+                }
+            }
+        };
+        self.types.expressions.insert(expr.span, ty);
+        Some(ty)
+    }
+}
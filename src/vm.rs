@@ -0,0 +1,226 @@
+//! Stack-based virtual machine that executes [bytecode](crate::bytecode).
+//!
+//! The virtual machine has the semantics of the [interpreter](crate::interpreter) and produces the
+//! same output, return values and runtime errors. Calls of the program do not nest calls of the
+//! virtual machine, so no large native stack is needed.
+
+use crate::ast::{BinaryOp, Type, UnaryOp};
+use crate::bytecode::{compile, Function, Instruction, Module};
+use crate::interpreter::{binary, error, RuntimeError, RuntimeErrorKind, RuntimeResult, Value, MAX_CALL_DEPTH};
+use crate::{C1Parser, Span};
+use std::io::Write;
+
+/// Parse and compile the source text and execute its `main` function. The output of `printf` is
+/// written to `out`, the return value of `main` is returned.
+/// ```
+/// use cb_3::interpreter::Value;
+/// use cb_3::vm::run;
+///
+/// let mut out = Vec::new();
+/// let result = run("int main() { printf(1.5 * 2); return 7; }", &mut out);
+/// assert_eq!(result, Ok(Value::Int(7)));
+/// assert_eq!(String::from_utf8(out).unwrap(), "3.000000\n");
+/// ```
+pub fn run(source: &str, out: &mut impl Write) -> Result<Value, RuntimeError> {
+    let program = C1Parser::parse(source)?;
+    let module = compile(&program);
+    execute(&module, out)
+}
+
+/// Initialize the global variables of a compiled program and execute its `main` function.
+pub fn execute(module: &Module, out: &mut impl Write) -> Result<Value, RuntimeError> {
+    let mut machine = Machine { module, globals: vec![None; module.globals.len()], locals: Vec::new(), stack: Vec::new(), frames: Vec::new(), out };
+    machine.frames.push(Frame { function: &module.init, pc: 0, base: 0 });
+    machine.execute()?;
+    let main = module.main().ok_or(RuntimeError { kind: RuntimeErrorKind::UndefinedFunction("main".to_string()), span: None })?;
+    let function = &module.functions[main];
+    if !function.parameters.is_empty() {
+        let kind = RuntimeErrorKind::ArityMismatch { function: function.name.clone(), expected: function.parameters.len(), found: 0 };
+        return error(kind, function.span);
+    }
+    machine.locals.resize(function.locals.len(), None);
+    machine.frames.push(Frame { function, pc: 0, base: 0 });
+    machine.execute()
+}
+
+/// A call that is executed
+struct Frame<'m> {
+    function: &'m Function,
+    /// Offset of the next instruction
+    pc: usize,
+    /// Position of the first local variable of the call in the locals of the machine
+    base: usize,
+}
+
+struct Machine<'m, W> {
+    module: &'m Module,
+    /// Global variables by their slot, `None` until a value is assigned
+    globals: Vec<Option<Value>>,
+    /// Local variables of all calls
+    locals: Vec<Option<Value>>,
+    stack: Vec<Value>,
+    frames: Vec<Frame<'m>>,
+    out: W,
+}
+
+impl<W: Write> Machine<'_, W> {
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("the compiler keeps the stack balanced")
+    }
+
+    /// Execute instructions until the call of the outermost frame returns
+    fn execute(&mut self) -> RuntimeResult<Value> {
+        loop {
+            let frame = self.frames.last_mut().expect("a call is executed");
+            let function = frame.function;
+            let pc = frame.pc;
+            let base = frame.base;
+            frame.pc += 1;
+            let span = function.span_at(pc);
+            match function.code[pc] {
+                Instruction::Const(index) => self.stack.push(self.module.constants[index as usize]),
+                Instruction::Void => self.stack.push(Value::Void),
+                Instruction::Load(slot) => match self.locals[base + slot as usize] {
+                    Some(value) => self.stack.push(value),
+                    None => return error(RuntimeErrorKind::UninitializedVariable(function.locals[slot as usize].clone()), span),
+                },
+                Instruction::Store(slot) => self.locals[base + slot as usize] = Some(self.pop()),
+                Instruction::Clear(slot) => self.locals[base + slot as usize] = None,
+                Instruction::LoadGlobal(slot) => match self.globals[slot as usize] {
+                    Some(value) => self.stack.push(value),
+                    None => {
+                        let name = self.module.globals[slot as usize].name.clone();
+                        return error(RuntimeErrorKind::UninitializedVariable(name), span);
+                    }
+                },
+                Instruction::StoreGlobal(slot) => self.globals[slot as usize] = Some(self.pop()),
+                Instruction::Convert(ty) => {
+                    let value = self.pop();
+                    match value.convert(ty) {
+                        Some(value) => self.stack.push(value),
+                        None => return error(RuntimeErrorKind::TypeMismatch { expected: ty, found: value.ty() }, span),
+                    }
+                }
+                Instruction::Dup => {
+                    let value = *self.stack.last().expect("the compiler keeps the stack balanced");
+                    self.stack.push(value);
+                }
+                Instruction::Pop => {
+                    self.pop();
+                }
+                Instruction::Neg => {
+                    let value = match self.pop() {
+                        Value::Int(value) => Value::Int(value.wrapping_neg()),
+                        Value::Float(value) => Value::Float(-value),
+                        value => return error(RuntimeErrorKind::InvalidOperand { op: UnaryOp::Neg, operand: value.ty() }, span),
+                    };
+                    self.stack.push(value);
+                }
+                Instruction::Binary(op) => {
+                    let rhs = self.pop();
+                    let lhs = self.pop();
+                    let value = match (op, lhs, rhs) {
+                        (BinaryOp::And | BinaryOp::Or, Value::Bool(_), Value::Bool(_)) => rhs,
+                        (BinaryOp::And | BinaryOp::Or, lhs, rhs) => {
+                            return error(RuntimeErrorKind::InvalidOperands { op, lhs: lhs.ty(), rhs: rhs.ty() }, span)
+                        }
+                        _ => binary(op, lhs, rhs, span)?,
+                    };
+                    self.stack.push(value);
+                }
+                Instruction::Jump(target) => self.jump(target),
+                Instruction::JumpIfFalse(target) => match self.pop() {
+                    Value::Bool(true) => {}
+                    Value::Bool(false) => self.jump(target),
+                    value => return error(RuntimeErrorKind::TypeMismatch { expected: Type::Boolean, found: value.ty() }, span),
+                },
+                Instruction::ShortCircuit(op, target) => {
+                    if self.stack.last() == Some(&Value::Bool(op == BinaryOp::Or)) {
+                        self.jump(target);
+                    }
+                }
+                Instruction::CheckCall { function: index, arguments } => self.check_call(index, arguments, span)?,
+                Instruction::ConvertArgument(above, ty) => {
+                    let position = self.stack.len() - 1 - above as usize;
+                    let value = self.stack[position];
+                    match value.convert(ty) {
+                        Some(value) => self.stack[position] = value,
+                        None => return error(RuntimeErrorKind::TypeMismatch { expected: ty, found: value.ty() }, span),
+                    }
+                }
+                Instruction::Call { function: index, arguments } => {
+                    self.check_call(index, arguments, span)?;
+                    let callee = &self.module.functions[index as usize];
+                    let arguments = arguments as usize;
+                    let base = self.locals.len();
+                    self.locals.resize(base + callee.locals.len(), None);
+                    for (slot, value) in self.stack.drain(self.stack.len() - arguments..).enumerate() {
+                        self.locals[base + slot] = Some(value);
+                    }
+                    self.frames.push(Frame { function: callee, pc: 0, base });
+                }
+                Instruction::Ret => {
+                    let value = self.pop();
+                    let value = match value.convert(function.return_type) {
+                        Some(value) => value,
+                        None => return error(RuntimeErrorKind::TypeMismatch { expected: function.return_type, found: value.ty() }, span),
+                    };
+                    if let Some(value) = self.ret(value) {
+                        return Ok(value);
+                    }
+                }
+                Instruction::End if function.return_type == Type::Void => {
+                    if let Some(value) = self.ret(Value::Void) {
+                        return Ok(value);
+                    }
+                }
+                Instruction::End => return error(RuntimeErrorKind::MissingReturn(function.name.clone()), span),
+                Instruction::UndefinedVariable(index) => {
+                    return error(RuntimeErrorKind::UndefinedVariable(self.module.names[index as usize].clone()), span)
+                }
+                Instruction::UndefinedFunction(index) => {
+                    return error(RuntimeErrorKind::UndefinedFunction(self.module.names[index as usize].clone()), span)
+                }
+                Instruction::Print => match self.pop() {
+                    Value::Void => return error(RuntimeErrorKind::PrintVoid, span),
+                    value => {
+                        if let Err(io_error) = writeln!(self.out, "{}", value) {
+                            return error(RuntimeErrorKind::Output(io_error.to_string()), span);
+                        }
+                    }
+                },
+            }
+        }
+    }
+
+    fn check_call(&self, function: u32, arguments: u32, span: Span) -> RuntimeResult<()> {
+        let callee = &self.module.functions[function as usize];
+        let arguments = arguments as usize;
+        if callee.parameters.len() != arguments {
+            let kind = RuntimeErrorKind::ArityMismatch { function: callee.name.clone(), expected: callee.parameters.len(), found: arguments };
+            return error(kind, span);
+        }
+        // the frame of the global initialization is not a call of the program
+        let depth = self.frames.len() - usize::from(std::ptr::eq(self.frames[0].function, &self.module.init));
+        if depth == MAX_CALL_DEPTH {
+            return error(RuntimeErrorKind::StackOverflow, span);
+        }
+        Ok(())
+    }
+
+    fn jump(&mut self, target: u32) {
+        self.frames.last_mut().expect("a call is executed").pc = target as usize;
+    }
+
+    /// Return from the current call. Returns the value if the outermost call returned, otherwise it
+    /// is pushed for the caller.
+    fn ret(&mut self, value: Value) -> Option<Value> {
+        let frame = self.frames.pop().expect("a call is executed");
+        self.locals.truncate(frame.base);
+        if self.frames.is_empty() {
+            return Some(value);
+        }
+        self.stack.push(value);
+        None
+    }
+}
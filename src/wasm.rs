@@ -0,0 +1,1219 @@
+//! Backend that translates C(-1) programs to binary WebAssembly modules, and a decoder that checks
+//! their structure.
+//!
+//! The module needs no memory and imports its runtime from the host:
+//! - `int` and `bool` are `i32`, `float` is `f64`. Arithmetic on `i32` wraps around
+//! - every function definition becomes a function of the module, with its parameters and then its
+//!   other variables as locals in the order of their slots. Global variables are mutable globals
+//!   that start as zero and are initialized by the start function of the module
+//! - the `main` function of the program is exported as `main`
+//! - `printf` calls one of the functions [IMPORTS] lists, which have to print the value like the
+//!   [interpreter](crate::interpreter). An integer division by zero calls `division_by_zero` with
+//!   the line and column of the division and then traps
+//!
+//! The program has to pass every [check](crate::analysis::analyze).
+
+use crate::analysis::{analyze, Analysis, Diagnostic};
+use crate::ast::{Assignment, BinaryOp, Block, Expr, ExprKind, FunctionCall, FunctionDefinition, Identifier, Program, Statement, StatementKind, Type, UnaryOp};
+use crate::resolve::BindingKind;
+use crate::symbols::FunctionTable;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+pub const MAGIC: &[u8; 4] = b"\0asm";
+
+/// The version of the binary format that is written, and the only one that is decoded
+pub const VERSION: u32 = 1;
+
+/// Module name of the imported runtime functions
+pub const RUNTIME_MODULE: &str = "c1";
+
+/// Names and parameter types of the imported runtime functions, which are the first functions of
+/// the module. None of them returns a value
+pub const IMPORTS: [(&str, &[ValueType]); 4] = [
+    ("print_int", &[ValueType::I32]),
+    ("print_float", &[ValueType::F64]),
+    ("print_bool", &[ValueType::I32]),
+    ("division_by_zero", &[ValueType::I32, ValueType::I32]),
+];
+
+const PRINT_INT: u32 = 0;
+const PRINT_FLOAT: u32 = 1;
+const PRINT_BOOL: u32 = 2;
+const DIVISION_BY_ZERO: u32 = 3;
+
+const SECTION_TYPE: u8 = 1;
+const SECTION_IMPORT: u8 = 2;
+const SECTION_FUNCTION: u8 = 3;
+const SECTION_GLOBAL: u8 = 6;
+const SECTION_EXPORT: u8 = 7;
+const SECTION_START: u8 = 8;
+const SECTION_CODE: u8 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueType {
+    I32,
+    F64,
+}
+
+impl ValueType {
+    fn of(ty: Type) -> Option<ValueType> {
+        match ty {
+            Type::Float => Some(ValueType::F64),
+            Type::Void => None,
+            _ => Some(ValueType::I32),
+        }
+    }
+
+    fn tag(self) -> u8 {
+        match self {
+            ValueType::I32 => 0x7f,
+            ValueType::F64 => 0x7c,
+        }
+    }
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ValueType::I32 => "i32",
+            ValueType::F64 => "f64",
+        })
+    }
+}
+
+/// The instructions the backend uses. `block`, `loop` and `if` carry the type of their result
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instruction {
+    Unreachable,
+    Block(Option<ValueType>),
+    Loop(Option<ValueType>),
+    If(Option<ValueType>),
+    Else,
+    End,
+    Br(u32),
+    BrIf(u32),
+    Return,
+    Call(u32),
+    Drop,
+    LocalGet(u32),
+    LocalSet(u32),
+    LocalTee(u32),
+    GlobalGet(u32),
+    GlobalSet(u32),
+    I32Const(i32),
+    F64Const(f64),
+    I32Eqz,
+    I32Eq,
+    I32Ne,
+    I32LtS,
+    I32GtS,
+    I32LeS,
+    I32GeS,
+    F64Eq,
+    F64Ne,
+    F64Lt,
+    F64Gt,
+    F64Le,
+    F64Ge,
+    I32Add,
+    I32Sub,
+    I32Mul,
+    I32DivS,
+    F64Neg,
+    F64Add,
+    F64Sub,
+    F64Mul,
+    F64Div,
+    F64ConvertI32S,
+}
+
+/// The instructions without immediates, which are decoded by looking up their opcode
+const SIMPLE: [Instruction; 28] = [
+    Instruction::Unreachable,
+    Instruction::Else,
+    Instruction::End,
+    Instruction::Return,
+    Instruction::Drop,
+    Instruction::I32Eqz,
+    Instruction::I32Eq,
+    Instruction::I32Ne,
+    Instruction::I32LtS,
+    Instruction::I32GtS,
+    Instruction::I32LeS,
+    Instruction::I32GeS,
+    Instruction::F64Eq,
+    Instruction::F64Ne,
+    Instruction::F64Lt,
+    Instruction::F64Gt,
+    Instruction::F64Le,
+    Instruction::F64Ge,
+    Instruction::I32Add,
+    Instruction::I32Sub,
+    Instruction::I32Mul,
+    Instruction::I32DivS,
+    Instruction::F64Neg,
+    Instruction::F64Add,
+    Instruction::F64Sub,
+    Instruction::F64Mul,
+    Instruction::F64Div,
+    Instruction::F64ConvertI32S,
+];
+
+impl Instruction {
+    fn opcode(self) -> u8 {
+        match self {
+            Instruction::Unreachable => 0x00,
+            Instruction::Block(_) => 0x02,
+            Instruction::Loop(_) => 0x03,
+            Instruction::If(_) => 0x04,
+            Instruction::Else => 0x05,
+            Instruction::End => 0x0b,
+            Instruction::Br(_) => 0x0c,
+            Instruction::BrIf(_) => 0x0d,
+            Instruction::Return => 0x0f,
+            Instruction::Call(_) => 0x10,
+            Instruction::Drop => 0x1a,
+            Instruction::LocalGet(_) => 0x20,
+            Instruction::LocalSet(_) => 0x21,
+            Instruction::LocalTee(_) => 0x22,
+            Instruction::GlobalGet(_) => 0x23,
+            Instruction::GlobalSet(_) => 0x24,
+            Instruction::I32Const(_) => 0x41,
+            Instruction::F64Const(_) => 0x44,
+            Instruction::I32Eqz => 0x45,
+            Instruction::I32Eq => 0x46,
+            Instruction::I32Ne => 0x47,
+            Instruction::I32LtS => 0x48,
+            Instruction::I32GtS => 0x4a,
+            Instruction::I32LeS => 0x4c,
+            Instruction::I32GeS => 0x4e,
+            Instruction::F64Eq => 0x61,
+            Instruction::F64Ne => 0x62,
+            Instruction::F64Lt => 0x63,
+            Instruction::F64Gt => 0x64,
+            Instruction::F64Le => 0x65,
+            Instruction::F64Ge => 0x66,
+            Instruction::I32Add => 0x6a,
+            Instruction::I32Sub => 0x6b,
+            Instruction::I32Mul => 0x6c,
+            Instruction::I32DivS => 0x6d,
+            Instruction::F64Neg => 0x9a,
+            Instruction::F64Add => 0xa0,
+            Instruction::F64Sub => 0xa1,
+            Instruction::F64Mul => 0xa2,
+            Instruction::F64Div => 0xa3,
+            Instruction::F64ConvertI32S => 0xb7,
+        }
+    }
+}
+
+/// Prints the instruction in the WebAssembly text format
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let block = |f: &mut fmt::Formatter<'_>, name: &str, ty: &Option<ValueType>| match ty {
+            Some(ty) => write!(f, "{} (result {})", name, ty),
+            None => f.write_str(name),
+        };
+        match self {
+            Instruction::Block(ty) => block(f, "block", ty),
+            Instruction::Loop(ty) => block(f, "loop", ty),
+            Instruction::If(ty) => block(f, "if", ty),
+            Instruction::Br(depth) => write!(f, "br {}", depth),
+            Instruction::BrIf(depth) => write!(f, "br_if {}", depth),
+            Instruction::Call(function) => write!(f, "call {}", function),
+            Instruction::LocalGet(index) => write!(f, "local.get {}", index),
+            Instruction::LocalSet(index) => write!(f, "local.set {}", index),
+            Instruction::LocalTee(index) => write!(f, "local.tee {}", index),
+            Instruction::GlobalGet(index) => write!(f, "global.get {}", index),
+            Instruction::GlobalSet(index) => write!(f, "global.set {}", index),
+            Instruction::I32Const(value) => write!(f, "i32.const {}", value),
+            Instruction::F64Const(value) => write!(f, "f64.const {:?}", value),
+            Instruction::Unreachable => f.write_str("unreachable"),
+            Instruction::Else => f.write_str("else"),
+            Instruction::End => f.write_str("end"),
+            Instruction::Return => f.write_str("return"),
+            Instruction::Drop => f.write_str("drop"),
+            Instruction::I32Eqz => f.write_str("i32.eqz"),
+            Instruction::I32Eq => f.write_str("i32.eq"),
+            Instruction::I32Ne => f.write_str("i32.ne"),
+            Instruction::I32LtS => f.write_str("i32.lt_s"),
+            Instruction::I32GtS => f.write_str("i32.gt_s"),
+            Instruction::I32LeS => f.write_str("i32.le_s"),
+            Instruction::I32GeS => f.write_str("i32.ge_s"),
+            Instruction::F64Eq => f.write_str("f64.eq"),
+            Instruction::F64Ne => f.write_str("f64.ne"),
+            Instruction::F64Lt => f.write_str("f64.lt"),
+            Instruction::F64Gt => f.write_str("f64.gt"),
+            Instruction::F64Le => f.write_str("f64.le"),
+            Instruction::F64Ge => f.write_str("f64.ge"),
+            Instruction::I32Add => f.write_str("i32.add"),
+            Instruction::I32Sub => f.write_str("i32.sub"),
+            Instruction::I32Mul => f.write_str("i32.mul"),
+            Instruction::I32DivS => f.write_str("i32.div_s"),
+            Instruction::F64Neg => f.write_str("f64.neg"),
+            Instruction::F64Add => f.write_str("f64.add"),
+            Instruction::F64Sub => f.write_str("f64.sub"),
+            Instruction::F64Mul => f.write_str("f64.mul"),
+            Instruction::F64Div => f.write_str("f64.div"),
+            Instruction::F64ConvertI32S => f.write_str("f64.convert_i32_s"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FunctionType {
+    pub parameters: Vec<ValueType>,
+    pub results: Vec<ValueType>,
+}
+
+/// An imported function
+#[derive(Debug, Clone, PartialEq)]
+pub struct Import {
+    pub module: String,
+    pub name: String,
+    /// Index of the function type
+    pub ty: u32,
+}
+
+/// A mutable global, initialized with a constant
+#[derive(Debug, Clone, PartialEq)]
+pub struct Global {
+    pub ty: ValueType,
+    pub init: Instruction,
+}
+
+/// An exported function
+#[derive(Debug, Clone, PartialEq)]
+pub struct Export {
+    pub name: String,
+    pub function: u32,
+}
+
+/// The code of a defined function
+#[derive(Debug, Clone, PartialEq)]
+pub struct Body {
+    /// Types of the locals after the parameters
+    pub locals: Vec<ValueType>,
+    /// The instructions, up to and including the `end` of the function
+    pub code: Vec<Instruction>,
+}
+
+/// The sections of a module. The functions are numbered with the imports first
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Module {
+    pub types: Vec<FunctionType>,
+    pub imports: Vec<Import>,
+    /// Type indices of the defined functions
+    pub functions: Vec<u32>,
+    pub globals: Vec<Global>,
+    pub exports: Vec<Export>,
+    pub start: Option<u32>,
+    /// Bodies of the defined functions
+    pub bodies: Vec<Body>,
+}
+
+impl Module {
+    /// Return the type of the function with the index, counting the imports
+    pub fn function_type(&self, function: u32) -> Option<&FunctionType> {
+        let function = function as usize;
+        let ty = match function.checked_sub(self.imports.len()) {
+            None => self.imports[function].ty,
+            Some(index) => *self.functions.get(index)?,
+        };
+        self.types.get(ty as usize)
+    }
+
+    /// Return the index of the function type, adding it if it does not exist yet
+    fn ty(&mut self, ty: FunctionType) -> u32 {
+        let index = self.types.iter().position(|other| *other == ty).unwrap_or_else(|| {
+            self.types.push(ty);
+            self.types.len() - 1
+        });
+        index as u32
+    }
+}
+
+/// A module that cannot be decoded
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeError {
+    pub kind: DecodeErrorKind,
+    /// Byte offset of the data that could not be read, or of the start of the invalid section,
+    /// function body or entry
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeErrorKind {
+    /// The module ends in the middle of the data
+    Truncated,
+    /// The module does not start with [MAGIC]
+    BadMagic,
+    UnsupportedVersion(u32),
+    /// A LEB128 integer that does not fit into its type
+    InvalidInteger,
+    /// A tag byte that is not defined or not supported for the kind of data, like "opcode" or
+    /// "section"
+    InvalidTag {
+        what: &'static str,
+        tag: u8,
+    },
+    InvalidString,
+    /// A section that appears after a section that has to follow it, or a second time
+    MisplacedSection(u8),
+    /// The content of the section does not end where its size says
+    SectionSize(u8),
+    /// The function and code sections have different lengths
+    FunctionCountMismatch {
+        functions: usize,
+        bodies: usize,
+    },
+    /// An index of a type or function outside of a function body that does not exist
+    OutOfRange {
+        what: &'static str,
+        index: u32,
+    },
+    /// A global that is immutable or not initialized with a single constant of its type
+    InvalidGlobal,
+    /// The start function has parameters or results
+    InvalidStart,
+    /// The instruction with the given position in the body of the function is invalid. The
+    /// function is numbered with the imports first
+    InvalidCode {
+        function: u32,
+        instruction: usize,
+        problem: CodeProblem,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CodeProblem {
+    LocalOutOfRange(u32),
+    GlobalOutOfRange(u32),
+    FunctionOutOfRange(u32),
+    /// A branch to a label that is not open
+    BranchOutOfRange(u32),
+    /// `else` outside of an `if` or a second time for the same `if`
+    UnexpectedElse,
+    /// Instructions follow the `end` of the function
+    UnexpectedEnd,
+    /// The body ends before the `end` of the function
+    MissingEnd,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "byte {}: ", self.offset)?;
+        match &self.kind {
+            DecodeErrorKind::Truncated => f.write_str("unexpected end of module"),
+            DecodeErrorKind::BadMagic => f.write_str("not a WebAssembly module"),
+            DecodeErrorKind::UnsupportedVersion(version) => write!(f, "unsupported version {}, expected {}", version, VERSION),
+            DecodeErrorKind::InvalidInteger => f.write_str("integer is too large"),
+            DecodeErrorKind::InvalidTag { what, tag } => write!(f, "invalid {} {:#04x}", what, tag),
+            DecodeErrorKind::InvalidString => f.write_str("name is not valid UTF-8"),
+            DecodeErrorKind::MisplacedSection(id) => write!(f, "section {} is out of order", id),
+            DecodeErrorKind::SectionSize(id) => write!(f, "section {} does not match its size", id),
+            DecodeErrorKind::FunctionCountMismatch { functions, bodies } => write!(f, "{} functions are declared, but {} have a body", functions, bodies),
+            DecodeErrorKind::OutOfRange { what, index } => write!(f, "{} {} does not exist", what, index),
+            DecodeErrorKind::InvalidGlobal => f.write_str("globals have to be mutable and initialized with a constant of their type"),
+            DecodeErrorKind::InvalidStart => f.write_str("the start function cannot have parameters or results"),
+            DecodeErrorKind::InvalidCode { function, instruction, problem } => {
+                write!(f, "instruction {} of function {}: ", instruction, function)?;
+                match problem {
+                    CodeProblem::LocalOutOfRange(index) => write!(f, "local {} does not exist", index),
+                    CodeProblem::GlobalOutOfRange(index) => write!(f, "global {} does not exist", index),
+                    CodeProblem::FunctionOutOfRange(index) => write!(f, "function {} does not exist", index),
+                    CodeProblem::BranchOutOfRange(depth) => write!(f, "branch depth {} exceeds the open blocks", depth),
+                    CodeProblem::UnexpectedElse => f.write_str("'else' without an 'if'"),
+                    CodeProblem::UnexpectedEnd => f.write_str("instructions after the end of the function"),
+                    CodeProblem::MissingEnd => f.write_str("the function body is not closed with 'end'"),
+                }
+            }
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+/// Translate a program to a WebAssembly module.
+///
+/// Returns the errors of the checks if the program is not correct.
+/// ```
+/// use cb_3::C1Parser;
+/// use cb_3::wasm::{decode, generate};
+///
+/// let program = C1Parser::parse("void main() { x = 2; printf(x * 3); }").unwrap();
+/// let module = decode(&generate(&program).unwrap()).unwrap();
+/// assert_eq!(module.exports[0].name, "main");
+/// let code: Vec<String> = module.bodies[0].code.iter().map(|instruction| instruction.to_string()).collect();
+/// assert_eq!(code, ["i32.const 2", "local.set 0", "local.get 0", "i32.const 3", "i32.mul", "call 0", "end"]);
+/// ```
+pub fn generate(program: &Program) -> Result<Vec<u8>, Vec<Diagnostic>> {
+    let analysis = analyze(program)?;
+    let indices = program.functions.iter().enumerate().map(|(index, function)| (function.name.name.as_str(), (IMPORTS.len() + index) as u32)).collect();
+    // the division function and the initialization of the globals follow the function definitions
+    let division = (IMPORTS.len() + program.functions.len()) as u32;
+    let mut generator = Generator { functions: FunctionTable::new(program), analysis, indices, division, code: Vec::new(), locals: HashMap::new() };
+    let mut module = Module::default();
+    for (name, parameters) in IMPORTS {
+        let ty = module.ty(FunctionType { parameters: parameters.to_vec(), results: Vec::new() });
+        module.imports.push(Import { module: RUNTIME_MODULE.to_string(), name: name.to_string(), ty });
+    }
+
+    for function in &program.functions {
+        let parameters: Vec<ValueType> = function.parameters.iter().filter_map(|parameter| ValueType::of(parameter.ty)).collect();
+        let ty = module.ty(FunctionType { parameters, results: ValueType::of(function.return_type).into_iter().collect() });
+        module.functions.push(ty);
+        generator.block(&function.body, function);
+        if function.return_type != Type::Void {
+            // every path returns, so the end of the function cannot be reached
+            generator.code.push(Instruction::Unreachable);
+        }
+        generator.code.push(Instruction::End);
+        let frame_size = generator.analysis.resolution.frame_size(function);
+        let locals = (function.parameters.len()..frame_size).map(|slot| generator.locals.get(&slot).copied().unwrap_or(ValueType::I32)).collect();
+        generator.locals.clear();
+        module.bodies.push(Body { locals, code: std::mem::take(&mut generator.code) });
+    }
+
+    let ty = module.ty(FunctionType { parameters: vec![ValueType::I32; 4], results: vec![ValueType::I32] });
+    module.functions.push(ty);
+    module.bodies.push(Body { locals: Vec::new(), code: division_code() });
+
+    for declaration in &program.globals {
+        let ty = ValueType::of(declaration.ty).expect("variables have a value type");
+        let init = match ty {
+            ValueType::I32 => Instruction::I32Const(0),
+            ValueType::F64 => Instruction::F64Const(0.0),
+        };
+        module.globals.push(Global { ty, init });
+        if let Some(value) = &declaration.value {
+            generator.converted(value, declaration.ty);
+            generator.set(&declaration.name);
+        }
+    }
+    generator.code.push(Instruction::End);
+    let ty = module.ty(FunctionType { parameters: Vec::new(), results: Vec::new() });
+    module.functions.push(ty);
+    module.bodies.push(Body { locals: Vec::new(), code: std::mem::take(&mut generator.code) });
+    module.start = Some(division + 1);
+    if let Some(main) = generator.indices.get("main") {
+        module.exports.push(Export { name: "main".to_string(), function: *main });
+    }
+    Ok(encode(&module))
+}
+
+/// The code of the function that divides integers like the interpreter, with the parameters
+/// dividend, divisor, line and column
+fn division_code() -> Vec<Instruction> {
+    use Instruction::*;
+    vec![
+        LocalGet(1),
+        I32Eqz,
+        If(None),
+        LocalGet(2),
+        LocalGet(3),
+        Call(DIVISION_BY_ZERO),
+        Unreachable,
+        End,
+        LocalGet(1),
+        I32Const(-1),
+        I32Eq,
+        If(Some(ValueType::I32)),
+        I32Const(0),
+        LocalGet(0),
+        I32Sub,
+        Else,
+        LocalGet(0),
+        LocalGet(1),
+        I32DivS,
+        End,
+        End,
+    ]
+}
+
+struct Generator<'p> {
+    functions: FunctionTable<'p>,
+    analysis: Analysis,
+    /// Function indices of the function definitions
+    indices: HashMap<&'p str, u32>,
+    /// Function index of the function that divides integers
+    division: u32,
+    /// Code of the function that is generated
+    code: Vec<Instruction>,
+    /// Types of the locals of the function, by their slot
+    locals: HashMap<usize, ValueType>,
+}
+
+impl Generator<'_> {
+    fn ty(&self, expr: &Expr) -> Type {
+        self.analysis.types.expr(expr)
+    }
+
+    /// Return whether the identifier refers to a global variable, its index and its type
+    fn variable(&mut self, identifier: &Identifier) -> (bool, u32, Type) {
+        let id = self.analysis.resolution.lookup(identifier).expect("the program is resolved");
+        let binding = self.analysis.resolution.binding(id);
+        let ty = self.analysis.types.variable(id);
+        let global = binding.kind == BindingKind::Global;
+        if !global {
+            self.locals.insert(binding.slot, ValueType::of(ty).expect("variables have a value type"));
+        }
+        (global, binding.slot as u32, ty)
+    }
+
+    fn set(&mut self, identifier: &Identifier) {
+        match self.variable(identifier) {
+            (true, index, _) => self.code.push(Instruction::GlobalSet(index)),
+            (false, index, _) => self.code.push(Instruction::LocalSet(index)),
+        }
+    }
+
+    /// Generate an expression and convert its value to the type
+    fn converted(&mut self, expr: &Expr, ty: Type) {
+        self.expr(expr);
+        if self.ty(expr) == Type::Int && ty == Type::Float {
+            self.code.push(Instruction::F64ConvertI32S);
+        }
+    }
+
+    fn block(&mut self, block: &Block, function: &FunctionDefinition) {
+        for statement in &block.statements {
+            self.statement(statement, function);
+        }
+    }
+
+    /// Generate a loop that runs the body as long as the condition is true, and then the step
+    fn while_loop(&mut self, condition: &Expr, body: &Statement, step: Option<&Assignment>, function: &FunctionDefinition) {
+        self.code.extend([Instruction::Block(None), Instruction::Loop(None)]);
+        self.expr(condition);
+        self.code.extend([Instruction::I32Eqz, Instruction::BrIf(1)]);
+        self.statement(body, function);
+        if let Some(step) = step {
+            self.assignment(step, false);
+        }
+        self.code.extend([Instruction::Br(0), Instruction::End, Instruction::End]);
+    }
+
+    fn statement(&mut self, statement: &Statement, function: &FunctionDefinition) {
+        match &statement.kind {
+            StatementKind::Block(block) => self.block(block, function),
+            StatementKind::Declaration(declaration) => match &declaration.value {
+                Some(value) => {
+                    self.converted(value, declaration.ty);
+                    self.set(&declaration.name);
+                }
+                None => {
+                    self.variable(&declaration.name);
+                }
+            },
+            StatementKind::If { condition, then_branch, else_branch } => {
+                self.expr(condition);
+                self.code.push(Instruction::If(None));
+                self.statement(then_branch, function);
+                if let Some(else_branch) = else_branch {
+                    self.code.push(Instruction::Else);
+                    self.statement(else_branch, function);
+                }
+                self.code.push(Instruction::End);
+            }
+            StatementKind::For { init, condition, step, body } => {
+                self.assignment(init, false);
+                self.while_loop(condition, body, Some(step), function);
+            }
+            StatementKind::While { condition, body } => self.while_loop(condition, body, None, function),
+            StatementKind::DoWhile { body, condition } => {
+                self.code.push(Instruction::Loop(None));
+                self.statement(body, function);
+                self.expr(condition);
+                self.code.extend([Instruction::BrIf(0), Instruction::End]);
+            }
+            StatementKind::Return(Some(value)) => {
+                self.converted(value, function.return_type);
+                self.code.push(Instruction::Return);
+            }
+            StatementKind::Return(None) => self.code.push(Instruction::Return),
+            StatementKind::Printf(value) => {
+                self.expr(value);
+                let function = match self.ty(value) {
+                    Type::Float => PRINT_FLOAT,
+                    Type::Boolean => PRINT_BOOL,
+                    _ => PRINT_INT,
+                };
+                self.code.push(Instruction::Call(function));
+            }
+            StatementKind::Assignment(assignment) => self.assignment(assignment, false),
+            StatementKind::Call(call) => {
+                if self.call(call) != Type::Void {
+                    self.code.push(Instruction::Drop);
+                }
+            }
+        }
+    }
+
+    /// Store the value of the assignment, and keep it on the stack if it is used
+    fn assignment(&mut self, assignment: &Assignment, used: bool) {
+        let (global, index, ty) = self.variable(&assignment.target);
+        self.converted(&assignment.value, ty);
+        match (global, used) {
+            (false, false) => self.code.push(Instruction::LocalSet(index)),
+            (false, true) => self.code.push(Instruction::LocalTee(index)),
+            (true, false) => self.code.push(Instruction::GlobalSet(index)),
+            (true, true) => self.code.extend([Instruction::GlobalSet(index), Instruction::GlobalGet(index)]),
+        }
+    }
+
+    /// Call a function and return its return type
+    fn call(&mut self, call: &FunctionCall) -> Type {
+        let function = self.functions.get(&call.name.name).expect("the functions are checked");
+        for (argument, parameter) in call.arguments.iter().zip(&function.parameters) {
+            self.converted(argument, parameter.ty);
+        }
+        self.code.push(Instruction::Call(self.indices[function.name.name.as_str()]));
+        function.return_type
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Int(value) => self.code.push(Instruction::I32Const(*value)),
+            ExprKind::Float(value) => self.code.push(Instruction::F64Const(*value)),
+            ExprKind::Bool(value) => self.code.push(Instruction::I32Const(*value as i32)),
+            ExprKind::Variable(identifier) => match self.variable(identifier) {
+                (true, index, _) => self.code.push(Instruction::GlobalGet(index)),
+                (false, index, _) => self.code.push(Instruction::LocalGet(index)),
+            },
+            ExprKind::Call(call) => {
+                self.call(call);
+            }
+            ExprKind::Assign(assignment) => self.assignment(assignment, true),
+            ExprKind::Unary { op: UnaryOp::Neg, operand } => match self.ty(operand) {
+                Type::Float => {
+                    self.expr(operand);
+                    self.code.push(Instruction::F64Neg);
+                }
+                _ => {
+                    self.code.push(Instruction::I32Const(0));
+                    self.expr(operand);
+                    self.code.push(Instruction::I32Sub);
+                }
+            },
+            ExprKind::Binary { op: BinaryOp::And, lhs, rhs } => {
+                self.expr(lhs);
+                self.code.push(Instruction::If(Some(ValueType::I32)));
+                self.expr(rhs);
+                self.code.extend([Instruction::Else, Instruction::I32Const(0), Instruction::End]);
+            }
+            ExprKind::Binary { op: BinaryOp::Or, lhs, rhs } => {
+                self.expr(lhs);
+                self.code.extend([Instruction::If(Some(ValueType::I32)), Instruction::I32Const(1), Instruction::Else]);
+                self.expr(rhs);
+                self.code.push(Instruction::End);
+            }
+            ExprKind::Binary { op, lhs, rhs } => {
+                let float = self.ty(lhs) == Type::Float || self.ty(rhs) == Type::Float;
+                let operand_type = if float { Type::Float } else { self.ty(lhs) };
+                self.converted(lhs, operand_type);
+                self.converted(rhs, operand_type);
+                let instruction = match (op, float) {
+                    (BinaryOp::Div, false) => {
+                        let (line, column) = (expr.span.line as i32, expr.span.column as i32);
+                        self.code.extend([Instruction::I32Const(line), Instruction::I32Const(column), Instruction::Call(self.division)]);
+                        return;
+                    }
+                    (BinaryOp::Add, false) => Instruction::I32Add,
+                    (BinaryOp::Sub, false) => Instruction::I32Sub,
+                    (BinaryOp::Mul, false) => Instruction::I32Mul,
+                    (BinaryOp::Equal, false) => Instruction::I32Eq,
+                    (BinaryOp::NotEqual, false) => Instruction::I32Ne,
+                    (BinaryOp::Less, false) => Instruction::I32LtS,
+                    (BinaryOp::LessEqual, false) => Instruction::I32LeS,
+                    (BinaryOp::Greater, false) => Instruction::I32GtS,
+                    (BinaryOp::GreaterEqual, false) => Instruction::I32GeS,
+                    (BinaryOp::Add, true) => Instruction::F64Add,
+                    (BinaryOp::Sub, true) => Instruction::F64Sub,
+                    (BinaryOp::Mul, true) => Instruction::F64Mul,
+                    (BinaryOp::Div, true) => Instruction::F64Div,
+                    (BinaryOp::Equal, true) => Instruction::F64Eq,
+                    (BinaryOp::NotEqual, true) => Instruction::F64Ne,
+                    (BinaryOp::Less, true) => Instruction::F64Lt,
+                    (BinaryOp::LessEqual, true) => Instruction::F64Le,
+                    (BinaryOp::Greater, true) => Instruction::F64Gt,
+                    (BinaryOp::GreaterEqual, true) => Instruction::F64Ge,
+                    (BinaryOp::And | BinaryOp::Or, _) => unreachable!("handled above"),
+                };
+                self.code.push(instruction);
+            }
+        }
+    }
+}
+
+fn encode(module: &Module) -> Vec<u8> {
+    let mut encoder = Encoder { bytes: Vec::new() };
+    encoder.bytes.extend_from_slice(MAGIC);
+    encoder.bytes.extend_from_slice(&VERSION.to_le_bytes());
+    encoder.section(SECTION_TYPE, |encoder| {
+        encoder.vector(&module.types, |encoder, ty| {
+            encoder.u8(0x60);
+            encoder.vector(&ty.parameters, |encoder, ty| encoder.u8(ty.tag()));
+            encoder.vector(&ty.results, |encoder, ty| encoder.u8(ty.tag()));
+        })
+    });
+    encoder.section(SECTION_IMPORT, |encoder| {
+        encoder.vector(&module.imports, |encoder, import| {
+            encoder.name(&import.module);
+            encoder.name(&import.name);
+            encoder.u8(0x00);
+            encoder.u32(import.ty);
+        })
+    });
+    encoder.section(SECTION_FUNCTION, |encoder| encoder.vector(&module.functions, |encoder, ty| encoder.u32(*ty)));
+    if !module.globals.is_empty() {
+        encoder.section(SECTION_GLOBAL, |encoder| {
+            encoder.vector(&module.globals, |encoder, global| {
+                encoder.u8(global.ty.tag());
+                encoder.u8(0x01);
+                encoder.instruction(global.init);
+                encoder.instruction(Instruction::End);
+            })
+        });
+    }
+    if !module.exports.is_empty() {
+        encoder.section(SECTION_EXPORT, |encoder| {
+            encoder.vector(&module.exports, |encoder, export| {
+                encoder.name(&export.name);
+                encoder.u8(0x00);
+                encoder.u32(export.function);
+            })
+        });
+    }
+    if let Some(start) = module.start {
+        encoder.section(SECTION_START, |encoder| encoder.u32(start));
+    }
+    encoder.section(SECTION_CODE, |encoder| {
+        encoder.vector(&module.bodies, |encoder, body| {
+            encoder.sized(|encoder| {
+                // consecutive locals of the same type are declared together
+                let mut runs: Vec<(u32, ValueType)> = Vec::new();
+                for ty in &body.locals {
+                    match runs.last_mut() {
+                        Some((count, last)) if last == ty => *count += 1,
+                        _ => runs.push((1, *ty)),
+                    }
+                }
+                encoder.vector(&runs, |encoder, (count, ty)| {
+                    encoder.u32(*count);
+                    encoder.u8(ty.tag());
+                });
+                for instruction in &body.code {
+                    encoder.instruction(*instruction);
+                }
+            })
+        })
+    });
+    encoder.bytes
+}
+
+struct Encoder {
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    /// Write an unsigned LEB128 integer
+    fn u32(&mut self, mut value: u32) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                return self.u8(byte);
+            }
+            self.u8(byte | 0x80);
+        }
+    }
+
+    /// Write a signed LEB128 integer
+    fn i32(&mut self, mut value: i32) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+                return self.u8(byte);
+            }
+            self.u8(byte | 0x80);
+        }
+    }
+
+    fn length(&mut self, length: usize) {
+        self.u32(length.try_into().unwrap_or_else(|_| panic!("value does not fit into the format")));
+    }
+
+    fn name(&mut self, name: &str) {
+        self.length(name.len());
+        self.bytes.extend_from_slice(name.as_bytes());
+    }
+
+    fn vector<T>(&mut self, items: &[T], mut item: impl FnMut(&mut Self, &T)) {
+        self.length(items.len());
+        for element in items {
+            item(self, element);
+        }
+    }
+
+    /// Write the data the function writes, preceded by its size
+    fn sized(&mut self, content: impl FnOnce(&mut Self)) {
+        let mut inner = Encoder { bytes: Vec::new() };
+        content(&mut inner);
+        self.length(inner.bytes.len());
+        self.bytes.extend(inner.bytes);
+    }
+
+    fn section(&mut self, id: u8, content: impl FnOnce(&mut Self)) {
+        self.u8(id);
+        self.sized(content);
+    }
+
+    fn instruction(&mut self, instruction: Instruction) {
+        self.u8(instruction.opcode());
+        match instruction {
+            Instruction::Block(ty) | Instruction::Loop(ty) | Instruction::If(ty) => self.u8(ty.map_or(0x40, ValueType::tag)),
+            Instruction::Br(index)
+            | Instruction::BrIf(index)
+            | Instruction::Call(index)
+            | Instruction::LocalGet(index)
+            | Instruction::LocalSet(index)
+            | Instruction::LocalTee(index)
+            | Instruction::GlobalGet(index)
+            | Instruction::GlobalSet(index) => self.u32(index),
+            Instruction::I32Const(value) => self.i32(value),
+            Instruction::F64Const(value) => self.bytes.extend_from_slice(&value.to_le_bytes()),
+            _ => {}
+        }
+    }
+}
+
+/// Decode a module and check its structure.
+///
+/// Only the sections, types and instructions the backend writes are supported, custom sections are
+/// skipped. The decoder checks that the sections are complete and in order, that every index
+/// refers to something that exists and that the blocks of every function body are nested, but not
+/// the types of the values on the stack.
+/// ```
+/// use cb_3::wasm::{decode, DecodeErrorKind};
+///
+/// let error = decode(b"\0asm\x02\0\0\0").unwrap_err();
+/// assert_eq!(error.kind, DecodeErrorKind::UnsupportedVersion(2));
+/// assert_eq!(error.to_string(), "byte 4: unsupported version 2, expected 1");
+/// ```
+pub fn decode(bytes: &[u8]) -> Result<Module, DecodeError> {
+    let mut reader = Reader { bytes, position: 0 };
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(DecodeError { kind: DecodeErrorKind::BadMagic, offset: 0 });
+    }
+    let version = u32::from_le_bytes(reader.take(4)?.try_into().unwrap());
+    if version != VERSION {
+        return Err(DecodeError { kind: DecodeErrorKind::UnsupportedVersion(version), offset: MAGIC.len() });
+    }
+    let mut module = Module::default();
+    let mut body_starts = Vec::new();
+    let mut last = 0;
+    while reader.position < bytes.len() {
+        let start = reader.position;
+        let id = reader.u8()?;
+        let size = reader.u32()? as usize;
+        let content_start = reader.position;
+        let end = content_start.checked_add(size).filter(|end| *end <= bytes.len()).ok_or(DecodeError { kind: DecodeErrorKind::Truncated, offset: content_start })?;
+        if id == 0 {
+            reader.position = end;
+            continue;
+        }
+        if id <= last {
+            return Err(DecodeError { kind: DecodeErrorKind::MisplacedSection(id), offset: start });
+        }
+        last = id;
+        let mut section = Reader { bytes: &bytes[..end], position: content_start };
+        section.section(id, &mut module, &mut body_starts).map_err(|error| match error.kind {
+            DecodeErrorKind::Truncated if end < bytes.len() => DecodeError { kind: DecodeErrorKind::SectionSize(id), offset: start },
+            _ => error,
+        })?;
+        if section.position != end {
+            return Err(DecodeError { kind: DecodeErrorKind::SectionSize(id), offset: start });
+        }
+        reader.position = end;
+    }
+    if module.functions.len() != module.bodies.len() {
+        let kind = DecodeErrorKind::FunctionCountMismatch { functions: module.functions.len(), bodies: module.bodies.len() };
+        return Err(DecodeError { kind, offset: bytes.len() });
+    }
+    validate(&module, &body_starts)?;
+    Ok(module)
+}
+
+struct Reader<'b> {
+    bytes: &'b [u8],
+    position: usize,
+}
+
+impl<'b> Reader<'b> {
+    fn take(&mut self, length: usize) -> Result<&'b [u8], DecodeError> {
+        match self.bytes.get(self.position..).and_then(|rest| rest.get(..length)) {
+            Some(bytes) => {
+                self.position += length;
+                Ok(bytes)
+            }
+            None => Err(DecodeError { kind: DecodeErrorKind::Truncated, offset: self.position }),
+        }
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Read a LEB128 integer of at most `bits` bits, sign-extended if it is signed
+    fn leb128(&mut self, bits: u32, signed: bool) -> Result<i64, DecodeError> {
+        let start = self.position;
+        let mut value: i64 = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            value |= i64::from(byte & 0x7f) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if signed && shift < 64 && byte & 0x40 != 0 {
+                    value |= -1 << shift;
+                }
+                break;
+            }
+            if shift >= bits + 7 {
+                return Err(DecodeError { kind: DecodeErrorKind::InvalidInteger, offset: start });
+            }
+        }
+        let fits = if signed { i32::try_from(value).is_ok() } else { u32::try_from(value).is_ok() };
+        if !fits {
+            return Err(DecodeError { kind: DecodeErrorKind::InvalidInteger, offset: start });
+        }
+        Ok(value)
+    }
+
+    fn u32(&mut self) -> Result<u32, DecodeError> {
+        Ok(self.leb128(32, false)? as u32)
+    }
+
+    fn i32(&mut self) -> Result<i32, DecodeError> {
+        Ok(self.leb128(32, true)? as i32)
+    }
+
+    fn name(&mut self) -> Result<String, DecodeError> {
+        let length = self.u32()? as usize;
+        let start = self.position;
+        let bytes = self.take(length)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| DecodeError { kind: DecodeErrorKind::InvalidString, offset: start })
+    }
+
+    fn vector<T>(&mut self, mut item: impl FnMut(&mut Self) -> Result<T, DecodeError>) -> Result<Vec<T>, DecodeError> {
+        let count = self.u32()?;
+        // the count is not trusted to reserve memory, as every item takes at least one byte
+        let mut items = Vec::with_capacity((count as usize).min(self.bytes.len() - self.position));
+        for _ in 0..count {
+            items.push(item(self)?);
+        }
+        Ok(items)
+    }
+
+    /// Read a byte and check that it is the expected one
+    fn expect(&mut self, what: &'static str, expected: u8) -> Result<(), DecodeError> {
+        let offset = self.position;
+        match self.u8()? {
+            tag if tag == expected => Ok(()),
+            tag => Err(DecodeError { kind: DecodeErrorKind::InvalidTag { what, tag }, offset }),
+        }
+    }
+
+    fn value_type(&mut self) -> Result<ValueType, DecodeError> {
+        let offset = self.position;
+        match self.u8()? {
+            0x7f => Ok(ValueType::I32),
+            0x7c => Ok(ValueType::F64),
+            tag => Err(DecodeError { kind: DecodeErrorKind::InvalidTag { what: "value type", tag }, offset }),
+        }
+    }
+
+    fn block_type(&mut self) -> Result<Option<ValueType>, DecodeError> {
+        if self.bytes.get(self.position) == Some(&0x40) {
+            self.position += 1;
+            return Ok(None);
+        }
+        self.value_type().map(Some)
+    }
+
+    fn section(&mut self, id: u8, module: &mut Module, body_starts: &mut Vec<usize>) -> Result<(), DecodeError> {
+        match id {
+            SECTION_TYPE => {
+                module.types = self.vector(|reader| {
+                    reader.expect("function type", 0x60)?;
+                    Ok(FunctionType { parameters: reader.vector(Self::value_type)?, results: reader.vector(Self::value_type)? })
+                })?
+            }
+            SECTION_IMPORT => {
+                module.imports = self.vector(|reader| {
+                    let (module, name) = (reader.name()?, reader.name()?);
+                    reader.expect("import kind", 0x00)?;
+                    Ok(Import { module, name, ty: reader.u32()? })
+                })?
+            }
+            SECTION_FUNCTION => module.functions = self.vector(Self::u32)?,
+            SECTION_GLOBAL => {
+                module.globals = self.vector(|reader| {
+                    let start = reader.position;
+                    let ty = reader.value_type()?;
+                    let mutable = reader.u8()? == 0x01;
+                    let init = reader.instruction()?;
+                    let constant = matches!((ty, init), (ValueType::I32, Instruction::I32Const(_)) | (ValueType::F64, Instruction::F64Const(_)));
+                    if !mutable || !constant || reader.instruction()? != Instruction::End {
+                        return Err(DecodeError { kind: DecodeErrorKind::InvalidGlobal, offset: start });
+                    }
+                    Ok(Global { ty, init })
+                })?
+            }
+            SECTION_EXPORT => {
+                module.exports = self.vector(|reader| {
+                    let name = reader.name()?;
+                    reader.expect("export kind", 0x00)?;
+                    Ok(Export { name, function: reader.u32()? })
+                })?
+            }
+            SECTION_START => module.start = Some(self.u32()?),
+            SECTION_CODE => {
+                module.bodies = self.vector(|reader| {
+                    let size = reader.u32()? as usize;
+                    body_starts.push(reader.position);
+                    let end = reader.position.checked_add(size).filter(|end| *end <= reader.bytes.len());
+                    let end = end.ok_or(DecodeError { kind: DecodeErrorKind::Truncated, offset: reader.position })?;
+                    let mut body = Reader { bytes: &reader.bytes[..end], position: reader.position };
+                    let mut locals = Vec::new();
+                    for (count, ty) in body.vector(|reader| Ok((reader.u32()?, reader.value_type()?)))? {
+                        // every declared local takes memory, but the count is not trusted
+                        if count as usize > body.bytes.len() * 8 {
+                            return Err(DecodeError { kind: DecodeErrorKind::InvalidInteger, offset: body.position });
+                        }
+                        locals.extend(std::iter::repeat_n(ty, count as usize));
+                    }
+                    let mut code = Vec::new();
+                    while body.position < end {
+                        code.push(body.instruction()?);
+                    }
+                    reader.position = end;
+                    Ok(Body { locals, code })
+                })?
+            }
+            tag => return Err(DecodeError { kind: DecodeErrorKind::InvalidTag { what: "section", tag }, offset: self.position }),
+        }
+        Ok(())
+    }
+
+    fn instruction(&mut self) -> Result<Instruction, DecodeError> {
+        let offset = self.position;
+        let opcode = self.u8()?;
+        let instruction = match opcode {
+            0x02 => Instruction::Block(self.block_type()?),
+            0x03 => Instruction::Loop(self.block_type()?),
+            0x04 => Instruction::If(self.block_type()?),
+            0x0c => Instruction::Br(self.u32()?),
+            0x0d => Instruction::BrIf(self.u32()?),
+            0x10 => Instruction::Call(self.u32()?),
+            0x20 => Instruction::LocalGet(self.u32()?),
+            0x21 => Instruction::LocalSet(self.u32()?),
+            0x22 => Instruction::LocalTee(self.u32()?),
+            0x23 => Instruction::GlobalGet(self.u32()?),
+            0x24 => Instruction::GlobalSet(self.u32()?),
+            0x41 => Instruction::I32Const(self.i32()?),
+            0x44 => Instruction::F64Const(f64::from_le_bytes(self.take(8)?.try_into().unwrap())),
+            _ => match SIMPLE.iter().find(|instruction| instruction.opcode() == opcode) {
+                Some(instruction) => *instruction,
+                None => return Err(DecodeError { kind: DecodeErrorKind::InvalidTag { what: "opcode", tag: opcode }, offset }),
+            },
+        };
+        Ok(instruction)
+    }
+}
+
+/// Check that the indices outside of the code refer to what exists, and then every function body
+fn validate(module: &Module, body_starts: &[usize]) -> Result<(), DecodeError> {
+    let functions = (module.imports.len() + module.functions.len()) as u32;
+    let out_of_range = |what, index| DecodeError { kind: DecodeErrorKind::OutOfRange { what, index }, offset: 0 };
+    for ty in module.imports.iter().map(|import| import.ty).chain(module.functions.iter().copied()) {
+        if ty as usize >= module.types.len() {
+            return Err(out_of_range("type", ty));
+        }
+    }
+    for export in &module.exports {
+        if export.function >= functions {
+            return Err(out_of_range("function", export.function));
+        }
+    }
+    if let Some(start) = module.start {
+        match module.function_type(start) {
+            None => return Err(out_of_range("function", start)),
+            Some(ty) if !ty.parameters.is_empty() || !ty.results.is_empty() => return Err(DecodeError { kind: DecodeErrorKind::InvalidStart, offset: 0 }),
+            Some(_) => {}
+        }
+    }
+    for (index, (body, start)) in module.bodies.iter().zip(body_starts).enumerate() {
+        let function = (module.imports.len() + index) as u32;
+        let parameters = module.types[module.functions[index] as usize].parameters.len();
+        validate_body(module, parameters + body.locals.len(), body)
+            .map_err(|(instruction, problem)| DecodeError { kind: DecodeErrorKind::InvalidCode { function, instruction, problem }, offset: *start })?;
+    }
+    Ok(())
+}
+
+/// Check the indices and the nesting of the blocks of a function body
+fn validate_body(module: &Module, locals: usize, body: &Body) -> Result<(), (usize, CodeProblem)> {
+    let functions = module.imports.len() + module.functions.len();
+    // the open blocks with whether they are an `if` that can still get an `else`, starting with the
+    // block of the function
+    let mut blocks = vec![false];
+    for (position, instruction) in body.code.iter().enumerate() {
+        let problem = match *instruction {
+            _ if blocks.is_empty() => Some(CodeProblem::UnexpectedEnd),
+            Instruction::Block(_) | Instruction::Loop(_) => {
+                blocks.push(false);
+                None
+            }
+            Instruction::If(_) => {
+                blocks.push(true);
+                None
+            }
+            Instruction::Else => match blocks.last_mut() {
+                Some(can_else @ true) => {
+                    *can_else = false;
+                    None
+                }
+                _ => Some(CodeProblem::UnexpectedElse),
+            },
+            Instruction::End => {
+                blocks.pop();
+                None
+            }
+            Instruction::Br(depth) | Instruction::BrIf(depth) => (depth as usize >= blocks.len()).then_some(CodeProblem::BranchOutOfRange(depth)),
+            Instruction::Call(index) => (index as usize >= functions).then_some(CodeProblem::FunctionOutOfRange(index)),
+            Instruction::LocalGet(index) | Instruction::LocalSet(index) | Instruction::LocalTee(index) => {
+                (index as usize >= locals).then_some(CodeProblem::LocalOutOfRange(index))
+            }
+            Instruction::GlobalGet(index) | Instruction::GlobalSet(index) => {
+                (index as usize >= module.globals.len()).then_some(CodeProblem::GlobalOutOfRange(index))
+            }
+            _ => None,
+        };
+        if let Some(problem) = problem {
+            return Err((position, problem));
+        }
+    }
+    if !blocks.is_empty() {
+        return Err((body.code.len(), CodeProblem::MissingEnd));
+    }
+    Ok(())
+}
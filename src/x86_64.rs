@@ -0,0 +1,577 @@
+//! Backend that translates C(-1) programs to x86-64 assembly for the GNU assembler.
+//!
+//! The generated assembly uses AT&T syntax and the System V calling convention, so `cc` can
+//! assemble it and link it with the C library on Linux. Every function definition becomes a
+//! function `c1.<name>`, and the program gets a C `main` function that initializes the global
+//! variables and calls the `main` function of the program. The code is generated for a simple
+//! stack machine:
+//! - `int` and `bool` values are computed in `%eax`, `float` values in `%xmm0`. Left operands and
+//!   arguments are kept on the stack while the next operand is computed
+//! - local variables and parameters have a stack slot of 8 bytes below `%rbp`, global variables
+//!   a quad word in the data section
+//! - `printf` calls the runtime functions `c1_print_int`, `c1_print_float` and `c1_print_bool`,
+//!   which are part of the generated assembly and print like the [interpreter](crate::interpreter)
+//! - an integer division by zero prints the runtime error of the interpreter to stderr and exits
+//!   with status 1. Nested calls are only limited by the stack of the process
+//!
+//! The program has to pass every [check](crate::analysis::analyze).
+
+use crate::analysis::{analyze, Analysis, Diagnostic};
+use crate::ast::{Assignment, BinaryOp, Block, Expr, ExprKind, FunctionCall, FunctionDefinition, Identifier, Program, Statement, StatementKind, Type, UnaryOp};
+use crate::resolve::BindingKind;
+use crate::symbols::FunctionTable;
+use std::fmt::Write;
+
+/// Registers for the first `int` and `bool` arguments
+const INT_REGISTERS: [&str; 6] = ["%edi", "%esi", "%edx", "%ecx", "%r8d", "%r9d"];
+
+/// Number of `%xmm` registers for the first `float` arguments
+const FLOAT_REGISTERS: usize = 8;
+
+/// The functions `printf` and integer divisions call
+const RUNTIME: &str = r#"
+c1_print_int:
+	pushq %rbp
+	movq %rsp, %rbp
+	movl %edi, %esi
+	leaq .Lformat_int(%rip), %rdi
+	xorl %eax, %eax
+	call printf@PLT
+	popq %rbp
+	ret
+
+c1_print_float:
+	pushq %rbp
+	movq %rsp, %rbp
+	ucomisd %xmm0, %xmm0
+	jp 1f
+	leaq .Lformat_float(%rip), %rdi
+	movl $1, %eax
+	call printf@PLT
+	popq %rbp
+	ret
+1:
+	leaq .Lnan(%rip), %rdi
+	call puts@PLT
+	popq %rbp
+	ret
+
+c1_print_bool:
+	pushq %rbp
+	movq %rsp, %rbp
+	testl %edi, %edi
+	leaq .Ltrue(%rip), %rdi
+	leaq .Lfalse(%rip), %rax
+	cmove %rax, %rdi
+	call puts@PLT
+	popq %rbp
+	ret
+
+c1_division_by_zero:
+	pushq %rbp
+	movq %rsp, %rbp
+	movl %esi, %ecx
+	movl %edi, %edx
+	leaq .Lformat_division(%rip), %rsi
+	movl $2, %edi
+	xorl %eax, %eax
+	call dprintf@PLT
+	movl $1, %edi
+	call exit@PLT
+
+	.section .rodata
+.Lformat_int:
+	.string "%d\n"
+.Lformat_float:
+	.string "%f\n"
+.Lnan:
+	.string "nan"
+.Ltrue:
+	.string "true"
+.Lfalse:
+	.string "false"
+.Lformat_division:
+	.string "line %d, column %d: division by zero\n"
+"#;
+
+/// Write one instruction to the generated text
+macro_rules! emit {
+    ($generator:expr, $($arg:tt)*) => {
+        writeln!($generator.text, "\t{}", format_args!($($arg)*)).unwrap()
+    };
+}
+
+/// Translate a program to assembly.
+///
+/// Returns the errors of the checks if the program is not correct.
+/// ```
+/// use cb_3::C1Parser;
+/// use cb_3::x86_64::generate;
+///
+/// let program = C1Parser::parse("void main() { printf(1 + 2); }").unwrap();
+/// let assembly = generate(&program).unwrap();
+/// assert!(assembly.contains("c1.main:\n"));
+/// assert!(assembly.contains("\tcall c1_print_int\n"));
+/// ```
+pub fn generate(program: &Program) -> Result<String, Vec<Diagnostic>> {
+    let analysis = analyze(program)?;
+    let mut generator = Generator { functions: FunctionTable::new(program), analysis, text: String::new(), constants: Vec::new(), labels: 0, depth: 0 };
+    generator.text.push_str("\t.text\n");
+    for function in &program.functions {
+        generator.function(function);
+    }
+
+    generator.text.push_str("\n.Linit:\n");
+    emit!(generator, "pushq %rbp");
+    emit!(generator, "movq %rsp, %rbp");
+    for declaration in &program.globals {
+        if let Some(value) = &declaration.value {
+            generator.expr(value);
+            generator.convert(generator.ty(value), declaration.ty);
+            generator.store(&declaration.name);
+        }
+    }
+    emit!(generator, "popq %rbp");
+    emit!(generator, "ret");
+
+    // the exit status is the value main returns
+    generator.text.push_str("\n\t.globl main\nmain:\n");
+    emit!(generator, "pushq %rbp");
+    emit!(generator, "movq %rsp, %rbp");
+    emit!(generator, "call .Linit");
+    emit!(generator, "call c1.main");
+    if generator.functions.get("main").map(|main| main.return_type) != Some(Type::Int) {
+        emit!(generator, "xorl %eax, %eax");
+    }
+    emit!(generator, "popq %rbp");
+    emit!(generator, "ret");
+    generator.text.push_str(RUNTIME);
+
+    for (index, bits) in generator.constants.iter().enumerate() {
+        writeln!(generator.text, "\t.p2align 3\n.Lconstant{}:\n\t.quad {:#018x}", index, bits).unwrap();
+    }
+    generator.text.push_str("\n\t.data\n\t.p2align 3\n");
+    for slot in 0..generator.analysis.resolution.globals() {
+        writeln!(generator.text, "c1.global.{}:\n\t.quad 0", slot).unwrap();
+    }
+    generator.text.push_str("\n\t.section .note.GNU-stack,\"\",@progbits\n");
+    Ok(generator.text)
+}
+
+struct Generator<'p> {
+    functions: FunctionTable<'p>,
+    analysis: Analysis,
+    text: String,
+    /// Bit patterns of the `float` literals, by their label
+    constants: Vec<u64>,
+    /// Number of labels created so far
+    labels: usize,
+    /// Number of quad words pushed since the prologue of the current function
+    depth: usize,
+}
+
+impl Generator<'_> {
+    fn ty(&self, expr: &Expr) -> Type {
+        self.analysis.types.expr(expr)
+    }
+
+    fn label(&mut self) -> String {
+        self.labels += 1;
+        format!(".L{}", self.labels)
+    }
+
+    fn place(&mut self, label: &str) {
+        writeln!(self.text, "{}:", label).unwrap();
+    }
+
+    /// Return the operand and the type of the variable the identifier refers to
+    fn variable(&self, identifier: &Identifier) -> (String, Type) {
+        let id = self.analysis.resolution.lookup(identifier).expect("the program is resolved");
+        let binding = self.analysis.resolution.binding(id);
+        let operand = match binding.kind {
+            BindingKind::Global => format!("c1.global.{}(%rip)", binding.slot),
+            _ => format!("-{}(%rbp)", 8 * (binding.slot + 1)),
+        };
+        (operand, self.analysis.types.variable(id))
+    }
+
+    /// Store the computed value in the variable, which has to have the type of the value
+    fn store(&mut self, identifier: &Identifier) {
+        let (operand, ty) = self.variable(identifier);
+        match ty {
+            Type::Float => emit!(self, "movsd %xmm0, {}", operand),
+            _ => emit!(self, "movl %eax, {}", operand),
+        }
+    }
+
+    /// Convert the computed value from one type to the other
+    fn convert(&mut self, from: Type, to: Type) {
+        if from == Type::Int && to == Type::Float {
+            emit!(self, "cvtsi2sdl %eax, %xmm0");
+        }
+    }
+
+    /// Push the computed value
+    fn push(&mut self, ty: Type) {
+        match ty {
+            Type::Float => {
+                emit!(self, "subq $8, %rsp");
+                emit!(self, "movsd %xmm0, (%rsp)");
+            }
+            _ => emit!(self, "pushq %rax"),
+        }
+        self.depth += 1;
+    }
+
+    /// Call a runtime function with the stack aligned to 16 bytes
+    fn call_runtime(&mut self, function: &str) {
+        if self.depth % 2 == 1 {
+            emit!(self, "subq $8, %rsp");
+            emit!(self, "call {}", function);
+            emit!(self, "addq $8, %rsp");
+        } else {
+            emit!(self, "call {}", function);
+        }
+    }
+
+    fn function(&mut self, function: &FunctionDefinition) {
+        let frame_size = self.analysis.resolution.frame_size(function);
+        let return_label = self.label();
+        writeln!(self.text, "\nc1.{}:", function.name.name).unwrap();
+        emit!(self, "pushq %rbp");
+        emit!(self, "movq %rsp, %rbp");
+        if frame_size > 0 {
+            emit!(self, "subq ${}, %rsp", (8 * frame_size).next_multiple_of(16));
+        }
+        self.depth = 0;
+
+        // arguments in registers are stored first, because %xmm0 is needed to copy the others
+        let (mut ints, mut floats, mut stack) = (0, 0, 0);
+        let mut stack_parameters = Vec::new();
+        for (slot, parameter) in function.parameters.iter().enumerate() {
+            let operand = format!("-{}(%rbp)", 8 * (slot + 1));
+            match parameter.ty {
+                Type::Float if floats < FLOAT_REGISTERS => {
+                    emit!(self, "movsd %xmm{}, {}", floats, operand);
+                    floats += 1;
+                }
+                Type::Float => {
+                    stack_parameters.push((stack, Type::Float, operand));
+                    stack += 1;
+                }
+                _ if ints < INT_REGISTERS.len() => {
+                    emit!(self, "movl {}, {}", INT_REGISTERS[ints], operand);
+                    ints += 1;
+                }
+                ty => {
+                    stack_parameters.push((stack, ty, operand));
+                    stack += 1;
+                }
+            }
+        }
+        for (index, ty, operand) in stack_parameters {
+            let argument = format!("{}(%rbp)", 16 + 8 * index);
+            match ty {
+                Type::Float => {
+                    emit!(self, "movsd {}, %xmm0", argument);
+                    emit!(self, "movsd %xmm0, {}", operand);
+                }
+                _ => {
+                    emit!(self, "movl {}, %eax", argument);
+                    emit!(self, "movl %eax, {}", operand);
+                }
+            }
+        }
+
+        self.block(&function.body, function, &return_label);
+        self.place(&return_label);
+        emit!(self, "leave");
+        emit!(self, "ret");
+    }
+
+    fn block(&mut self, block: &Block, function: &FunctionDefinition, return_label: &str) {
+        for statement in &block.statements {
+            self.statement(statement, function, return_label);
+        }
+    }
+
+    /// Compute a condition and jump to the label if it is false
+    fn jump_if_false(&mut self, condition: &Expr, label: &str) {
+        self.expr(condition);
+        emit!(self, "testl %eax, %eax");
+        emit!(self, "je {}", label);
+    }
+
+    fn statement(&mut self, statement: &Statement, function: &FunctionDefinition, return_label: &str) {
+        match &statement.kind {
+            StatementKind::Block(block) => self.block(block, function, return_label),
+            StatementKind::Declaration(declaration) => {
+                if let Some(value) = &declaration.value {
+                    self.expr(value);
+                    self.convert(self.ty(value), declaration.ty);
+                    self.store(&declaration.name);
+                }
+            }
+            StatementKind::If { condition, then_branch, else_branch } => {
+                let else_label = self.label();
+                self.jump_if_false(condition, &else_label);
+                self.statement(then_branch, function, return_label);
+                match else_branch {
+                    Some(else_branch) => {
+                        let end = self.label();
+                        emit!(self, "jmp {}", end);
+                        self.place(&else_label);
+                        self.statement(else_branch, function, return_label);
+                        self.place(&end);
+                    }
+                    None => self.place(&else_label),
+                }
+            }
+            StatementKind::For { init, condition, step, body } => {
+                let (start, end) = (self.label(), self.label());
+                self.assignment(init);
+                self.place(&start);
+                self.jump_if_false(condition, &end);
+                self.statement(body, function, return_label);
+                self.assignment(step);
+                emit!(self, "jmp {}", start);
+                self.place(&end);
+            }
+            StatementKind::While { condition, body } => {
+                let (start, end) = (self.label(), self.label());
+                self.place(&start);
+                self.jump_if_false(condition, &end);
+                self.statement(body, function, return_label);
+                emit!(self, "jmp {}", start);
+                self.place(&end);
+            }
+            StatementKind::DoWhile { body, condition } => {
+                let start = self.label();
+                self.place(&start);
+                self.statement(body, function, return_label);
+                self.expr(condition);
+                emit!(self, "testl %eax, %eax");
+                emit!(self, "jne {}", start);
+            }
+            StatementKind::Return(value) => {
+                if let Some(value) = value {
+                    self.expr(value);
+                    self.convert(self.ty(value), function.return_type);
+                }
+                emit!(self, "jmp {}", return_label);
+            }
+            StatementKind::Printf(value) => {
+                self.expr(value);
+                match self.ty(value) {
+                    Type::Float => self.call_runtime("c1_print_float"),
+                    Type::Boolean => {
+                        emit!(self, "movl %eax, %edi");
+                        self.call_runtime("c1_print_bool");
+                    }
+                    _ => {
+                        emit!(self, "movl %eax, %edi");
+                        self.call_runtime("c1_print_int");
+                    }
+                }
+            }
+            StatementKind::Assignment(assignment) => self.assignment(assignment),
+            StatementKind::Call(call) => self.call(call),
+        }
+    }
+
+    fn assignment(&mut self, assignment: &Assignment) {
+        self.expr(&assignment.value);
+        let (_, ty) = self.variable(&assignment.target);
+        self.convert(self.ty(&assignment.value), ty);
+        self.store(&assignment.target);
+    }
+
+    /// Call a function of the program. Arguments are computed from left to right and kept on the
+    /// stack until all of them are known.
+    fn call(&mut self, call: &FunctionCall) {
+        let function = self.functions.get(&call.name.name).expect("the functions are checked");
+        let first = self.depth;
+        for (argument, parameter) in call.arguments.iter().zip(&function.parameters) {
+            self.expr(argument);
+            self.convert(self.ty(argument), parameter.ty);
+            self.push(parameter.ty);
+        }
+
+        let (mut ints, mut floats) = (0, 0);
+        let mut registers = Vec::new();
+        let mut stack = Vec::new();
+        for (index, parameter) in function.parameters.iter().enumerate() {
+            match parameter.ty {
+                Type::Float if floats < FLOAT_REGISTERS => {
+                    registers.push((index, format!("%xmm{}", floats)));
+                    floats += 1;
+                }
+                Type::Float => stack.push(index),
+                _ if ints < INT_REGISTERS.len() => {
+                    registers.push((index, INT_REGISTERS[ints].to_string()));
+                    ints += 1;
+                }
+                _ => stack.push(index),
+            }
+        }
+        let padding = (self.depth + stack.len()) % 2;
+        if padding == 1 {
+            emit!(self, "subq $8, %rsp");
+            self.depth += 1;
+        }
+        // the argument with the given index is pushed `depth - 1 - (first + index)` quad words ago
+        for &index in stack.iter().rev() {
+            emit!(self, "pushq {}(%rsp)", 8 * (self.depth - 1 - (first + index)));
+            self.depth += 1;
+        }
+        for (index, register) in registers {
+            let offset = 8 * (self.depth - 1 - (first + index));
+            if register.starts_with("%xmm") {
+                emit!(self, "movsd {}(%rsp), {}", offset, register);
+            } else {
+                emit!(self, "movl {}(%rsp), {}", offset, register);
+            }
+        }
+        emit!(self, "call c1.{}", function.name.name);
+        if self.depth > first {
+            emit!(self, "addq ${}, %rsp", 8 * (self.depth - first));
+            self.depth = first;
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Int(value) => emit!(self, "movl ${}, %eax", value),
+            ExprKind::Float(value) => {
+                self.constants.push(value.to_bits());
+                emit!(self, "movsd .Lconstant{}(%rip), %xmm0", self.constants.len() - 1);
+            }
+            ExprKind::Bool(value) => emit!(self, "movl ${}, %eax", *value as i32),
+            ExprKind::Variable(identifier) => match self.variable(identifier) {
+                (operand, Type::Float) => emit!(self, "movsd {}, %xmm0", operand),
+                (operand, _) => emit!(self, "movl {}, %eax", operand),
+            },
+            ExprKind::Call(call) => self.call(call),
+            ExprKind::Assign(assignment) => self.assignment(assignment),
+            ExprKind::Unary { op: UnaryOp::Neg, operand } => {
+                self.expr(operand);
+                if self.ty(operand) == Type::Float {
+                    emit!(self, "movq %xmm0, %rax");
+                    emit!(self, "btcq $63, %rax");
+                    emit!(self, "movq %rax, %xmm0");
+                } else {
+                    emit!(self, "negl %eax");
+                }
+            }
+            ExprKind::Binary { op: op @ (BinaryOp::And | BinaryOp::Or), lhs, rhs } => {
+                // %eax already holds the result if the left operand decides it
+                let end = self.label();
+                self.expr(lhs);
+                emit!(self, "testl %eax, %eax");
+                emit!(self, "{} {}", if *op == BinaryOp::And { "je" } else { "jne" }, end);
+                self.expr(rhs);
+                self.place(&end);
+            }
+            ExprKind::Binary { op, lhs, rhs } => {
+                let (lhs_type, rhs_type) = (self.ty(lhs), self.ty(rhs));
+                self.expr(lhs);
+                self.push(lhs_type);
+                self.expr(rhs);
+                self.depth -= 1;
+                if lhs_type == Type::Float || rhs_type == Type::Float {
+                    // the left operand goes to %xmm0 and the right one to %xmm1
+                    if rhs_type == Type::Int {
+                        emit!(self, "cvtsi2sdl %eax, %xmm1");
+                    } else {
+                        emit!(self, "movapd %xmm0, %xmm1");
+                    }
+                    if lhs_type == Type::Int {
+                        emit!(self, "popq %rax");
+                        emit!(self, "cvtsi2sdl %eax, %xmm0");
+                    } else {
+                        emit!(self, "movsd (%rsp), %xmm0");
+                        emit!(self, "addq $8, %rsp");
+                    }
+                    self.float_operator(*op);
+                } else {
+                    // the left operand goes to %eax and the right one to %ecx
+                    emit!(self, "movl %eax, %ecx");
+                    emit!(self, "popq %rax");
+                    self.int_operator(*op, expr);
+                }
+            }
+        }
+    }
+
+    fn int_operator(&mut self, op: BinaryOp, expr: &Expr) {
+        let condition = match op {
+            BinaryOp::Add => return emit!(self, "addl %ecx, %eax"),
+            BinaryOp::Sub => return emit!(self, "subl %ecx, %eax"),
+            BinaryOp::Mul => return emit!(self, "imull %ecx, %eax"),
+            BinaryOp::Div => return self.division(expr),
+            BinaryOp::Equal => "e",
+            BinaryOp::NotEqual => "ne",
+            BinaryOp::Less => "l",
+            BinaryOp::LessEqual => "le",
+            BinaryOp::Greater => "g",
+            BinaryOp::GreaterEqual => "ge",
+            BinaryOp::And | BinaryOp::Or => unreachable!("logical operators are short-circuited"),
+        };
+        emit!(self, "cmpl %ecx, %eax");
+        emit!(self, "set{} %al", condition);
+        emit!(self, "movzbl %al, %eax");
+    }
+
+    /// Divide `%eax` by `%ecx`. Dividing the smallest `int` by -1 wraps around instead of trapping.
+    fn division(&mut self, expr: &Expr) {
+        let (divide, negate, end) = (self.label(), self.label(), self.label());
+        emit!(self, "testl %ecx, %ecx");
+        emit!(self, "jne {}", divide);
+        emit!(self, "movl ${}, %edi", expr.span.line);
+        emit!(self, "movl ${}, %esi", expr.span.column);
+        // the runtime function does not return, so the stack can be aligned without restoring it
+        emit!(self, "andq $-16, %rsp");
+        emit!(self, "call c1_division_by_zero");
+        self.place(&divide);
+        emit!(self, "cmpl $-1, %ecx");
+        emit!(self, "je {}", negate);
+        emit!(self, "cltd");
+        emit!(self, "idivl %ecx");
+        emit!(self, "jmp {}", end);
+        self.place(&negate);
+        emit!(self, "negl %eax");
+        self.place(&end);
+    }
+
+    /// Apply an operator to `%xmm0` and `%xmm1`. Comparisons with NaN are false except for `!=`.
+    fn float_operator(&mut self, op: BinaryOp) {
+        match op {
+            BinaryOp::Add => return emit!(self, "addsd %xmm1, %xmm0"),
+            BinaryOp::Sub => return emit!(self, "subsd %xmm1, %xmm0"),
+            BinaryOp::Mul => return emit!(self, "mulsd %xmm1, %xmm0"),
+            BinaryOp::Div => return emit!(self, "divsd %xmm1, %xmm0"),
+            BinaryOp::Equal => {
+                emit!(self, "ucomisd %xmm1, %xmm0");
+                emit!(self, "sete %al");
+                emit!(self, "setnp %cl");
+                emit!(self, "andb %cl, %al");
+            }
+            BinaryOp::NotEqual => {
+                emit!(self, "ucomisd %xmm1, %xmm0");
+                emit!(self, "setne %al");
+                emit!(self, "setp %cl");
+                emit!(self, "orb %cl, %al");
+            }
+            BinaryOp::Greater | BinaryOp::GreaterEqual => {
+                emit!(self, "ucomisd %xmm1, %xmm0");
+                emit!(self, "{} %al", if op == BinaryOp::Greater { "seta" } else { "setae" });
+            }
+            BinaryOp::Less | BinaryOp::LessEqual => {
+                emit!(self, "ucomisd %xmm0, %xmm1");
+                emit!(self, "{} %al", if op == BinaryOp::Less { "seta" } else { "setae" });
+            }
+            BinaryOp::And | BinaryOp::Or => unreachable!("logical operators are short-circuited"),
+        }
+        emit!(self, "movzbl %al, %eax");
+    }
+}
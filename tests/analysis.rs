@@ -0,0 +1,18 @@
+use cb_3::analysis::{analyze, Diagnostic};
+use cb_3::C1Parser;
+
+fn errors(source: &str) -> Vec<String> {
+    let program = C1Parser::parse(source).unwrap();
+    analyze(&program).unwrap_err().iter().map(Diagnostic::to_string).collect()
+}
+
+#[test]
+fn incorrect_programs_are_rejected() {
+    assert_eq!(errors("void main() { x = 1; x = true; }"), ["line 1, column 26: expected a value of type 'int', found 'bool'"]);
+    assert_eq!(errors("void main() { z = 1.5; z = 2; printf(z); }"), ["line 1, column 28: expected a value of type 'float', found 'int'"]);
+}
+
+#[test]
+fn unknown_types_are_only_reported_without_their_cause() {
+    assert_eq!(errors("void main() { printf(foo()); }"), ["line 1, column 22: function 'foo' is not defined"]);
+}
@@ -0,0 +1,103 @@
+use cb_3::ast::Type;
+use cb_3::bytecode::{compile, Instruction, Module};
+use cb_3::c1b::{disassemble, load, write, CodeProblem, LoadErrorKind};
+use cb_3::interpreter::Value;
+use cb_3::{vm, C1Parser};
+use std::fs;
+
+fn compile_source(source: &str) -> Module {
+    compile(&C1Parser::parse(source).unwrap())
+}
+
+#[test]
+fn example_matches_the_golden_files() {
+    let text = fs::read_to_string("tests/data/beispiel.c-1").unwrap();
+    let module = compile_source(&text);
+    let bytes = fs::read("tests/data/beispiel.c1b").unwrap();
+    assert_eq!(write(&module), bytes);
+    assert_eq!(disassemble(&module), fs::read_to_string("tests/data/beispiel.dis").unwrap());
+
+    let loaded = load(&bytes).unwrap();
+    assert_eq!(loaded, module);
+    let mut out = Vec::new();
+    assert_eq!(vm::execute(&loaded, &mut out), Ok(Value::Void));
+    assert_eq!(String::from_utf8(out).unwrap(), fs::read_to_string("tests/data/beispiel.out").unwrap());
+}
+
+#[test]
+fn every_instruction_survives_a_round_trip() {
+    let source = "
+float g;
+bool t = true;
+void nothing() { }
+float main() {
+    int i;
+    i = 3;
+    x = y = -i;
+    nothing();
+    if (t && (x < 0) || false) g = i;
+    if (false) { missing(); printf(unknown); }
+    return g / 2;
+}";
+    let module = compile_source(source);
+    let loaded = load(&write(&module)).unwrap();
+    assert_eq!(loaded, module);
+    assert_eq!(vm::execute(&loaded, &mut Vec::new()), Ok(Value::Float(1.5)));
+}
+
+#[test]
+fn truncated_files_are_rejected() {
+    let bytes = fs::read("tests/data/beispiel.c1b").unwrap();
+    for length in 0..bytes.len() {
+        let error = load(&bytes[..length]).unwrap_err();
+        assert_eq!(error.kind, LoadErrorKind::Truncated, "prefix of {} bytes", length);
+    }
+    let mut longer = bytes.clone();
+    longer.push(0);
+    assert_eq!(load(&longer).unwrap_err().kind, LoadErrorKind::TrailingBytes);
+}
+
+#[test]
+fn malformed_headers_and_tags_are_rejected() {
+    let bytes = fs::read("tests/data/beispiel.c1b").unwrap();
+    let mut wrong_magic = bytes.clone();
+    wrong_magic[0] = b'X';
+    assert_eq!(load(&wrong_magic).unwrap_err().kind, LoadErrorKind::BadMagic);
+
+    let mut wrong_version = bytes.clone();
+    wrong_version[4] = 2;
+    let error = load(&wrong_version).unwrap_err();
+    assert_eq!(error.to_string(), "byte 4: unsupported version 2, expected 1");
+
+    // the first constant starts behind magic, version and the number of constants
+    let mut wrong_tag = bytes;
+    wrong_tag[10] = 9;
+    let error = load(&wrong_tag).unwrap_err();
+    assert_eq!((error.kind, error.offset), (LoadErrorKind::InvalidTag { what: "constant", tag: 9 }, 10));
+}
+
+#[test]
+fn invalid_code_is_rejected() {
+    let module = compile_source("int f(int n) {\n    if (n < 1) return 0;\n    return f(n - 1);\n}\nvoid main() { printf(f(3)); }");
+    let problem = |change: &dyn Fn(&mut Module)| {
+        let mut module = module.clone();
+        change(&mut module);
+        match load(&write(&module)).unwrap_err().kind {
+            LoadErrorKind::InvalidCode { function, instruction, problem } => (function, instruction, problem),
+            kind => panic!("unexpected error {:?}", kind),
+        }
+    };
+    let f = |instruction, problem| ("f".to_string(), instruction, problem);
+    assert_eq!(problem(&|module| module.functions[0].code[0] = Instruction::Load(7)), f(0, CodeProblem::LocalOutOfRange(7)));
+    assert_eq!(problem(&|module| module.functions[0].code[1] = Instruction::Const(99)), f(1, CodeProblem::ConstantOutOfRange(99)));
+    assert_eq!(problem(&|module| module.functions[0].code[3] = Instruction::JumpIfFalse(40)), f(3, CodeProblem::JumpOutOfRange(40)));
+    assert_eq!(problem(&|module| module.functions[0].code[0] = Instruction::UndefinedVariable(0)), f(0, CodeProblem::NameOutOfRange(0)));
+    assert_eq!(problem(&|module| module.functions[0].code[1] = Instruction::Pop), f(2, CodeProblem::StackUnderflow));
+    assert_eq!(problem(&|module| module.functions[0].code[10] = Instruction::ConvertArgument(1, Type::Int)), f(10, CodeProblem::StackUnderflow));
+    assert_eq!(problem(&|module| module.functions[0].code[3] = Instruction::Jump(6)), f(12, CodeProblem::UnbalancedReturn));
+    let without_end = |module: &mut Module| {
+        module.functions[1].code.pop();
+        module.functions[1].lines.pop();
+    };
+    assert_eq!(problem(&without_end), ("main".to_string(), 4, CodeProblem::MissingEnd));
+}
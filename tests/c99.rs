@@ -0,0 +1,68 @@
+mod common;
+
+use cb_3::c99::translate;
+use cb_3::C1Parser;
+use common::assert_like_interpreter;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Translate the program, pipe the C source through `cc` and run the binary
+fn compile_and_run(name: &str, source: &str) -> Output {
+    let program = C1Parser::parse(source).unwrap();
+    let c_source = translate(&program).unwrap_or_else(|errors| panic!("{:?}", errors));
+    common::build_and_run("c99", name, |binary| {
+        let mut cc = Command::new("cc")
+            .args(["-std=c99", "-pedantic", "-Wall", "-Werror", "-x", "c", "-o"])
+            .arg(binary)
+            .arg("-")
+            .stdin(Stdio::piped())
+            .spawn()
+            .expect("cc is installed");
+        cc.stdin.take().unwrap().write_all(c_source.as_bytes()).unwrap();
+        assert!(cc.wait().unwrap().success(), "cc failed for\n{}", c_source);
+    })
+}
+
+#[test]
+fn run_example() {
+    common::run_example(compile_and_run);
+}
+
+#[test]
+fn arithmetic_is_like_the_interpreter() {
+    common::arithmetic_is_like_the_interpreter(compile_and_run);
+}
+
+#[test]
+fn implicit_variables_are_like_the_interpreter() {
+    common::implicit_variables_are_like_the_interpreter(compile_and_run);
+}
+
+#[test]
+fn division_by_zero_aborts() {
+    common::division_by_zero_aborts(compile_and_run);
+}
+
+#[test]
+fn scopes_and_evaluation_order_are_kept() {
+    let source = "
+int trace(int value) { printf(value); return value; }
+int pair(int a, int b) { return a * 10 + b; }
+int main() {
+    x = 1;
+    {
+        int x = 2;
+        printf(x);
+        if (x > 1) { float x = 2.5; printf(x); }
+    }
+    printf(x);
+    printf(pair(trace(1), trace(2)));
+    printf(trace(3) - trace(4));
+    y = 0;
+    printf((y = 5) + y);
+    for (i = 0; i < 3; i = i + 1) { int j = i * 2; printf(j); }
+    return pair(x, y);
+}";
+    let output = assert_like_interpreter(compile_and_run, "scopes", source);
+    assert_eq!(output.status.code(), Some(15));
+}
@@ -0,0 +1,106 @@
+//! Helpers and shared tests for the backends that build native binaries
+
+use cb_3::interpreter;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Translate a program with the name and source text to a binary and run it
+pub type CompileAndRun = fn(&str, &str) -> Output;
+
+/// A temporary directory that is removed with its content when it is dropped
+struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Create an empty directory, unique for the test process
+    fn new(name: &str) -> TempDir {
+        let path = std::env::temp_dir().join(format!("c1-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir { path }
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Let `build` create the binary at the given path in a temporary directory and run it. The
+/// directory is removed afterwards, also if the build fails.
+pub fn build_and_run(backend: &str, name: &str, build: impl FnOnce(&Path)) -> Output {
+    let directory = TempDir::new(&format!("{}-{}", backend, name));
+    let binary = directory.path.join(name);
+    build(&binary);
+    Command::new(&binary).output().unwrap()
+}
+
+/// Compare the output of the compiled program with the output of the interpreter
+pub fn assert_like_interpreter(compile_and_run: CompileAndRun, name: &str, source: &str) -> Output {
+    let output = compile_and_run(name, source);
+    let mut expected = Vec::new();
+    interpreter::run(source, &mut expected).unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&expected));
+    output
+}
+
+pub fn run_example(compile_and_run: CompileAndRun) {
+    let text = fs::read_to_string("tests/data/beispiel.c-1").unwrap();
+    let output = compile_and_run("beispiel", &text);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), fs::read_to_string("tests/data/beispiel.out").unwrap());
+    assert!(output.status.success());
+}
+
+pub fn arithmetic_is_like_the_interpreter(compile_and_run: CompileAndRun) {
+    let source = "
+float third = 1 / 3.0;
+int big = 2147483647;
+bool yes = true;
+void main() {
+    printf(third);
+    printf(big + 1);
+    printf(big * big);
+    printf((-2147483647 - 1) / (-1));
+    printf(-7 / 2 + 7 / (-2));
+    printf(-third * 3);
+    zero = 0.0;
+    nan = zero / zero;
+    printf(nan);
+    printf(nan == nan);
+    printf(nan != nan);
+    printf((nan < 1) || (nan >= 1));
+    printf(yes && (2 <= 2.5));
+    printf((true != false) && (1.5 > 1));
+    printf(1e300 * 1e300);
+    printf(1e400 > 0);
+}";
+    assert_like_interpreter(compile_and_run, "arithmetic", source);
+}
+
+/// Implicit variables only get values of the type of their first value, which the type checker
+/// ensures
+pub fn implicit_variables_are_like_the_interpreter(compile_and_run: CompileAndRun) {
+    let source = "
+void main() {
+    z = 1.5;
+    z = 2.0;
+    printf(z);
+    n = 1;
+    n = n * 2;
+    printf(n);
+    b = n > z;
+    printf(b);
+}";
+    assert_like_interpreter(compile_and_run, "implicit", source);
+}
+
+pub fn division_by_zero_aborts(compile_and_run: CompileAndRun) {
+    let source = "void main() {\n    printf(1);\n    x = 0;\n    printf(1 / x);\n}";
+    let output = compile_and_run("division", source);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1\n");
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "line 4, column 12: division by zero\n");
+    assert_eq!(output.status.code(), Some(1));
+}
@@ -0,0 +1,152 @@
+
+define i32 @c1.blub() {
+entry:
+  %blub1.0 = alloca i32
+  %blub2.1 = alloca i32
+  %blub3.2 = alloca i32
+  %blub4.3 = alloca i32
+  store i32 23, ptr %blub1.0
+  store i32 17, ptr %blub2.1
+  store i32 42, ptr %blub3.2
+  %0 = load i32, ptr %blub1.0
+  %1 = load i32, ptr %blub2.1
+  %2 = load i32, ptr %blub3.2
+  %3 = add i32 %1, %2
+  %4 = mul i32 %0, %3
+  store i32 %4, ptr %blub4.3
+  %5 = load i32, ptr %blub1.0
+  %6 = load i32, ptr %blub4.3
+  %7 = icmp slt i32 %5, %6
+  br i1 %7, label %then1, label %end3
+then1:
+  %8 = load i32, ptr %blub2.1
+  ret i32 %8
+end3:
+  %9 = load i32, ptr %blub3.2
+  ret i32 %9
+}
+
+define double @c1.blah() {
+entry:
+  %a.0 = alloca i32
+  %b.1 = alloca i32
+  store i32 1, ptr %a.0
+  store i32 2, ptr %b.1
+  %0 = load i32, ptr %a.0
+  %1 = call i32 @c1.blub()
+  %2 = icmp slt i32 %0, %1
+  br i1 %2, label %then4, label %end6
+then4:
+  %3 = load i32, ptr %b.1
+  %4 = call i32 @c1.blub()
+  %5 = icmp sgt i32 %3, %4
+  br i1 %5, label %then7, label %end9
+then7:
+  %6 = call i32 @c1.blub()
+  %7 = call i32 @c1.blub()
+  %8 = add i32 %6, %7
+  call void @c1_print_int(i32 %8)
+  br label %end9
+end9:
+  br label %end6
+end6:
+  ret double 0x400921F9F01B866E
+}
+
+define void @c1.main() {
+entry:
+  %a.0 = alloca i32
+  %b.1 = alloca i32
+  store i32 1, ptr %a.0
+  store i32 2, ptr %b.1
+  %0 = load i32, ptr %a.0
+  %1 = load i32, ptr %b.1
+  %2 = icmp sle i32 %0, %1
+  br i1 %2, label %then10, label %end12
+then10:
+  %3 = load i32, ptr %a.0
+  %4 = load i32, ptr %b.1
+  %5 = add i32 %3, %4
+  call void @c1_print_int(i32 %5)
+  br label %end12
+end12:
+  %6 = load i32, ptr %a.0
+  %7 = load i32, ptr %b.1
+  %8 = icmp sge i32 %6, %7
+  br i1 %8, label %then13, label %end15
+then13:
+  %9 = load i32, ptr %a.0
+  %10 = load i32, ptr %b.1
+  %11 = sub i32 %9, %10
+  call void @c1_print_int(i32 %11)
+  br label %end15
+end15:
+  %12 = call i32 @c1.blub()
+  call void @c1_print_int(i32 %12)
+  %13 = call double @c1.blah()
+  call void @c1_print_float(double %13)
+  ret void
+}
+
+define private void @c1_init() {
+entry:
+  ret void
+}
+
+define i32 @main() {
+  call void @c1_init()
+  call void @c1.main()
+  ret i32 0
+}
+
+@.format_int = private unnamed_addr constant [4 x i8] c"%d\0A\00"
+@.format_float = private unnamed_addr constant [4 x i8] c"%f\0A\00"
+@.nan = private unnamed_addr constant [4 x i8] c"nan\00"
+@.true = private unnamed_addr constant [5 x i8] c"true\00"
+@.false = private unnamed_addr constant [6 x i8] c"false\00"
+@.format_division = private unnamed_addr constant [38 x i8] c"line %d, column %d: division by zero\0A\00"
+
+declare i32 @printf(ptr, ...)
+declare i32 @puts(ptr)
+declare i32 @dprintf(i32, ptr, ...)
+declare void @exit(i32) noreturn
+
+define private void @c1_print_int(i32 %value) {
+  call i32 (ptr, ...) @printf(ptr @.format_int, i32 %value)
+  ret void
+}
+
+define private void @c1_print_float(double %value) {
+  %is_nan = fcmp uno double %value, %value
+  br i1 %is_nan, label %nan, label %number
+nan:
+  call i32 @puts(ptr @.nan)
+  ret void
+number:
+  call i32 (ptr, ...) @printf(ptr @.format_float, double %value)
+  ret void
+}
+
+define private void @c1_print_bool(i1 %value) {
+  %text = select i1 %value, ptr @.true, ptr @.false
+  call i32 @puts(ptr %text)
+  ret void
+}
+
+define private i32 @c1_div(i32 %lhs, i32 %rhs, i32 %line, i32 %column) {
+  %is_zero = icmp eq i32 %rhs, 0
+  br i1 %is_zero, label %zero, label %nonzero
+zero:
+  call i32 (i32, ptr, ...) @dprintf(i32 2, ptr @.format_division, i32 %line, i32 %column)
+  call void @exit(i32 1)
+  unreachable
+nonzero:
+  %is_minus_one = icmp eq i32 %rhs, -1
+  br i1 %is_minus_one, label %negate, label %divide
+negate:
+  %negated = sub i32 0, %lhs
+  ret i32 %negated
+divide:
+  %quotient = sdiv i32 %lhs, %rhs
+  ret i32 %quotient
+}
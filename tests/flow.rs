@@ -0,0 +1,83 @@
+use cb_3::ast::Type;
+use cb_3::flow::{check_returns, FlowErrorKind};
+use cb_3::C1Parser;
+use std::fs;
+
+fn flow_errors(text: &str) -> Vec<(usize, usize, FlowErrorKind)> {
+    let program = C1Parser::parse(text).unwrap();
+    check_returns(&program).into_iter().map(|error| (error.span.line, error.span.column, error.kind)).collect()
+}
+
+#[test]
+fn example_returns_on_every_path() {
+    let text = fs::read_to_string("tests/data/beispiel.c-1").unwrap();
+    let program = C1Parser::parse(text.as_str()).unwrap();
+    assert_eq!(check_returns(&program), []);
+}
+
+#[test]
+fn every_path_of_a_non_void_function_returns() {
+    let text = "int a(bool b) { if (b) return 1; else return 2; }
+int b(bool b) { if (b) return 1; }
+int c(bool b) { while (b) { return 1; } }
+int d(bool b) { do { return 1; } while (b); }
+int e(bool b) { if (b) { if (b) return 1; else { return 2; } } else return 3; }
+float f(bool b) { if (b) { return 1.0; } else { printf(b); } }
+void g(bool b) { if (b) return; }";
+    let missing = |function: &str, expected| FlowErrorKind::MissingReturn { function: function.to_string(), expected };
+    assert_eq!(
+        flow_errors(text),
+        [(2, 5, missing("b", Type::Int)), (3, 5, missing("c", Type::Int)), (6, 7, missing("f", Type::Float))]
+    );
+}
+
+#[test]
+fn return_values_must_match_void() {
+    let text = "void f() { return 1; }
+int g() {
+    if (true) {
+        return;
+    }
+    return 2;
+}";
+    let errors = flow_errors(text);
+    assert_eq!(
+        errors,
+        [
+            (1, 19, FlowErrorKind::ReturnValueInVoidFunction("f".to_string())),
+            (4, 9, FlowErrorKind::MissingReturnValue { function: "g".to_string(), expected: Type::Int }),
+        ]
+    );
+    let program = C1Parser::parse(text).unwrap();
+    assert_eq!(check_returns(&program)[0].to_string(), "line 1, column 19: void function 'f' cannot return a value");
+}
+
+#[test]
+fn statements_after_return_are_unreachable() {
+    let text = "int f(bool b) {
+    if (b) {
+        return 1;
+        printf(2);
+        printf(3);
+    } else {
+        return 2;
+    }
+    x = 3;
+    return x;
+}
+void g() {
+    return;
+    return 1;
+}";
+    assert_eq!(
+        flow_errors(text),
+        [
+            (4, 9, FlowErrorKind::UnreachableStatement),
+            (9, 5, FlowErrorKind::UnreachableStatement),
+            (14, 5, FlowErrorKind::UnreachableStatement),
+            (14, 12, FlowErrorKind::ReturnValueInVoidFunction("g".to_string())),
+        ]
+    );
+    let program = C1Parser::parse(text).unwrap();
+    assert_eq!(check_returns(&program)[0].to_string(), "line 4, column 9: warning: statement is unreachable");
+}
@@ -0,0 +1,84 @@
+use cb_3::init::{check_initialization, InitErrorKind};
+use cb_3::C1Parser;
+use std::fs;
+
+fn init_errors(text: &str) -> Vec<(usize, InitErrorKind)> {
+    let program = C1Parser::parse(text).unwrap();
+    check_initialization(&program).into_iter().map(|error| (error.span.line, error.kind)).collect()
+}
+
+fn unassigned(name: &str) -> InitErrorKind {
+    InitErrorKind::Unassigned(name.to_string())
+}
+
+#[test]
+fn example_assigns_before_reading() {
+    let text = fs::read_to_string("tests/data/beispiel.c-1").unwrap();
+    let program = C1Parser::parse(text.as_str()).unwrap();
+    assert_eq!(check_initialization(&program), []);
+}
+
+#[test]
+fn declared_variable_is_never_assigned() {
+    let text = "void main() {
+    int blub4;
+    int blub5;
+    blub4 = 1;
+    printf(blub5 + blub4);
+    printf(blub5);
+    printf(blub6);
+}";
+    // blub6 is reported by the resolver
+    assert_eq!(init_errors(text), [(5, InitErrorKind::NeverAssigned("blub5".to_string()))]);
+    let program = C1Parser::parse(text).unwrap();
+    assert_eq!(
+        check_initialization(&program)[0].to_string(),
+        "line 5, column 12: variable 'blub5' is never assigned"
+    );
+}
+
+#[test]
+fn shadowing_variables_are_separate() {
+    let text = "void main() {
+    a = 1;
+    { int a; printf(a); a = 2; }
+    printf(a);
+    { int b; b = 1; }
+    { int b; printf(b); }
+}";
+    assert_eq!(init_errors(text), [(3, unassigned("a")), (6, InitErrorKind::NeverAssigned("b".to_string()))]);
+}
+
+#[test]
+fn branches_must_all_assign() {
+    let text = "int g;
+void main(bool c) {
+    if (c) { a = 1; b = 1; } else { a = 2; }
+    printf(a + b);
+    if (c) { d = 1; }
+    printf(d);
+    if (c) { return; } else { e = 1; }
+    printf(e + g);
+}";
+    assert_eq!(init_errors(text), [(4, unassigned("b")), (6, unassigned("d"))]);
+}
+
+#[test]
+fn loops_and_short_circuits_can_skip_assignments() {
+    let text = "void main(bool c) {
+    while (c) { a = 1; }
+    do { b = 1; } while (c);
+    for (i = 0; i < 3; i = i + 1) { d = i; }
+    printf(a + b + d + i);
+    if (c && (e = true)) { printf(e); }
+    printf(e);
+    int f;
+    printf(f);
+    int g = 1;
+    printf(g);
+}";
+    assert_eq!(
+        init_errors(text),
+        [(5, unassigned("a")), (5, unassigned("d")), (7, unassigned("e")), (9, InitErrorKind::NeverAssigned("f".to_string()))]
+    );
+}
@@ -0,0 +1,167 @@
+use cb_3::interpreter::{run, RuntimeErrorKind, Value, MAX_CALL_DEPTH};
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+/// Run the program and return the value of main together with the output
+fn run_program(source: &str) -> (Value, String) {
+    let mut out = Vec::new();
+    let value = run(source, &mut out).unwrap_or_else(|error| panic!("{}", error));
+    (value, String::from_utf8(out).unwrap())
+}
+
+fn run_error(source: &str) -> (RuntimeErrorKind, Option<usize>) {
+    let error = run(source, &mut Vec::new()).unwrap_err();
+    (error.kind.clone(), error.line())
+}
+
+#[test]
+fn run_example() {
+    let text = fs::read_to_string("tests/data/beispiel.c-1").unwrap();
+    let expected = fs::read_to_string("tests/data/beispiel.out").unwrap();
+    assert_eq!(run_program(&text), (Value::Void, expected));
+}
+
+#[test]
+fn loops_and_recursion() {
+    let source = "
+int fib(int n) {
+    if (n < 2) return n;
+    return fib(n - 1) + fib(n - 2);
+}
+int main() {
+    for (i = 0; i < 5; i = i + 1) printf(fib(i));
+    n = 3;
+    while (n > 0) n = n - 1;
+    do { n = n + 10; } while (n < 25);
+    return n;
+}";
+    assert_eq!(run_program(source), (Value::Int(30), "0\n1\n1\n2\n3\n".to_string()));
+}
+
+#[test]
+fn globals_declarations_and_promotion() {
+    let source = "
+int counter;
+float half = 1 / 2.0;
+void count() { counter = counter + 1; }
+float main() {
+    count();
+    count();
+    float f = counter;
+    printf(f);
+    printf(half);
+    printf((counter == 2) || (1 / 0 == 1));
+    return f / 4;
+}";
+    assert_eq!(run_program(source), (Value::Float(0.5), "2.000000\n0.500000\ntrue\n".to_string()));
+}
+
+#[test]
+fn syntax_errors_are_reported() {
+    // C(-1) has no '!' operator
+    let source = "void main() {\n    printf(true && !(false));\n}";
+    let (kind, line) = run_error(source);
+    assert!(matches!(kind, RuntimeErrorKind::Syntax(_)));
+    assert_eq!(line, Some(2));
+}
+
+#[test]
+fn integer_arithmetic_wraps() {
+    let source = "int main() { x = 2147483647; printf(x + 1); printf(-(x + 1) / (-1)); printf(7 / 2); printf(-7 / 2); return 0; }";
+    let (_, out) = run_program(source);
+    assert_eq!(out, "-2147483648\n-2147483648\n3\n-3\n");
+}
+
+#[test]
+fn locals_are_separate_per_call() {
+    let source = "
+int f() { a = 5; return a; }
+int main() { a = 1; f(); return a; }";
+    assert_eq!(run_program(source).0, Value::Int(1));
+}
+
+#[test]
+fn runtime_errors_have_lines() {
+    let source = "int main() {\n  a = 0;\n  return 1 / a;\n}";
+    assert_eq!(run_error(source), (RuntimeErrorKind::DivisionByZero, Some(3)));
+    let source = "void main() {\n\n  foo(1);\n}";
+    assert_eq!(run_error(source), (RuntimeErrorKind::UndefinedFunction("foo".to_string()), Some(3)));
+    let error = run(source, &mut Vec::new()).unwrap_err();
+    assert_eq!(error.to_string(), "line 3, column 3: call to undefined function 'foo'");
+    let source = "void main() { printf(blub5); }";
+    assert_eq!(run_error(source), (RuntimeErrorKind::UndefinedVariable("blub5".to_string()), Some(1)));
+    let source = "void f() { }\nvoid main() {\n printf(f());\n}";
+    assert_eq!(run_error(source), (RuntimeErrorKind::PrintVoid, Some(3)));
+    let source = "int f() { }\nvoid main() { f(); }";
+    assert_eq!(run_error(source), (RuntimeErrorKind::MissingReturn("f".to_string()), Some(1)));
+    let source = "void main() { int x; x = true; }";
+    assert!(matches!(run_error(source).0, RuntimeErrorKind::TypeMismatch { .. }));
+    let source = "int f() { return f(); }\nvoid main() { f(); }";
+    assert_eq!(run_error(source), (RuntimeErrorKind::StackOverflow, Some(1)));
+    assert_eq!(run_error("void f() { }").0, RuntimeErrorKind::UndefinedFunction("main".to_string()));
+}
+
+#[test]
+fn stack_usage_is_bounded() {
+    // the deepest recursion that is allowed fits into the stack of the interpreter thread
+    let source = format!("int f(int n) {{ if (n > 0) {{ return 1 + f(n - 1); }} return 0; }}\nint main() {{ return f({}); }}", MAX_CALL_DEPTH - 2);
+    assert_eq!(run_program(&source).0, Value::Int(MAX_CALL_DEPTH as i32 - 2));
+    // deeply nested bodies use up the stack before the call depth is reached
+    let body = format!("{}return 1 + f(n - 1);{}", "{".repeat(300), "}".repeat(300));
+    let source = format!("int f(int n) {{ if (n > 0) {} return 0; }}\nint main() {{ return f(900); }}", body);
+    assert_eq!(run_error(&source), (RuntimeErrorKind::StackExhausted, Some(1)));
+}
+
+/// A writer that cannot be sent to another thread
+struct SharedOutput(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.0.borrow().len() >= 4 {
+            return Err(io::Error::new(io::ErrorKind::WriteZero, "full"));
+        }
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn output_is_written_on_the_calling_thread() {
+    let buffer = Rc::new(RefCell::new(Vec::new()));
+    let error = run("void main() {\n    printf(1);\n    printf(2);\n    printf(3);\n}", &mut SharedOutput(buffer.clone())).unwrap_err();
+    assert_eq!(String::from_utf8(buffer.borrow().clone()).unwrap(), "1\n2\n");
+    assert_eq!((error.kind.clone(), error.line()), (RuntimeErrorKind::Output("full".to_string()), Some(4)));
+}
+
+#[test]
+fn blocks_have_their_own_scope() {
+    let source = "
+int x = 1;
+void show() { printf(x); }
+int main() {
+    int y = 2;
+    {
+        int x = 10;
+        float y = x;
+        printf(y);
+        show();
+    }
+    printf(y);
+    for (i = 0; i < 2; i = i + 1) {
+        int n;
+        if (i == 0) { n = 5; } else { printf(n); }
+    }
+    return x;
+}";
+    assert_eq!(
+        run_error(source),
+        (RuntimeErrorKind::UninitializedVariable("n".to_string()), Some(15))
+    );
+    let source = source.replace("else { printf(n); }", "else { printf(i); }");
+    assert_eq!(run_program(&source), (Value::Int(1), "10.000000\n1\n2\n1\n".to_string()));
+}
@@ -0,0 +1,119 @@
+use cb_3::llvm::generate;
+use cb_3::C1Parser;
+use std::fs;
+
+/// Translate the program and return the IR of the functions, without the runtime
+fn functions(source: &str) -> String {
+    let program = C1Parser::parse(source).unwrap();
+    let ir = generate(&program).unwrap_or_else(|errors| panic!("{:?}", errors));
+    let start = ir.find("\ndefine").unwrap();
+    let end = ir.find("\ndefine private void @c1_init").unwrap();
+    ir[start + 1..end].to_string()
+}
+
+#[test]
+fn example_matches_golden_file() {
+    let text = fs::read_to_string("tests/data/beispiel.c-1").unwrap();
+    let program = C1Parser::parse(&text).unwrap();
+    assert_eq!(generate(&program).unwrap(), fs::read_to_string("tests/data/beispiel.ll").unwrap());
+}
+
+#[test]
+fn logical_operators_short_circuit() {
+    let ir = functions("bool both(bool a, bool b) { return a && b; }\nbool either(bool a, bool b) { return a || b; }\nvoid main() {}");
+    assert_eq!(
+        ir,
+        "define i1 @c1.both(i1 %a.arg, i1 %b.arg) {
+entry:
+  %a.0 = alloca i1
+  %b.1 = alloca i1
+  store i1 %a.arg, ptr %a.0
+  store i1 %b.arg, ptr %b.1
+  %0 = load i1, ptr %a.0
+  br i1 %0, label %rhs1, label %end2
+rhs1:
+  %1 = load i1, ptr %b.1
+  br label %end2
+end2:
+  %2 = phi i1 [ false, %entry ], [ %1, %rhs1 ]
+  ret i1 %2
+}
+
+define i1 @c1.either(i1 %a.arg, i1 %b.arg) {
+entry:
+  %a.0 = alloca i1
+  %b.1 = alloca i1
+  store i1 %a.arg, ptr %a.0
+  store i1 %b.arg, ptr %b.1
+  %0 = load i1, ptr %a.0
+  br i1 %0, label %end4, label %rhs3
+rhs3:
+  %1 = load i1, ptr %b.1
+  br label %end4
+end4:
+  %2 = phi i1 [ true, %entry ], [ %1, %rhs3 ]
+  ret i1 %2
+}
+
+define void @c1.main() {
+entry:
+  ret void
+}
+"
+    );
+}
+
+#[test]
+fn comparisons_promote_and_nested_logic_merges_from_the_last_block() {
+    let ir = functions("bool compare(int a, float b) { return (a < b) && ((a != 2) || (b >= 3)); }\nvoid main() {}");
+    assert!(ir.starts_with(
+        "define i1 @c1.compare(i32 %a.arg, double %b.arg) {
+entry:
+  %a.0 = alloca i32
+  %b.1 = alloca double
+  store i32 %a.arg, ptr %a.0
+  store double %b.arg, ptr %b.1
+  %0 = load i32, ptr %a.0
+  %1 = sitofp i32 %0 to double
+  %2 = load double, ptr %b.1
+  %3 = fcmp olt double %1, %2
+  br i1 %3, label %rhs1, label %end2
+rhs1:
+  %4 = load i32, ptr %a.0
+  %5 = icmp ne i32 %4, 2
+  br i1 %5, label %end4, label %rhs3
+rhs3:
+  %6 = load double, ptr %b.1
+  %7 = sitofp i32 3 to double
+  %8 = fcmp oge double %6, %7
+  br label %end4
+end4:
+  %9 = phi i1 [ true, %rhs1 ], [ %8, %rhs3 ]
+  br label %end2
+end2:
+  %10 = phi i1 [ false, %entry ], [ %9, %end4 ]
+  ret i1 %10
+}
+"
+    ));
+    let ir = functions("void main() { x = 1.5; printf((x == x) && (x != x)); printf((1 <= 2) || (3 > 4)); }");
+    for instruction in ["fcmp oeq double", "fcmp une double", "icmp sle i32 1, 2", "icmp sgt i32 3, 4"] {
+        assert!(ir.contains(instruction), "{} missing in\n{}", instruction, ir);
+    }
+}
+
+#[test]
+fn globals_division_and_unreachable_code() {
+    let program = C1Parser::parse(
+        "float scale = 2;\nint counter;\nint divide(int a, int b) { return a / b; }\nint main() {\n    return divide(counter, 2);\n    printf(1);\n}",
+    )
+    .unwrap();
+    let ir = generate(&program).unwrap();
+    assert!(ir.starts_with("@c1.global.0 = internal global double 0.0\n@c1.global.1 = internal global i32 0\n"));
+    assert!(ir.contains("  %2 = call i32 @c1_div(i32 %0, i32 %1, i32 3, i32 35)\n  ret i32 %2\n"));
+    assert!(ir.contains("  ret i32 %1\ndead1:\n  call void @c1_print_int(i32 1)\n  unreachable\n}\n"));
+    assert!(ir.contains(
+        "define private void @c1_init() {\nentry:\n  %0 = sitofp i32 2 to double\n  store double %0, ptr @c1.global.0\n  ret void\n}\n"
+    ));
+    assert!(ir.contains("define i32 @main() {\n  call void @c1_init()\n  %status = call i32 @c1.main()\n  ret i32 %status\n}\n"));
+}
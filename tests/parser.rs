@@ -1,4 +1,5 @@
-use cb_3::C1Parser;
+use cb_3::ast::{BinaryOp, ExprKind, Statement, StatementKind, Type, UnaryOp};
+use cb_3::{C1Parser, C1Token, LexErrorKind, ParseErrorKind};
 use std::fs;
 
 #[test]
@@ -7,3 +8,377 @@ fn run_example() {
     let result = C1Parser::parse(text.as_str());
     assert!(result.is_ok(), "Parse result: {}", result.err().unwrap());
 }
+
+#[test]
+fn example_produces_ast() {
+    let text = fs::read_to_string("tests/data/beispiel.c-1").unwrap();
+    let program = C1Parser::parse(text.as_str()).unwrap();
+    let names: Vec<&str> = program.functions.iter().map(|f| f.name.name.as_str()).collect();
+    assert_eq!(names, ["blub", "blah", "main"]);
+    assert_eq!(program.functions[0].return_type, Type::Int);
+    assert_eq!(program.functions[1].return_type, Type::Float);
+    assert_eq!(program.functions[1].span.line, 10);
+    assert_eq!(program.functions[2].body.statements.len(), 6);
+}
+
+#[test]
+fn operator_precedence() {
+    let program = C1Parser::parse("int f() { return -a + b * c == 4; }").unwrap();
+    let body = &program.functions[0].body.statements;
+    let value = match &body[0].kind {
+        StatementKind::Return(Some(value)) => value,
+        other => panic!("expected return, got {:?}", other),
+    };
+    let (lhs, rhs) = match &value.kind {
+        ExprKind::Binary { op: BinaryOp::Equal, lhs, rhs } => (lhs, rhs),
+        other => panic!("expected ==, got {:?}", other),
+    };
+    assert_eq!(rhs.kind, ExprKind::Int(4));
+    match &lhs.kind {
+        ExprKind::Binary { op: BinaryOp::Add, lhs, rhs } => {
+            assert!(matches!(lhs.kind, ExprKind::Unary { op: UnaryOp::Neg, .. }));
+            assert!(matches!(rhs.kind, ExprKind::Binary { op: BinaryOp::Mul, .. }));
+        }
+        other => panic!("expected +, got {:?}", other),
+    }
+}
+
+#[test]
+fn chained_assignment_is_right_associative() {
+    let program = C1Parser::parse("void f() { a = b = 1.5; }").unwrap();
+    match &program.functions[0].body.statements[0].kind {
+        StatementKind::Assignment(assignment) => {
+            assert_eq!(assignment.target.name, "a");
+            match &assignment.value.kind {
+                ExprKind::Assign(inner) => {
+                    assert_eq!(inner.target.name, "b");
+                    assert_eq!(inner.value.kind, ExprKind::Float(1.5));
+                }
+                other => panic!("expected assignment, got {:?}", other),
+            }
+        }
+        other => panic!("expected assignment, got {:?}", other),
+    }
+}
+
+#[test]
+fn missing_semicolon_is_reported_with_location() {
+    let error = C1Parser::parse("int f() {\n  return 1\n}").unwrap_err();
+    assert_eq!(error.found(), Some(C1Token::RightBrace));
+    assert_eq!(error.expected(), [C1Token::Semicolon]);
+    assert_eq!(error.span.line, 3);
+    assert_eq!(error.span.column, 1);
+    assert_eq!(error.span.start, 21);
+    assert_eq!(
+        error.to_string(),
+        "line 3, column 1: expected ';' after return statement, found '}'"
+    );
+}
+
+#[test]
+fn unexpected_end_of_input() {
+    let error = C1Parser::parse("void main() {\n  printf(1);").unwrap_err();
+    assert_eq!(error.found(), None);
+    assert_eq!(error.expected(), [C1Token::RightBrace]);
+    assert_eq!((error.span.line, error.span.column), (2, 13));
+    assert_eq!(error.span.start, error.span.end);
+    assert!(error.to_string().ends_with("found end of input"), "{}", error);
+}
+
+#[test]
+fn invalid_factor_lists_all_alternatives() {
+    let error = C1Parser::parse("void main() { x = * 2; }").unwrap_err();
+    assert_eq!(error.found(), Some(C1Token::Asterisk));
+    assert_eq!(
+        error.expected(),
+        [
+            C1Token::ConstInt,
+            C1Token::ConstFloat,
+            C1Token::ConstBoolean,
+            C1Token::Identifier,
+            C1Token::LeftParenthesis
+        ]
+    );
+    assert_eq!(
+        error.to_string(),
+        "line 1, column 19: expected integer literal, float literal, boolean literal, identifier or '(' in expression, found '*'"
+    );
+}
+
+#[test]
+fn integer_literal_out_of_range() {
+    let error = C1Parser::parse("int f() { return 2147483648; }").unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::IntegerOutOfRange("2147483648".to_string()));
+    assert_eq!(error.span.column, 18);
+
+    // beyond 64 bits the lexer already rejects the literal
+    let error = C1Parser::parse("int f() { return 99999999999999999999; }").unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::Lexical(LexErrorKind::IntegerOverflow("99999999999999999999".to_string())));
+}
+
+#[test]
+fn every_invalid_escape_is_reported() {
+    let (_, errors) = C1Parser::parse_with_recovery("void main() {\n  x = \"\\a\\q\";\n}");
+    let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+    assert_eq!(messages, ["line 2, column 8: invalid escape sequence '\\a'", "line 2, column 10: invalid escape sequence '\\q'"]);
+}
+
+#[test]
+fn nodes_cover_their_tokens() {
+    let text = "void main() {\n  x = (a + 12) * b;\n}";
+    let program = C1Parser::parse(text).unwrap();
+    let function = &program.functions[0];
+    assert_eq!(&text[function.span.start..function.span.end], text);
+    let statement = &function.body.statements[0];
+    assert_eq!(&text[statement.span.start..statement.span.end], "x = (a + 12) * b;");
+    assert_eq!((statement.span.line, statement.span.column), (2, 3));
+    match &statement.kind {
+        StatementKind::Assignment(assignment) => {
+            let value = &assignment.value.span;
+            assert_eq!(&text[value.start..value.end], "(a + 12) * b");
+            assert_eq!(value.column, 7);
+        }
+        other => panic!("expected assignment, got {:?}", other),
+    }
+}
+
+#[test]
+fn recovery_reports_every_missing_semicolon() {
+    let text = "void main() {\n a = 1\n b = 2\n printf(a)\n return 0\n c = 3\n}";
+    let (program, errors) = C1Parser::parse_with_recovery(text);
+    let lines: Vec<usize> = errors.iter().map(|error| error.span.line).collect();
+    assert_eq!(lines, [3, 4, 5, 6, 7]);
+    assert!(errors.iter().all(|error| error.expected() == [C1Token::Semicolon]));
+    // the missing semicolons are inserted, so no statement is lost
+    let statements = &program.functions[0].body.statements;
+    assert_eq!(statements.len(), 5);
+    assert!(matches!(statements[4].kind, StatementKind::Assignment(_)));
+}
+
+#[test]
+fn recovery_resumes_at_next_functiondefinition() {
+    let text = "int f( { return 1; }\nvoid g() { if (1 { } }\nint h() { return 2; }";
+    let (program, errors) = C1Parser::parse_with_recovery(text);
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert_eq!(errors[0].found(), Some(C1Token::LeftBrace));
+    assert_eq!(errors[0].expected().last(), Some(&C1Token::RightParenthesis));
+    assert_eq!(errors[1].span.line, 2);
+    let names: Vec<&str> = program.functions.iter().map(|f| f.name.name.as_str()).collect();
+    assert_eq!(names, ["g", "h"]);
+}
+
+#[test]
+fn recovery_closes_unterminated_function_body() {
+    let (program, errors) = C1Parser::parse_with_recovery("void f() { a = 1;\nint g() { }");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].found(), Some(C1Token::KwInt));
+    assert_eq!(errors[0].expected(), [C1Token::RightBrace]);
+    assert_eq!(program.functions.len(), 2);
+}
+
+#[test]
+fn recovery_stops_after_too_many_errors() {
+    let text = format!("void main() {{ {} }}", "a = ; ".repeat(100));
+    let (_, errors) = C1Parser::parse_with_recovery(&text);
+    assert_eq!(errors.len(), C1Parser::MAX_ERRORS);
+    assert_eq!(errors.last().unwrap().kind, ParseErrorKind::TooManyErrors);
+    assert!(errors[..C1Parser::MAX_ERRORS - 1].iter().all(|error| error.kind != ParseErrorKind::TooManyErrors));
+
+    // exactly as many errors as the limit allows are reported without truncation
+    let text = format!("void main() {{ {} }}", "a = ; ".repeat(C1Parser::MAX_ERRORS - 1));
+    let (_, errors) = C1Parser::parse_with_recovery(&text);
+    assert_eq!(errors.len(), C1Parser::MAX_ERRORS - 1);
+    assert!(errors.iter().all(|error| error.kind != ParseErrorKind::TooManyErrors));
+}
+
+/// Return the branches of an if statement
+fn if_branches(statement: &Statement) -> (&Statement, Option<&Statement>) {
+    match &statement.kind {
+        StatementKind::If { then_branch, else_branch, .. } => (then_branch, else_branch.as_deref()),
+        other => panic!("expected if statement, got {:?}", other),
+    }
+}
+
+#[test]
+fn else_belongs_to_nearest_if() {
+    let program = C1Parser::parse("void f() { if (a) if (b) x = 1; else x = 2; }").unwrap();
+    let (inner, outer_else) = if_branches(&program.functions[0].body.statements[0]);
+    assert!(outer_else.is_none());
+    let (_, inner_else) = if_branches(inner);
+    assert!(matches!(inner_else.unwrap().kind, StatementKind::Assignment(_)));
+}
+
+#[test]
+fn else_after_braced_then_branch_belongs_to_outer_if() {
+    let program = C1Parser::parse("void f() { if (a) { if (b) x = 1; } else x = 2; }").unwrap();
+    let (then_branch, else_branch) = if_branches(&program.functions[0].body.statements[0]);
+    assert!(else_branch.is_some());
+    match &then_branch.kind {
+        StatementKind::Block(block) => assert!(if_branches(&block.statements[0]).1.is_none()),
+        other => panic!("expected block, got {:?}", other),
+    }
+}
+
+#[test]
+fn else_if_chain() {
+    let text = "int sign() {\n if (x < 0) return -1;\n else if (x == 0) return 0;\n else { return 1; }\n}";
+    let program = C1Parser::parse(text).unwrap();
+    let (_, else_branch) = if_branches(&program.functions[0].body.statements[0]);
+    let else_if = else_branch.unwrap();
+    assert_eq!(else_if.span.line, 3);
+    let (_, last) = if_branches(else_if);
+    assert!(matches!(last.unwrap().kind, StatementKind::Block(_)));
+}
+
+#[test]
+fn else_without_if_is_an_error() {
+    let error = C1Parser::parse("void f() { x = 1; else x = 2; }").unwrap_err();
+    assert_eq!(error.found(), Some(C1Token::KwElse));
+}
+
+#[test]
+fn loop_statements() {
+    let text = "void f() {\n while (i < 10) i = i + 1;\n do { i = i - 1; } while (i > 0);\n for (i = 0; i < 3; i = i + 1) printf(i);\n}";
+    let program = C1Parser::parse(text).unwrap();
+    let statements = &program.functions[0].body.statements;
+    assert!(matches!(&statements[0].kind, StatementKind::While { condition, .. } if matches!(condition.kind, ExprKind::Binary { op: BinaryOp::Less, .. })));
+    match &statements[1].kind {
+        StatementKind::DoWhile { body, condition } => {
+            assert!(matches!(body.kind, StatementKind::Block(_)));
+            assert!(matches!(condition.kind, ExprKind::Binary { op: BinaryOp::Greater, .. }));
+        }
+        other => panic!("expected do-while, got {:?}", other),
+    }
+    assert_eq!(statements[1].span.line, 3);
+    match &statements[2].kind {
+        StatementKind::For { init, step, body, .. } => {
+            assert_eq!(init.target.name, "i");
+            assert_eq!(step.target.name, "i");
+            assert!(matches!(body.kind, StatementKind::Printf(_)));
+        }
+        other => panic!("expected for, got {:?}", other),
+    }
+}
+
+#[test]
+fn do_while_needs_semicolon() {
+    let error = C1Parser::parse("void f() { do x = 1; while (x) }").unwrap_err();
+    assert_eq!(error.expected(), [C1Token::Semicolon]);
+    assert!(error.to_string().contains("after do-while statement"), "{}", error);
+}
+
+#[test]
+fn parameters_and_arguments() {
+    let text = "int add(int a, float b) { return a + b; }\nvoid main() { add(1, add(2, 3.5)); printf(add(x = 1, y)); }";
+    let program = C1Parser::parse(text).unwrap();
+    let parameters = &program.functions[0].parameters;
+    assert_eq!(parameters.len(), 2);
+    assert_eq!((parameters[0].ty, parameters[0].name.name.as_str()), (Type::Int, "a"));
+    assert_eq!((parameters[1].ty, parameters[1].name.name.as_str()), (Type::Float, "b"));
+    let main = &program.functions[1].body.statements;
+    match &main[0].kind {
+        StatementKind::Call(call) => {
+            assert_eq!(call.arguments.len(), 2);
+            assert!(matches!(&call.arguments[1].kind, ExprKind::Call(inner) if inner.arguments.len() == 2));
+        }
+        other => panic!("expected call, got {:?}", other),
+    }
+    match &main[1].kind {
+        StatementKind::Printf(value) => match &value.kind {
+            ExprKind::Call(call) => assert!(matches!(call.arguments[0].kind, ExprKind::Assign(_))),
+            other => panic!("expected call, got {:?}", other),
+        },
+        other => panic!("expected printf, got {:?}", other),
+    }
+}
+
+#[test]
+fn missing_comma_in_parameter_list() {
+    let error = C1Parser::parse("int f(int a float b) { }").unwrap_err();
+    assert_eq!(error.found(), Some(C1Token::KwFloat));
+    assert_eq!(error.expected(), [C1Token::Comma, C1Token::RightParenthesis]);
+}
+
+#[test]
+fn global_and_local_declarations() {
+    let text = "int counter;\nfloat scale = 1.5;\nvoid main() {\n int x;\n float y = x = 2;\n x = 3;\n}\nbool flag = true;";
+    let program = C1Parser::parse(text).unwrap();
+    let globals: Vec<(&str, Type, bool)> = program
+        .globals
+        .iter()
+        .map(|global| (global.name.name.as_str(), global.ty, global.value.is_some()))
+        .collect();
+    assert_eq!(globals, [("counter", Type::Int, false), ("scale", Type::Float, true), ("flag", Type::Boolean, true)]);
+    assert_eq!(program.functions.len(), 1);
+    let statements = &program.functions[0].body.statements;
+    match (&statements[0].kind, &statements[1].kind) {
+        (StatementKind::Declaration(x), StatementKind::Declaration(y)) => {
+            assert_eq!((x.ty, x.name.name.as_str(), x.value.is_none()), (Type::Int, "x", true));
+            assert_eq!(y.ty, Type::Float);
+            assert!(matches!(y.value.as_ref().unwrap().kind, ExprKind::Assign(_)));
+            assert_eq!(&text[y.span.start..y.span.end], "float y = x = 2");
+        }
+        other => panic!("expected declarations, got {:?}", other),
+    }
+    assert!(matches!(statements[2].kind, StatementKind::Assignment(_)));
+}
+
+#[test]
+fn invalid_token_after_global_name() {
+    let error = C1Parser::parse("int x + 1;").unwrap_err();
+    assert_eq!(error.found(), Some(C1Token::Plus));
+    assert_eq!(error.expected(), [C1Token::LeftParenthesis, C1Token::Assign, C1Token::Semicolon]);
+}
+
+#[test]
+fn local_declaration_is_not_mistaken_for_next_function() {
+    let (program, errors) = C1Parser::parse_with_recovery("void f() {\n int x = 1;\nint g() { }");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].span.line, 3);
+    assert_eq!(errors[0].expected(), [C1Token::RightBrace]);
+    assert_eq!(program.functions[0].body.statements.len(), 1);
+    assert_eq!(program.functions[1].name.name, "g");
+}
+
+#[test]
+fn errors_after_multi_line_comments_have_correct_lines() {
+    let error = C1Parser::parse("/**\n * Returns one.\n */\nint f() { // the body\n  return 1\n}").unwrap_err();
+    assert_eq!((error.span.line, error.span.column), (6, 1));
+}
+
+#[test]
+fn unterminated_comment_is_reported() {
+    let error = C1Parser::parse("void main() {\n  printf(1); /* a*b\n}").unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::Lexical(LexErrorKind::UnterminatedComment));
+    assert_eq!(error.found(), None);
+    assert_eq!(error.to_string(), "line 2, column 14: comment is not closed with '*/'");
+
+    let error = C1Parser::parse("int x; /*").unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::Lexical(LexErrorKind::UnterminatedComment));
+}
+
+#[test]
+fn lexical_errors_are_reported_with_their_cause() {
+    let error = C1Parser::parse("void main() { x = 1 $ 2; }").unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::Lexical(LexErrorKind::UnexpectedCharacter('$')));
+    assert_eq!(error.to_string(), "line 1, column 21: unexpected character '$'");
+
+    // the invalid tokens in skipped text are reported, too
+    let (_, errors) = C1Parser::parse_with_recovery("void main() {\n  x = 1.2.3 @ 4;\n  y = \"open;\n  z = 1e;\n}");
+    let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+    assert_eq!(
+        messages,
+        [
+            "line 2, column 7: malformed number 1.2.3",
+            "line 2, column 13: unexpected character '@'",
+            "line 3, column 7: string literal is not closed with '\"'",
+            "line 4, column 7: malformed number 1e",
+        ]
+    );
+
+    // they count towards the limit of errors
+    let text = format!("void main() {{ x = 1 {}; }}", "@ ".repeat(100));
+    let (_, errors) = C1Parser::parse_with_recovery(&text);
+    assert_eq!(errors.len(), C1Parser::MAX_ERRORS);
+    assert_eq!(errors.last().unwrap().kind, ParseErrorKind::TooManyErrors);
+}
@@ -0,0 +1,110 @@
+use cb_3::ast::{ExprKind, Identifier, Statement, StatementKind};
+use cb_3::resolve::{resolve, BindingKind, ResolveErrorKind};
+use cb_3::C1Parser;
+use std::fs;
+
+/// Collect the variable names that are read by printf statements, in order
+fn printed<'p>(statements: &'p [Statement], names: &mut Vec<&'p Identifier>) {
+    for statement in statements {
+        match &statement.kind {
+            StatementKind::Printf(value) => match &value.kind {
+                ExprKind::Variable(identifier) => names.push(identifier),
+                _ => unreachable!(),
+            },
+            StatementKind::Block(block) => printed(&block.statements, names),
+            StatementKind::If { then_branch, .. } => printed(std::slice::from_ref(&**then_branch), names),
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn example_variables_are_local_to_their_functions() {
+    let text = fs::read_to_string("tests/data/beispiel.c-1").unwrap();
+    let program = C1Parser::parse(text.as_str()).unwrap();
+    let (resolution, errors) = resolve(&program);
+    assert_eq!(errors, []);
+    let a: Vec<(usize, usize)> = resolution
+        .bindings()
+        .map(|(_, binding)| binding)
+        .filter(|binding| binding.name == "a")
+        .map(|binding| (binding.span.line, binding.slot))
+        .collect();
+    assert_eq!(a, [(11, 0), (22, 0)]);
+    assert!(resolution.bindings().all(|(_, binding)| binding.kind == BindingKind::Implicit));
+    assert_eq!(resolution.frame_size(&program.functions[0]), 4);
+}
+
+#[test]
+fn blocks_shadow_outer_variables() {
+    let text = "int x = 1;
+int y = x;
+void main(int p) {
+    printf(x);
+    x = 2;
+    int y = 3;
+    {
+        float x = 4;
+        printf(x);
+        printf(y);
+        z = 5;
+    }
+    printf(x);
+    printf(z);
+    if (true) { int p = 6; printf(p); }
+    printf(p);
+}";
+    let program = C1Parser::parse(text).unwrap();
+    let (resolution, errors) = resolve(&program);
+    assert_eq!(errors, []);
+    let mut names = Vec::new();
+    printed(&program.functions[0].body.statements, &mut names);
+    let bindings: Vec<(BindingKind, usize)> = names
+        .iter()
+        .map(|name| {
+            let binding = resolution.binding(resolution.lookup(name).unwrap());
+            (binding.kind, binding.span.line)
+        })
+        .collect();
+    assert_eq!(
+        bindings,
+        [
+            (BindingKind::Global, 1),
+            (BindingKind::Local, 8),
+            (BindingKind::Local, 6),
+            (BindingKind::Global, 1),
+            (BindingKind::Implicit, 11),
+            (BindingKind::Local, 15),
+            (BindingKind::Parameter, 3),
+        ]
+    );
+    assert_eq!(resolution.globals(), 2);
+    assert_eq!(resolution.frame_size(&program.functions[0]), 5);
+}
+
+#[test]
+fn undefined_and_duplicate_variables() {
+    let text = "int g = h;
+int g;
+void main(int a) {
+    int a;
+    printf(b);
+    b = 1;
+    { c = 1; int c; }
+    while (true) { d = 1; }
+    printf(d);
+}";
+    let program = C1Parser::parse(text).unwrap();
+    let (_, errors) = resolve(&program);
+    let found: Vec<(usize, usize, &ResolveErrorKind)> =
+        errors.iter().map(|error| (error.span.line, error.span.column, &error.kind)).collect();
+    // implicit variables belong to the function scope, so c can be declared again in the block and
+    // d is still visible after the loop
+    assert_eq!(found.len(), 4);
+    assert_eq!(found[0], (1, 9, &ResolveErrorKind::UndefinedVariable("h".to_string())));
+    assert!(matches!(found[1], (2, 5, ResolveErrorKind::DuplicateVariable { previous, .. }) if previous.line == 1));
+    assert!(matches!(found[2], (4, 9, ResolveErrorKind::DuplicateVariable { previous, .. }) if previous.line == 3));
+    assert_eq!(found[3], (5, 12, &ResolveErrorKind::UndefinedVariable("b".to_string())));
+    assert_eq!(errors[0].to_string(), "line 1, column 9: variable 'h' is not defined");
+    assert_eq!(errors[2].to_string(), "line 4, column 9: variable 'a' is already defined in line 3");
+}
@@ -0,0 +1,94 @@
+use cb_3::ast::Type;
+use cb_3::symbols::{check_functions, SymbolErrorKind};
+use cb_3::C1Parser;
+use std::fs;
+
+#[test]
+fn example_has_matching_calls() {
+    let text = fs::read_to_string("tests/data/beispiel.c-1").unwrap();
+    let program = C1Parser::parse(text.as_str()).unwrap();
+    assert_eq!(check_functions(&program), []);
+}
+
+#[test]
+fn arity_is_checked_in_every_position() {
+    let text = "int f(int a, int b) { return a; }
+void main() {
+    f(1);
+    x = f(1, 2) + f();
+    printf(f(f(1, 2, 3), 2));
+}";
+    let program = C1Parser::parse(text).unwrap();
+    let errors = check_functions(&program);
+    let found: Vec<(usize, usize, usize)> = errors
+        .iter()
+        .map(|error| match &error.kind {
+            SymbolErrorKind::ArityMismatch { expected, found, .. } => (error.span.line, *expected, *found),
+            _ => unreachable!(),
+        })
+        .collect();
+    assert_eq!(found, [(3, 2, 1), (4, 2, 0), (5, 2, 3)]);
+    assert_eq!(errors[1].span.column, 19);
+    assert_eq!(
+        errors[0].to_string(),
+        "line 3, column 5: function 'f' takes 2 arguments but 1 was supplied"
+    );
+}
+
+#[test]
+fn calls_to_unknown_functions_are_not_arity_errors() {
+    let program = C1Parser::parse("void main() { g(1); }").unwrap();
+    assert_eq!(
+        check_functions(&program)
+            .into_iter()
+            .map(|error| error.kind)
+            .collect::<Vec<_>>(),
+        [SymbolErrorKind::UndefinedFunction { name: "g".to_string(), suggestion: None }]
+    );
+}
+
+#[test]
+fn undefined_functions_suggest_similar_names() {
+    let text = "int blub() { return 1; }
+int blah(int a) { return a; }
+void main() {
+    x = blubb() + blha(1) + square(2);
+    foo();
+}";
+    let program = C1Parser::parse(text).unwrap();
+    let errors = check_functions(&program);
+    let suggestions: Vec<Option<&str>> = errors
+        .iter()
+        .map(|error| match &error.kind {
+            SymbolErrorKind::UndefinedFunction { suggestion, .. } => suggestion.as_deref(),
+            _ => unreachable!(),
+        })
+        .collect();
+    assert_eq!(suggestions, [Some("blub"), Some("blah"), None, None]);
+    assert_eq!((errors[1].span.line, errors[1].span.column), (4, 19));
+    assert_eq!(
+        errors[0].to_string(),
+        "line 4, column 9: function 'blubb' is not defined, did you mean 'blub'?"
+    );
+}
+
+#[test]
+fn duplicate_definitions_and_main_are_checked() {
+    let text = "int blub() { return 1; }
+float main(int argc) { return 0.0; }
+int blub() { return 2; }";
+    let program = C1Parser::parse(text).unwrap();
+    let errors = check_functions(&program);
+    let found: Vec<(usize, usize, &SymbolErrorKind)> =
+        errors.iter().map(|error| (error.span.line, error.span.column, &error.kind)).collect();
+    assert_eq!(found.len(), 3);
+    assert!(matches!(found[0], (3, 5, SymbolErrorKind::DuplicateFunction { previous, .. }) if previous.line == 1));
+    assert_eq!(found[1], (2, 1, &SymbolErrorKind::MainReturnType(Type::Float)));
+    assert_eq!(found[2], (2, 12, &SymbolErrorKind::MainParameters(1)));
+    assert_eq!(errors[0].to_string(), "line 3, column 5: function 'blub' is already defined in line 1");
+
+    let program = C1Parser::parse("int blub() { return 1; }").unwrap();
+    let errors = check_functions(&program);
+    assert_eq!(errors[0].kind, SymbolErrorKind::MissingMain);
+    assert_eq!(errors[0].to_string(), "the program does not define a 'main' function");
+}
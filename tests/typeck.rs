@@ -0,0 +1,163 @@
+use cb_3::ast::{BinaryOp, StatementKind, Type};
+use cb_3::typeck::{check_types, TypeError, TypeErrorKind};
+use cb_3::C1Parser;
+use std::fs;
+
+fn type_errors(text: &str) -> Vec<TypeError> {
+    let program = C1Parser::parse(text).unwrap();
+    check_types(&program).unwrap_err()
+}
+
+#[test]
+fn example_is_well_typed() {
+    let text = fs::read_to_string("tests/data/beispiel.c-1").unwrap();
+    let program = C1Parser::parse(text.as_str()).unwrap();
+    assert!(check_types(&program).is_ok());
+}
+
+#[test]
+fn int_is_promoted_to_float() {
+    let text = "float g = 1;
+float half(float x) { return x / 2; }
+int main() {
+    f = half(3) + 1;
+    g = 2;
+    b = 1 < 2.5;
+    printf(f);
+    return 0;
+}";
+    let program = C1Parser::parse(text).unwrap();
+    let types = check_types(&program).unwrap();
+    let values: Vec<Type> = program.functions[1].body.statements[..3]
+        .iter()
+        .map(|statement| match &statement.kind {
+            StatementKind::Assignment(assignment) => types.expr(&assignment.value),
+            _ => unreachable!(),
+        })
+        .collect();
+    assert_eq!(values, [Type::Float, Type::Int, Type::Boolean]);
+
+    let errors = type_errors("void main() { i = 1; i = 2.5; int j = 1.0; }");
+    let found: Vec<TypeErrorKind> = errors.into_iter().map(|error| error.kind).collect();
+    assert_eq!(
+        found,
+        [
+            TypeErrorKind::Mismatch { expected: Type::Int, found: Type::Float },
+            TypeErrorKind::Mismatch { expected: Type::Int, found: Type::Float },
+        ]
+    );
+}
+
+#[test]
+fn implicit_variables_keep_the_type_of_their_first_value() {
+    // the interpreter would store the int unchanged, so it is not promoted
+    let errors = type_errors("void main() {\n    z = 1.5;\n    z = 2;\n    printf(z);\n}");
+    let found: Vec<(usize, TypeErrorKind)> = errors.into_iter().map(|error| (error.span.line, error.kind)).collect();
+    assert_eq!(found, [(3, TypeErrorKind::Mismatch { expected: Type::Float, found: Type::Int })]);
+    let program = C1Parser::parse("float f(float x) { return x; }\nvoid main() { z = 1.5; z = f(2); z = z * 2; }").unwrap();
+    assert!(check_types(&program).is_ok());
+}
+
+#[test]
+fn undefined_names_leave_no_expression_untyped() {
+    // foo and y are reported by the other checks, but check_types must not succeed without their types
+    let errors = type_errors("void main() {\n    printf(foo());\n    x = y;\n    foo();\n}");
+    let found: Vec<(usize, TypeErrorKind)> = errors.into_iter().map(|error| (error.span.line, error.kind)).collect();
+    assert_eq!(found, [(2, TypeErrorKind::UnknownType), (3, TypeErrorKind::UnknownType)]);
+}
+
+#[test]
+fn returns_are_checked_against_the_function_type() {
+    let text = "bool f() { return 1; }
+void g() { return 1 + true; }
+int h() { return; }
+float k() { return 1; }
+int m() { return 2.0; }";
+    let errors = type_errors(text);
+    let found: Vec<(usize, TypeErrorKind)> = errors.into_iter().map(|error| (error.span.line, error.kind)).collect();
+    assert_eq!(
+        found,
+        [
+            (1, TypeErrorKind::Mismatch { expected: Type::Boolean, found: Type::Int }),
+            (2, TypeErrorKind::InvalidOperands { op: BinaryOp::Add, lhs: Type::Int, rhs: Type::Boolean }),
+            (5, TypeErrorKind::Mismatch { expected: Type::Int, found: Type::Float }),
+        ]
+    );
+}
+
+#[test]
+fn conditions_and_logical_operators_need_bools() {
+    let text = "void main() {
+    if (1 + 2) { }
+    while (true || 1.5) { }
+    for (i = 0; i; i = i + 1) { }
+    b = (1 == 1) && true;
+    c = true == false;
+}";
+    let errors = type_errors(text);
+    assert_eq!(errors.len(), 3);
+    assert_eq!(errors[0].kind, TypeErrorKind::NonBooleanCondition(Type::Int));
+    assert_eq!((errors[0].span.line, errors[0].span.column), (2, 9));
+    assert_eq!(
+        errors[1].kind,
+        TypeErrorKind::InvalidOperands { op: BinaryOp::Or, lhs: Type::Boolean, rhs: Type::Float }
+    );
+    assert_eq!((errors[1].span.line, errors[1].span.column), (3, 12));
+    assert_eq!(errors[1].to_string(), "line 3, column 12: operator '||' cannot be applied to 'bool' and 'float'");
+    assert_eq!(errors[2].kind, TypeErrorKind::NonBooleanCondition(Type::Int));
+    assert_eq!((errors[2].span.line, errors[2].span.column), (4, 17));
+}
+
+#[test]
+fn void_values_cannot_be_used() {
+    let text = "void f() { }
+void main(void p) {
+    printf(f());
+    void v;
+    x = f();
+    y = -true;
+}";
+    let errors = type_errors(text);
+    let found: Vec<(usize, usize, TypeErrorKind)> =
+        errors.into_iter().map(|error| (error.span.line, error.span.column, error.kind)).collect();
+    assert_eq!(
+        found,
+        [
+            (2, 11, TypeErrorKind::VoidVariable("p".to_string())),
+            (3, 12, TypeErrorKind::PrintVoid),
+            (4, 5, TypeErrorKind::VoidVariable("v".to_string())),
+            (5, 9, TypeErrorKind::VoidVariable("x".to_string())),
+            (6, 9, TypeErrorKind::InvalidOperand { op: cb_3::ast::UnaryOp::Neg, operand: Type::Boolean }),
+        ]
+    );
+}
+
+#[test]
+fn errors_do_not_cascade() {
+    // y is reported by the resolver
+    let errors = type_errors("void main() { x = y + 1; z = x * 2; printf(z && true); w = 1 + true; printf(w); void v; v = 1; }");
+    let found: Vec<TypeErrorKind> = errors.into_iter().map(|error| error.kind).collect();
+    assert_eq!(
+        found,
+        [
+            TypeErrorKind::InvalidOperands { op: BinaryOp::Add, lhs: Type::Int, rhs: Type::Boolean },
+            TypeErrorKind::VoidVariable("v".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn variables_are_typed_per_scope() {
+    let text = "float x = 1.5;
+void main() {
+    { int x = 1; x = 2; }
+    { bool x = true; x = false; }
+    x = 2.5;
+    if (true) { y = true; }
+    y = false;
+}";
+    let program = C1Parser::parse(text).unwrap();
+    assert!(check_types(&program).is_ok());
+    let errors = type_errors("void main() { { bool x = true; } { x = 1; } { x = true; } }");
+    assert_eq!(errors[0].kind, TypeErrorKind::Mismatch { expected: Type::Int, found: Type::Boolean });
+}
@@ -0,0 +1,107 @@
+use cb_3::ast::Type;
+use cb_3::bytecode::compile;
+use cb_3::interpreter::{self, RuntimeErrorKind, Value};
+use cb_3::{vm, C1Parser};
+use std::fs;
+
+/// Run the program with the virtual machine and the interpreter, which have to agree, and return
+/// the result of the virtual machine together with its output
+fn run_both(source: &str) -> (Result<Value, interpreter::RuntimeError>, String) {
+    let mut out = Vec::new();
+    let result = vm::run(source, &mut out);
+    let output = String::from_utf8(out).unwrap();
+    let mut expected_out = Vec::new();
+    let expected = interpreter::run(source, &mut expected_out);
+    assert_eq!((&result, &output), (&expected, &String::from_utf8(expected_out).unwrap()));
+    (result, output)
+}
+
+#[test]
+fn run_example() {
+    let text = fs::read_to_string("tests/data/beispiel.c-1").unwrap();
+    let expected = fs::read_to_string("tests/data/beispiel.out").unwrap();
+    assert_eq!(run_both(&text), (Ok(Value::Void), expected));
+}
+
+#[test]
+fn loops_recursion_and_globals() {
+    let source = "
+int counter;
+float half = 1 / 2.0;
+int fib(int n) {
+    counter = counter + 1;
+    if (n < 2) return n;
+    return fib(n - 1) + fib(n - 2);
+}
+float main() {
+    for (i = 0; i < 5; i = i + 1) printf(fib(i));
+    n = 3;
+    while (n > 0) n = n - 1;
+    do { n = n + 10; } while (n < 25);
+    printf(n);
+    printf(counter);
+    float f = counter;
+    printf((f > 10) && ((x = 2) == 2));
+    printf(true || (1 / 0 == 1));
+    return -n + f * half;
+}";
+    let (result, output) = run_both(source);
+    assert_eq!(result, Ok(Value::Float(-20.5)));
+    assert_eq!(output, "0\n1\n1\n2\n3\n30\n19\ntrue\ntrue\n");
+}
+
+#[test]
+fn runtime_errors_are_reported_at_the_same_location() {
+    let sources = [
+        "void main() {\n    printf(1);\n    printf(1 / (2 - 2));\n}",
+        "void main() {\n    int x;\n    if (true) { x = 1; }\n    { int x; printf(x); }\n}",
+        "int f(int n) { return f(n + 1); }\nvoid main() { f(0); }",
+        "int f() { if (false) return 1; }\nvoid main() {\n    printf(f());\n}",
+        "void main() {\n    b = 1 < 2;\n    printf(b && 3);\n}",
+        "void main() {\n    x = 1;\n    while (x) { }\n}",
+        "float g = true;\nvoid main() { }",
+        "void f(float x) { }\nvoid main() {\n    f(1, 2);\n}",
+    ];
+    let errors: Vec<(RuntimeErrorKind, Option<usize>)> =
+        sources.iter().map(|source| run_both(source).0.map_err(|error| (error.kind.clone(), error.line())).unwrap_err()).collect();
+    assert_eq!(errors[0], (RuntimeErrorKind::DivisionByZero, Some(3)));
+    assert_eq!(errors[1], (RuntimeErrorKind::UninitializedVariable("x".to_string()), Some(4)));
+    assert_eq!(errors[2], (RuntimeErrorKind::StackOverflow, Some(1)));
+    assert_eq!(errors[3], (RuntimeErrorKind::MissingReturn("f".to_string()), Some(1)));
+    assert!(matches!(errors[4], (RuntimeErrorKind::InvalidOperands { .. }, Some(3))));
+    assert!(matches!(errors[5], (RuntimeErrorKind::TypeMismatch { .. }, Some(3))));
+    assert!(matches!(errors[6], (RuntimeErrorKind::TypeMismatch { .. }, Some(1))));
+    assert!(matches!(errors[7], (RuntimeErrorKind::ArityMismatch { .. }, Some(3))));
+}
+
+#[test]
+fn instructions_map_to_lines() {
+    let program = C1Parser::parse("void main() {\n    x = 1;\n    printf(x + 2);\n}").unwrap();
+    let module = compile(&program);
+    let main = &module.functions[0];
+    let lines: Vec<usize> = (0..main.code.len()).map(|offset| main.span_at(offset).line).collect();
+    assert_eq!(lines, [2, 2, 3, 3, 3, 3, 1]);
+    assert_eq!(module.constants, [Value::Int(1), Value::Int(2)]);
+}
+
+#[test]
+fn unresolved_names_fail_when_they_are_reached() {
+    let (result, output) = run_both("void main() { printf(1); if (false) foo(); }");
+    assert_eq!((result, output.as_str()), (Ok(Value::Void), "1\n"));
+    let (result, output) = run_both("void main() {\n    printf(1);\n    foo(printf2(2));\n    printf(y);\n}");
+    let error = result.unwrap_err();
+    assert_eq!(error.to_string(), "line 3, column 5: call to undefined function 'foo'");
+    assert_eq!(output, "1\n");
+    let (result, output) = run_both("void main() {\n    x = 2;\n    printf(x);\n    printf(x + y);\n}");
+    assert_eq!(result.unwrap_err().to_string(), "line 4, column 16: variable 'y' is not defined");
+    assert_eq!(output, "2\n");
+    let (result, _) = run_both("void main(int argc) { }");
+    assert_eq!(result.unwrap_err().to_string(), "line 1, column 6: function 'main' takes 1 argument but 0 were supplied");
+}
+
+#[test]
+fn arguments_are_converted_after_all_are_evaluated() {
+    let (result, output) = run_both("int g() { printf(5); return 1; }\nvoid f(int a, int b) { }\nvoid main() { f(true, g()); }");
+    assert!(matches!(result.unwrap_err().kind, RuntimeErrorKind::TypeMismatch { expected: Type::Int, found: Type::Boolean }));
+    assert_eq!(output, "5\n");
+}
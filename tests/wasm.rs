@@ -0,0 +1,127 @@
+use cb_3::wasm::{decode, generate, CodeProblem, DecodeErrorKind, Export, FunctionType, Global, Instruction, Module, ValueType};
+use cb_3::C1Parser;
+use std::fs;
+
+fn module(source: &str) -> Module {
+    let program = C1Parser::parse(source).unwrap();
+    decode(&generate(&program).unwrap_or_else(|errors| panic!("{:?}", errors))).unwrap()
+}
+
+fn listing(code: &[Instruction]) -> Vec<String> {
+    code.iter().map(|instruction| instruction.to_string()).collect()
+}
+
+#[test]
+fn example_matches_golden_file() {
+    let text = fs::read_to_string("tests/data/beispiel.c-1").unwrap();
+    let bytes = generate(&C1Parser::parse(&text).unwrap()).unwrap();
+    assert_eq!(bytes, fs::read("tests/data/beispiel.wasm").unwrap());
+    let module = decode(&bytes).unwrap();
+    let imports: Vec<(&str, &str)> = module.imports.iter().map(|import| (import.module.as_str(), import.name.as_str())).collect();
+    assert_eq!(imports, [("c1", "print_int"), ("c1", "print_float"), ("c1", "print_bool"), ("c1", "division_by_zero")]);
+    // blub, blah, main, the division and the initialization
+    assert_eq!(module.bodies.len(), 5);
+    assert_eq!(module.exports, [Export { name: "main".to_string(), function: 6 }]);
+    assert_eq!(module.start, Some(8));
+}
+
+#[test]
+fn module_layout() {
+    let module = module(
+        "float scale = 2;\nint add(int a, float b) { sum = a + b; int n = 1; return n; }\nint main() {\n    i = 0;\n    while (i < 3) i = i + 1;\n    return i / 2;\n}",
+    );
+    let add = module.function_type(4).unwrap();
+    assert_eq!(*add, FunctionType { parameters: vec![ValueType::I32, ValueType::F64], results: vec![ValueType::I32] });
+    assert_eq!(module.function_type(5).unwrap().parameters, []);
+    assert_eq!(module.function_type(7).unwrap(), &FunctionType { parameters: Vec::new(), results: Vec::new() });
+    assert_eq!(module.globals, [Global { ty: ValueType::F64, init: Instruction::F64Const(0.0) }]);
+    // the implicit variable `sum` is a float
+    assert_eq!(module.bodies[0].locals, [ValueType::F64, ValueType::I32]);
+    assert_eq!(module.bodies[1].locals, [ValueType::I32]);
+    assert_eq!(
+        listing(&module.bodies[1].code),
+        [
+            "i32.const 0",
+            "local.set 0",
+            "block",
+            "loop",
+            "local.get 0",
+            "i32.const 3",
+            "i32.lt_s",
+            "i32.eqz",
+            "br_if 1",
+            "local.get 0",
+            "i32.const 1",
+            "i32.add",
+            "local.set 0",
+            "br 0",
+            "end",
+            "end",
+            "local.get 0",
+            "i32.const 2",
+            "i32.const 6",
+            "i32.const 12",
+            "call 6",
+            "return",
+            "unreachable",
+            "end",
+        ]
+    );
+    assert_eq!(listing(&module.bodies[3].code), ["i32.const 2", "f64.convert_i32_s", "global.set 0", "end"]);
+}
+
+#[test]
+fn logical_operators_short_circuit() {
+    let module = module("bool either(bool a, bool b) { return (a && b) || (a != b); }\nvoid main() { printf(either(true, false)); }");
+    assert_eq!(
+        listing(&module.bodies[0].code),
+        [
+            "local.get 0",
+            "if (result i32)",
+            "local.get 1",
+            "else",
+            "i32.const 0",
+            "end",
+            "if (result i32)",
+            "i32.const 1",
+            "else",
+            "local.get 0",
+            "local.get 1",
+            "i32.ne",
+            "end",
+            "return",
+            "unreachable",
+            "end",
+        ]
+    );
+    assert_eq!(listing(&module.bodies[1].code), ["i32.const 1", "i32.const 0", "call 4", "call 2", "end"]);
+}
+
+#[test]
+fn malformed_modules_are_rejected() {
+    let bytes = fs::read("tests/data/beispiel.wasm").unwrap();
+    // a prefix that ends between the sections before the functions is a valid module
+    for length in 0..bytes.len() {
+        if let Ok(module) = decode(&bytes[..length]) {
+            assert!(module.functions.is_empty() && length >= 8, "prefix of {} bytes was decoded", length);
+        }
+    }
+    assert_eq!(decode(b"\0ASM\x01\0\0\0").unwrap_err().kind, DecodeErrorKind::BadMagic);
+
+    // the type section again after the code section
+    let mut repeated = bytes.clone();
+    repeated.extend_from_slice(&[1, 1, 0]);
+    assert_eq!(decode(&repeated).unwrap_err().kind, DecodeErrorKind::MisplacedSection(1));
+
+    // the first instruction of blub is `i32.const 23`, make it `call 23`
+    let mut call = bytes.clone();
+    let position = bytes.windows(2).position(|window| window == [0x41, 23]).unwrap();
+    call[position] = 0x10;
+    let error = decode(&call).unwrap_err();
+    assert_eq!(error.kind, DecodeErrorKind::InvalidCode { function: 4, instruction: 0, problem: CodeProblem::FunctionOutOfRange(23) });
+    assert_eq!(error.to_string(), format!("byte {}: instruction 0 of function 4: function 23 does not exist", position - 3));
+
+    // an overlong integer
+    let error = decode(b"\0asm\x01\0\0\0\x01\x80\x80\x80\x80\x80\x01").unwrap_err();
+    assert_eq!((error.kind, error.offset), (DecodeErrorKind::InvalidInteger, 9));
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use cb_3::x86_64::generate;
+use cb_3::C1Parser;
+use common::assert_like_interpreter;
+use std::fs;
+use std::process::{Command, Output};
+
+/// Translate the program, assemble and link it with `cc` and run it
+fn compile_and_run(name: &str, source: &str) -> Output {
+    let program = C1Parser::parse(source).unwrap();
+    let assembly = generate(&program).unwrap_or_else(|errors| panic!("{:?}", errors));
+    common::build_and_run("x86_64", name, |binary| {
+        let file = binary.with_extension("s");
+        fs::write(&file, assembly).unwrap();
+        let status = Command::new("cc").arg("-o").arg(binary).arg(&file).status().expect("cc is installed");
+        assert!(status.success(), "cc failed for {}", file.display());
+    })
+}
+
+#[test]
+fn run_example() {
+    common::run_example(compile_and_run);
+}
+
+#[test]
+fn arithmetic_is_like_the_interpreter() {
+    common::arithmetic_is_like_the_interpreter(compile_and_run);
+}
+
+#[test]
+fn implicit_variables_are_like_the_interpreter() {
+    common::implicit_variables_are_like_the_interpreter(compile_and_run);
+}
+
+#[test]
+fn division_by_zero_aborts() {
+    common::division_by_zero_aborts(compile_and_run);
+}
+
+#[test]
+fn calls_pass_many_and_mixed_arguments() {
+    let source = "
+float sum(int a, float b, int c, int d, int e, int f, int g, bool h, float i, float j, float k, float l, float m, float n, float o, float p) {
+    if (h) return a + b + c + d + e + f + g + i + j + k + l + m + n + o + p;
+    return 0;
+}
+int fib(int n) {
+    if (n < 2) return n;
+    return fib(n - 1) + fib(n - 2);
+}
+int counter;
+void count() { counter = counter + 1; }
+int main() {
+    printf(sum(1, 2, 3, 4, 5, 6, 7, true, 9, 10, 11, 12, 13, 14, 15, 16));
+    printf(1 + sum(1, 2, 3, 4, 5, 6, 7, false, 9, 10, 11, 12, 13, 14, 15, 16) * fib(10));
+    for (i = 0; i < 3; i = i + 1) count();
+    do { counter = counter * 2; } while (counter < 20);
+    printf(counter);
+    return fib(12);
+}";
+    let output = assert_like_interpreter(compile_and_run, "calls", source);
+    assert_eq!(output.status.code(), Some(144));
+}